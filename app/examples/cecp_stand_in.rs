@@ -0,0 +1,256 @@
+//! A scripted engine speaking the Chess Engine Communication Protocol, for trying the XBoard
+//! support without installing a real engine. The tests of the adapter play against it.
+//!
+//! It negotiates a few features, follows the game, and always plays its first legal move
+//! (or the first capture, when the "Greedy" option is set), in UCI notation or in SAN as set by
+//! the "Notation" option. In analysis mode, it reports this move as its principal variation.
+//! The commands it receives are echoed on the standard error.
+//!
+//! Started with `--protover 1`, it plays an engine of the first version of the protocol : it
+//! sends no feature, takes the moves without `usermove`, and only knows the edit mode to set
+//! up a position.
+//!
+//! Build with `cargo build -p chess_against_engine_rust --example cecp_stand_in`, then register
+//! `target/debug/examples/cecp_stand_in` as an XBoard engine in the engines manager.
+use std::io::{self, BufRead, Write};
+
+use owlchess::{Board, Cell, Color, Coord, Move, MoveChain, movegen::legal};
+
+const FEATURES: &str = concat!(
+    "feature myname=\"Stand-in engine\" usermove=1 setboard=1 sigint=0 ",
+    "option=\"Greedy -check 0\" option=\"Thinking lines -spin 1 0 5\" ",
+    "option=\"Notation -combo *UCI /// SAN\" done=1"
+);
+
+/// The board being set up in the edit mode.
+struct Edit {
+    cells: [Cell; 64],
+    /// The color of the pieces placed.
+    color: Color,
+    side: Color,
+}
+
+impl Edit {
+    /// The position set up, with the castling rights of the kings and rooks left on their
+    /// squares.
+    fn board(&self) -> Result<Board, String> {
+        let mut placement = String::new();
+        for rank in 0..8 {
+            let mut empty = 0;
+            for file in 0..8 {
+                let cell = self.cells[8 * rank + file];
+                if !cell.is_occupied() {
+                    empty += 1;
+                    continue;
+                }
+                if empty > 0 {
+                    placement.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                placement.push(cell.as_char());
+            }
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if rank < 7 {
+                placement.push('/');
+            }
+        }
+        let on = |square: &str, piece: char| {
+            let coord = square.parse::<Coord>();
+            coord.is_ok_and(|coord| self.cells[coord.index()].as_char() == piece)
+        };
+        let mut castling: String = [
+            ("e1", 'K', "h1", 'R', 'K'),
+            ("e1", 'K', "a1", 'R', 'Q'),
+            ("e8", 'k', "h8", 'r', 'k'),
+            ("e8", 'k', "a8", 'r', 'q'),
+        ]
+        .into_iter()
+        .filter(|(king, king_piece, rook, rook_piece, _)| {
+            on(king, *king_piece) && on(rook, *rook_piece)
+        })
+        .map(|(.., right)| right)
+        .collect();
+        if castling.is_empty() {
+            castling.push('-');
+        }
+        let fen = format!("{placement} {} {castling} - 0 1", self.side.as_char());
+        Board::from_fen(&fen).map_err(|err| err.to_string())
+    }
+
+    fn command(&mut self, command: &str) {
+        match command {
+            "#" => self.cells = [Cell::EMPTY; 64],
+            "c" => self.color = self.color.inv(),
+            _ => {
+                let mut chars = command.chars();
+                let (Some(piece), Ok(square)) = (chars.next(), chars.as_str().parse::<Coord>())
+                else {
+                    return;
+                };
+                let piece = match self.color {
+                    Color::White => piece.to_ascii_uppercase(),
+                    Color::Black => piece.to_ascii_lowercase(),
+                };
+                self.cells[square.index()] = if piece.eq_ignore_ascii_case(&'x') {
+                    Cell::EMPTY
+                } else {
+                    Cell::from_char(piece).unwrap_or(Cell::EMPTY)
+                };
+            }
+        }
+    }
+}
+
+struct Engine {
+    game: MoveChain,
+    /// In force mode, the engine only follows the moves it is given.
+    force: bool,
+    analyzing: bool,
+    greedy: bool,
+    thinking_lines: u32,
+    san: bool,
+    edit: Option<Edit>,
+}
+
+impl Engine {
+    fn choose_move(&self) -> Option<Move> {
+        let position = self.game.last();
+        let moves = legal::gen_all(position);
+        let capture = moves
+            .iter()
+            .find(|legal_move| position.get(legal_move.dst()).is_occupied());
+        match capture {
+            Some(capture) if self.greedy => Some(*capture),
+            _ => moves.iter().next().copied(),
+        }
+    }
+
+    /// Plays a move for the side to move, and answers with it.
+    fn play(&mut self, output: &mut impl Write) -> io::Result<()> {
+        for depth in 1..=self.thinking_lines {
+            writeln!(output, "{depth} 0 0 {depth} thinking")?;
+        }
+        let Some(chosen) = self.choose_move() else {
+            return writeln!(output, "resign");
+        };
+        let notation = match chosen.san(self.game.last()) {
+            Ok(san) if self.san => san.to_string(),
+            _ => chosen.to_string(),
+        };
+        self.game.push(chosen).ok();
+        writeln!(output, "move {notation}")
+    }
+
+    /// Follows the move of the opponent, and answers it unless in force mode.
+    fn user_move(&mut self, played: &str, output: &mut impl Write) -> io::Result<()> {
+        match Move::from_uci_legal(played, self.game.last()) {
+            Ok(user_move) => {
+                self.game.push(user_move).ok();
+                if self.analyzing {
+                    self.analyze(output)
+                } else if !self.force {
+                    self.play(output)
+                } else {
+                    Ok(())
+                }
+            }
+            Err(_) => writeln!(output, "Illegal move: {played}"),
+        }
+    }
+
+    /// Reports the analysis of the position, as a thinking line.
+    fn analyze(&self, output: &mut impl Write) -> io::Result<()> {
+        match self.choose_move() {
+            Some(chosen) => writeln!(output, "1 0 0 1 {chosen}"),
+            None => Ok(()),
+        }
+    }
+
+    fn set_option(&mut self, option: &str) {
+        match option.split_once('=') {
+            Some(("Greedy", value)) => self.greedy = value == "1",
+            Some(("Thinking lines", value)) => {
+                self.thinking_lines = value.parse().unwrap_or(self.thinking_lines)
+            }
+            Some(("Notation", value)) => self.san = value == "SAN",
+            _ => {}
+        }
+    }
+}
+
+fn main() -> io::Result<()> {
+    let first_version = std::env::args().skip(1).collect::<Vec<_>>() == ["--protover", "1"];
+    let mut output = io::stdout().lock();
+    let mut engine = Engine {
+        game: MoveChain::new_initial(),
+        force: true,
+        analyzing: false,
+        greedy: false,
+        thinking_lines: 1,
+        san: false,
+        edit: None,
+    };
+
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let line = line.trim();
+        eprintln!("< {line}");
+        if let Some(edit) = engine.edit.as_mut() {
+            if line == "." {
+                match edit.board() {
+                    Ok(board) => engine.game = MoveChain::new(board),
+                    Err(err) => writeln!(output, "tellusererror Illegal position: {err}")?,
+                }
+                engine.edit = None;
+            } else {
+                edit.command(line);
+            }
+            continue;
+        }
+        let (command, arguments) = line.split_once(' ').unwrap_or((line, ""));
+        match command {
+            "protover" if !first_version => writeln!(output, "{FEATURES}")?,
+            "new" => {
+                engine.game = MoveChain::new_initial();
+                engine.force = false;
+            }
+            "force" => engine.force = true,
+            "setboard" if !first_version => match Board::from_fen(arguments) {
+                Ok(board) => engine.game = MoveChain::new(board),
+                Err(err) => writeln!(output, "tellusererror Illegal position: {err}")?,
+            },
+            "edit" => {
+                let position = engine.game.last();
+                engine.edit = Some(Edit {
+                    cells: Coord::iter()
+                        .map(|square| position.get(square))
+                        .collect::<Vec<_>>()
+                        .try_into()
+                        .unwrap_or([Cell::EMPTY; 64]),
+                    color: Color::White,
+                    side: position.side(),
+                });
+            }
+            "usermove" if !first_version => engine.user_move(arguments, &mut output)?,
+            "go" => {
+                engine.force = false;
+                engine.play(&mut output)?;
+            }
+            "analyze" => {
+                engine.analyzing = true;
+                engine.analyze(&mut output)?;
+            }
+            "exit" => engine.analyzing = false,
+            "option" => engine.set_option(arguments),
+            "quit" => break,
+            _ if first_version && Move::from_uci_legal(line, engine.game.last()).is_ok() => {
+                engine.user_move(line, &mut output)?
+            }
+            _ => {}
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,227 @@
+//! What the engine reports while it searches : evaluation and principal variation.
+use std::fmt;
+
+use owlchess::Color;
+
+use crate::{
+    engine::{EngineCommand, EngineOption, OptionKind},
+    game::Game,
+};
+
+/// The option setting how many lines the engine analyses.
+pub const MULTI_PV_OPTION: &str = "MultiPV";
+
+/// The most lines shown in the analysis, whatever the engine allows.
+const MAX_LINES: i64 = 5;
+
+/// How many lines the engine can analyse at once.
+pub fn max_lines(options: &[EngineOption]) -> usize {
+    options
+        .iter()
+        .find_map(|option| match option.kind {
+            OptionKind::Spin { max, .. } if option.name.eq_ignore_ascii_case(MULTI_PV_OPTION) => {
+                Some(max.clamp(1, MAX_LINES) as usize)
+            }
+            _ => None,
+        })
+        .unwrap_or(1)
+}
+
+/// The command setting the number of lines to analyse, if the engine can analyse several lines.
+pub fn lines_command(options: &[EngineOption], lines: usize) -> Option<EngineCommand> {
+    let option = options
+        .iter()
+        .find(|option| option.name.eq_ignore_ascii_case(MULTI_PV_OPTION))?;
+    let value = lines.to_string();
+    option
+        .kind
+        .accepts(&value)
+        .then(|| EngineCommand::SetOption {
+            name: option.name.clone(),
+            value: Some(value),
+        })
+}
+
+/// The evaluation of a position, from the point of view of the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    Centipawns(i32),
+    /// Mate in the given number of moves, negative when the side to move gets mated.
+    Mate(i32),
+}
+
+impl Score {
+    /// The same evaluation, from the point of view of White.
+    pub fn for_white(self, side_to_move: Color) -> Score {
+        match (side_to_move, self) {
+            (Color::White, _) => self,
+            (Color::Black, Score::Centipawns(centipawns)) => Score::Centipawns(-centipawns),
+            (Color::Black, Score::Mate(moves)) => Score::Mate(-moves),
+        }
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Score::Centipawns(centipawns) => {
+                write!(f, "{:+.2}", f64::from(*centipawns) / 100.0)
+            }
+            Score::Mate(moves) => write!(f, "#{moves}"),
+        }
+    }
+}
+
+/// The state of the search, as reported by the engine.
+/// The engines often report only some of the fields at once.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnalysisInfo {
+    /// The rank of the line, starting from 1, when the engine analyses several lines.
+    pub multipv: Option<usize>,
+    pub depth: Option<u32>,
+    pub score: Option<Score>,
+    pub nodes: Option<u64>,
+    /// Nodes searched per second.
+    pub nps: Option<u64>,
+    /// The principal variation, in UCI notation.
+    pub pv: Vec<String>,
+}
+
+impl AnalysisInfo {
+    pub fn is_empty(&self) -> bool {
+        *self == AnalysisInfo::default()
+    }
+
+    /// Updates the fields given by a newer report, keeping the others.
+    pub fn merge(&mut self, newer: AnalysisInfo) {
+        self.multipv = newer.multipv.or(self.multipv);
+        self.depth = newer.depth.or(self.depth);
+        self.score = newer.score.or(self.score);
+        self.nodes = newer.nodes.or(self.nodes);
+        self.nps = newer.nps.or(self.nps);
+        if !newer.pv.is_empty() {
+            self.pv = newer.pv;
+        }
+    }
+}
+
+/// The moves (in UCI notation) played from the current position of the game, in SAN with move
+/// numbers, as in `12... Nc6 13. Bb5 a6`. The line stops at the first illegal move.
+pub fn san_line(game: &Game, moves: &[String]) -> String {
+    let mut line_game = game.continuation(game.moves().len());
+    line_game.play_line(0, moves);
+    let mut line = String::new();
+    for (index, (board, played)) in line_game.walk().enumerate() {
+        let san = played.san(board);
+        let number = board.raw().move_number;
+        let label = match (board.side(), index) {
+            (Color::White, _) => format!("{number}. {san}"),
+            (Color::Black, 0) => format!("{number}... {san}"),
+            (Color::Black, _) => san,
+        };
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&label);
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use owlchess::Board;
+
+    use super::*;
+    use crate::game::chess960::parse_fen;
+
+    #[test]
+    fn turns_scores_to_white_point_of_view() {
+        assert_eq!(
+            Score::Centipawns(40).for_white(Color::White),
+            Score::Centipawns(40)
+        );
+        assert_eq!(
+            Score::Centipawns(40).for_white(Color::Black),
+            Score::Centipawns(-40)
+        );
+        assert_eq!(Score::Mate(-2).for_white(Color::Black), Score::Mate(2));
+        assert_eq!(Score::Centipawns(-135).to_string(), "-1.35");
+        assert_eq!(Score::Centipawns(0).to_string(), "+0.00");
+        assert_eq!(Score::Mate(3).to_string(), "#3");
+    }
+
+    #[test]
+    fn merges_partial_reports() {
+        let mut info = AnalysisInfo {
+            depth: Some(10),
+            score: Some(Score::Centipawns(20)),
+            pv: vec![String::from("e2e4")],
+            ..Default::default()
+        };
+        info.merge(AnalysisInfo {
+            nodes: Some(5000),
+            ..Default::default()
+        });
+        assert_eq!(info.depth, Some(10));
+        assert_eq!(info.nodes, Some(5000));
+        assert_eq!(info.pv, [String::from("e2e4")]);
+
+        info.merge(AnalysisInfo {
+            depth: Some(11),
+            pv: vec![String::from("d2d4")],
+            ..Default::default()
+        });
+        assert_eq!(info.depth, Some(11));
+        assert_eq!(info.score, Some(Score::Centipawns(20)));
+        assert_eq!(info.pv, [String::from("d2d4")]);
+        assert!(AnalysisInfo::default().is_empty());
+    }
+
+    #[test]
+    fn writes_lines_with_move_numbers() {
+        let moves = ["e2e4", "e7e5", "g1f3"].map(String::from);
+        assert_eq!(
+            san_line(&Game::new(Board::initial()), &moves),
+            "1. e4 e5 2. Nf3"
+        );
+
+        let board =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        let moves = ["e7e5", "g1f3", "b8c6", "a1a5"].map(String::from);
+        // The line stops at the illegal move.
+        assert_eq!(san_line(&Game::new(board), &moves), "1... e5 2. Nf3 Nc6");
+
+        // A Chess960 castling is the king taking its rook.
+        let (board, castling) = parse_fen("3k4/8/8/8/8/8/8/1R2K2R w KQ - 0 1").unwrap();
+        let moves = ["e1b1", "d8e8"].map(String::from);
+        assert_eq!(
+            san_line(&Game::new_chess960(board, castling), &moves),
+            "1. O-O-O+ Ke8"
+        );
+    }
+
+    #[test]
+    fn limits_the_number_of_lines() {
+        let multi_pv = |max| EngineOption {
+            name: String::from("multipv"),
+            kind: OptionKind::Spin {
+                default: 1,
+                min: 1,
+                max,
+            },
+        };
+        assert_eq!(max_lines(&[]), 1);
+        assert_eq!(max_lines(&[multi_pv(3)]), 3);
+        assert_eq!(max_lines(&[multi_pv(500)]), 5);
+
+        assert_eq!(lines_command(&[], 3), None);
+        assert_eq!(lines_command(&[multi_pv(3)], 4), None);
+        assert_eq!(
+            lines_command(&[multi_pv(3)], 2),
+            Some(EngineCommand::SetOption {
+                name: String::from("multipv"),
+                value: Some(String::from("2")),
+            })
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! Opening books in the Polyglot format, which let the engine vary its first moves.
+use std::{fmt, fs, io, path::Path};
+
+use owlchess::{Board, Coord, File, Move, Piece, Rank};
+use rand::Rng;
+
+use crate::game::polyglot::polyglot_key;
+
+/// An entry takes 16 bytes : the key of the position, the move, its weight and 4 bytes of
+/// learning data, all in big-endian order.
+const ENTRY_SIZE: usize = 16;
+
+#[derive(Debug)]
+pub enum BookError {
+    Io(io::Error),
+    /// The size of the file is not a multiple of the size of an entry.
+    InvalidSize(usize),
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookError::Io(err) => write!(f, "{err}"),
+            BookError::InvalidSize(size) => {
+                write!(f, "invalid Polyglot book of {size} bytes")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BookError {}
+
+impl From<io::Error> for BookError {
+    fn from(err: io::Error) -> Self {
+        BookError::Io(err)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BookEntry {
+    key: u64,
+    raw_move: u16,
+    weight: u16,
+}
+
+/// A move of the book, with its share of the weights of the moves of the position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookMove {
+    pub book_move: Move,
+    pub weight: u16,
+    /// From 0 to 100.
+    pub percent: f64,
+}
+
+pub struct OpeningBook {
+    /// Sorted by key, as in the file.
+    entries: Vec<BookEntry>,
+}
+
+impl OpeningBook {
+    pub fn open(path: &Path) -> Result<Self, BookError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BookError> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(BookError::InvalidSize(bytes.len()));
+        }
+        let mut entries: Vec<BookEntry> = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|chunk| BookEntry {
+                key: u64::from_be_bytes(chunk[0..8].try_into().unwrap()),
+                raw_move: u16::from_be_bytes([chunk[8], chunk[9]]),
+                weight: u16::from_be_bytes([chunk[10], chunk[11]]),
+            })
+            .collect();
+        // The books are sorted, but a stable sort keeps the order of the moves of a position.
+        entries.sort_by_key(|entry| entry.key);
+        Ok(Self { entries })
+    }
+
+    /// The legal moves of the book in the position, the most played first.
+    pub fn moves(&self, position: &Board) -> Vec<BookMove> {
+        let key = polyglot_key(position);
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let mut moves: Vec<(Move, u16)> = self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| Some((decode_move(entry.raw_move, position)?, entry.weight)))
+            .collect();
+        moves.sort_by(|(_, weight), (_, other)| other.cmp(weight));
+        let total: u32 = moves.iter().map(|(_, weight)| u32::from(*weight)).sum();
+        moves
+            .into_iter()
+            .map(|(book_move, weight)| BookMove {
+                book_move,
+                weight,
+                percent: if total == 0 {
+                    0.0
+                } else {
+                    100.0 * f64::from(weight) / f64::from(total)
+                },
+            })
+            .collect()
+    }
+
+    /// A move of the book, picked at random according to the weights, or the most played move.
+    pub fn choose(&self, position: &Board, best_move_only: bool) -> Option<Move> {
+        let moves = self.moves(position);
+        let total: u32 = moves
+            .iter()
+            .map(|book_move| u32::from(book_move.weight))
+            .sum();
+        if best_move_only || total == 0 {
+            return moves.first().map(|book_move| book_move.book_move);
+        }
+        let mut pick = rand::thread_rng().gen_range(0..total);
+        moves.into_iter().find_map(|book_move| {
+            let weight = u32::from(book_move.weight);
+            if pick < weight {
+                Some(book_move.book_move)
+            } else {
+                pick -= weight;
+                None
+            }
+        })
+    }
+}
+
+fn coord(file: usize, row: usize) -> Coord {
+    Coord::from_parts(File::from_index(file), Rank::from_index(7 - row))
+}
+
+/// The legal move of a book entry. The castlings are written as the king taking its rook.
+fn decode_move(raw_move: u16, position: &Board) -> Option<Move> {
+    let field = |shift: u16| usize::from((raw_move >> shift) & 7);
+    let destination = coord(field(0), field(3));
+    let source = coord(field(6), field(9));
+    let promotion = match field(12) {
+        1 => "n",
+        2 => "b",
+        3 => "r",
+        4 => "q",
+        _ => "",
+    };
+    let is_king = position.get(source).piece() == Some(Piece::King);
+    let destination = match (
+        source.to_string().as_str(),
+        destination.to_string().as_str(),
+    ) {
+        ("e1", "h1") | ("e8", "h8") if is_king => coord(6, field(3)),
+        ("e1", "a1") | ("e8", "a8") if is_king => coord(2, field(3)),
+        _ => destination,
+    };
+    Move::from_uci_legal(&format!("{source}{destination}{promotion}"), position).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(moves: &[&str]) -> Board {
+        moves.iter().fold(Board::initial(), |board, uci| {
+            let legal_move = Move::from_uci_legal(uci, &board).unwrap();
+            board.make_move(legal_move).unwrap()
+        })
+    }
+
+    /// The raw move of a book entry, from its squares.
+    fn raw_move(source: &str, destination: &str, promotion: u16) -> u16 {
+        let square = |name: &str| {
+            let bytes = name.as_bytes();
+            (u16::from(bytes[0] - b'a'), u16::from(bytes[1] - b'1'))
+        };
+        let (source_file, source_row) = square(source);
+        let (destination_file, destination_row) = square(destination);
+        destination_file
+            | destination_row << 3
+            | source_file << 6
+            | source_row << 9
+            | promotion << 12
+    }
+
+    fn entry(key: u64, raw_move: u16, weight: u16) -> Vec<u8> {
+        let mut bytes = key.to_be_bytes().to_vec();
+        bytes.extend(raw_move.to_be_bytes());
+        bytes.extend(weight.to_be_bytes());
+        bytes.extend([0; 4]);
+        bytes
+    }
+
+    #[test]
+    fn decodes_castling_as_the_king_taking_its_rook() {
+        let white = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let decode = |source, destination, board: &Board| {
+            decode_move(raw_move(source, destination, 0), board).map(|m| m.uci().to_string())
+        };
+        assert_eq!(decode("e1", "h1", &white).as_deref(), Some("e1g1"));
+        assert_eq!(decode("e1", "a1", &white).as_deref(), Some("e1c1"));
+        assert_eq!(decode("e1", "f1", &white).as_deref(), Some("e1f1"));
+
+        let black = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1").unwrap();
+        assert_eq!(decode("e8", "h8", &black).as_deref(), Some("e8g8"));
+        assert_eq!(decode("e8", "a8", &black).as_deref(), Some("e8c8"));
+
+        // A rook taking on the corner is not a castling.
+        let rook = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(decode("a1", "a8", &rook).as_deref(), Some("a1a8"));
+    }
+
+    #[test]
+    fn decodes_promotions_and_skips_illegal_moves() {
+        let board = Board::from_fen("8/4P1k1/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            decode_move(raw_move("e7", "e8", 2), &board).map(|m| m.uci().to_string()),
+            Some(String::from("e7e8b"))
+        );
+        assert_eq!(decode_move(raw_move("e1", "e3", 0), &board), None);
+    }
+
+    #[test]
+    fn lists_the_moves_of_the_position_by_weight() {
+        let start = polyglot_key(&Board::initial());
+        let bytes = [
+            entry(start, raw_move("d2", "d4", 0), 10),
+            entry(start, raw_move("e2", "e4", 0), 30),
+            // Not legal in the position, as for a colliding key.
+            entry(start, raw_move("e2", "e5", 0), 50),
+            entry(start + 1, raw_move("c2", "c4", 0), 100),
+        ]
+        .concat();
+        let book = OpeningBook::from_bytes(&bytes).unwrap();
+        let moves = book.moves(&Board::initial());
+        let summary: Vec<_> = moves
+            .iter()
+            .map(|book_move| (book_move.book_move.uci().to_string(), book_move.percent))
+            .collect();
+        assert_eq!(
+            summary,
+            [(String::from("e2e4"), 75.0), (String::from("d2d4"), 25.0)]
+        );
+        assert_eq!(
+            book.choose(&Board::initial(), true)
+                .map(|m| m.uci().to_string()),
+            Some(String::from("e2e4"))
+        );
+        let chosen = book.choose(&Board::initial(), false).unwrap();
+        assert!(moves.iter().any(|book_move| book_move.book_move == chosen));
+        assert!(
+            book.choose(&play(&["e2e4"]), false).is_none(),
+            "no move out of the book"
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_books() {
+        assert!(matches!(
+            OpeningBook::from_bytes(&[0; 17]),
+            Err(BookError::InvalidSize(17))
+        ));
+    }
+}
@@ -0,0 +1,816 @@
+//! The Chess Engine Communication Protocol, spoken by XBoard engines.
+//!
+//! Unlike UCI, the engine keeps its own copy of the game : the adapter tracks the position known
+//! by the engine, and sends only the new moves when the game goes on.
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use iced::futures::{
+    SinkExt, Stream, StreamExt,
+    channel::mpsc::{self, Sender},
+    stream,
+};
+use owlchess::{Board, Color, Coord, GameStatus, Move, MoveChain};
+
+use crate::{
+    engine::{
+        EngineCommand, EngineError, EngineEvent, EngineIdentity, EngineOption, EngineSender,
+        OptionKind, Protocol, SearchLimit,
+        analysis::{AnalysisInfo, Score},
+        default_name,
+        process::{Input, handshake_timeout, output_lines, spawn, timeout},
+    },
+    game::pgn::normalize_san,
+};
+
+/// An engine sending no feature within this delay speaks the first version of the protocol.
+const FEATURES_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The features the adapter can work with. The others are rejected.
+const ACCEPTED_FEATURES: [&str; 11] = [
+    "myname", "usermove", "setboard", "option", "done", "sigint", "sigterm", "reuse", "colors",
+    "debug", "egt",
+];
+
+/// Splits the arguments of a `feature` command into name and value pairs, as in
+/// `feature myname="Some engine 1.0" usermove=1 done=1`.
+fn parse_features(arguments: &str) -> Vec<(String, String)> {
+    let mut features = Vec::new();
+    let mut rest = arguments.trim_start();
+    while let Some((name, after_name)) = rest.split_once('=') {
+        let (value, after_value) = match after_name.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => after_name
+                .split_once(char::is_whitespace)
+                .unwrap_or((after_name, "")),
+        };
+        features.push((name.trim().to_string(), value.to_string()));
+        rest = after_value.trim_start();
+    }
+    features
+}
+
+/// Parses the value of an `option` feature, as in `Skill Level -spin 20 0 20`.
+pub fn parse_option(definition: &str) -> Option<EngineOption> {
+    const KINDS: [&str; 10] = [
+        "-spin", "-slider", "-check", "-combo", "-string", "-file", "-path", "-button", "-save",
+        "-reset",
+    ];
+    let (name, kind, arguments) = KINDS.iter().find_map(|kind| {
+        let start = definition.find(&format!(" {kind}"))?;
+        let arguments = &definition[start + kind.len() + 1..];
+        (arguments.is_empty() || arguments.starts_with(' '))
+            .then(|| (definition[..start].trim(), *kind, arguments.trim()))
+    })?;
+    if name.is_empty() {
+        return None;
+    }
+
+    let kind = match kind {
+        "-spin" | "-slider" => {
+            let mut numbers = arguments.split_whitespace().map(str::parse::<i64>);
+            OptionKind::Spin {
+                default: numbers.next()?.ok()?,
+                min: numbers.next()?.ok()?,
+                max: numbers.next()?.ok()?,
+            }
+        }
+        "-check" => OptionKind::Check {
+            default: arguments == "1",
+        },
+        "-combo" => {
+            let choices: Vec<&str> = arguments.split("///").map(str::trim).collect();
+            let default = choices
+                .iter()
+                .find_map(|choice| choice.strip_prefix('*'))
+                .or(choices.first().copied())?;
+            OptionKind::Combo {
+                default: default.to_string(),
+                values: choices
+                    .iter()
+                    .map(|choice| choice.trim_start_matches('*').to_string())
+                    .collect(),
+            }
+        }
+        "-string" | "-file" | "-path" => OptionKind::String {
+            default: arguments.to_string(),
+        },
+        _ => OptionKind::Button,
+    };
+    Some(EngineOption {
+        name: name.to_string(),
+        kind,
+    })
+}
+
+/// Mate scores are given as this score plus the number of moves to mate.
+const MATE_SCORE: i32 = 100_000;
+
+/// Parses a thinking line, as in `12 35 150 120000 Nf3 Nc6 Bb5` : the depth, the score in
+/// centipawns, the time in centiseconds, the nodes, then the principal variation.
+/// The moves of the principal variation are converted to UCI notation from the searched position.
+fn parse_thinking(line: &str, position: &Board) -> Option<AnalysisInfo> {
+    let mut words = line.split_whitespace();
+    // Some engines mark the depth with a trailing character.
+    let depth = words
+        .next()?
+        .trim_end_matches(|c: char| !c.is_ascii_digit())
+        .parse()
+        .ok()?;
+    let score: i32 = words.next()?.parse().ok()?;
+    let centiseconds: u64 = words.next()?.parse().ok()?;
+    let nodes: u64 = words.next()?.parse().ok()?;
+
+    let mut board = position.clone();
+    let mut pv = Vec::new();
+    for word in words {
+        // Move numbers may be glued to the moves, as in `1.Nf3`.
+        let word = word.rsplit('.').next().unwrap_or(word);
+        if word.is_empty() {
+            continue;
+        }
+        let Some(legal_move) = Move::from_san(&normalize_san(word), &board)
+            .ok()
+            .or_else(|| Move::from_uci_legal(word, &board).ok())
+        else {
+            break;
+        };
+        match board.make_move(legal_move) {
+            Ok(next_board) => board = next_board,
+            Err(_) => break,
+        }
+        pv.push(legal_move.to_string());
+    }
+
+    Some(AnalysisInfo {
+        multipv: None,
+        depth: Some(depth),
+        score: Some(if score.abs() > MATE_SCORE {
+            Score::Mate(score.signum() * (score.abs() - MATE_SCORE))
+        } else {
+            Score::Centipawns(score)
+        }),
+        nodes: Some(nodes),
+        nps: (centiseconds > 0).then(|| nodes * 100 / centiseconds),
+        pv,
+    })
+}
+
+/// The features negotiated with the engine.
+#[derive(Default)]
+struct Features {
+    name: Option<String>,
+    /// Whether the moves are prefixed with `usermove`.
+    usermove: bool,
+    /// Whether the positions can be given as FEN, rather than in the edit mode.
+    setboard: bool,
+    /// Whether the engine asked for more time to send its features, with `done=0`.
+    needs_time: bool,
+    options: Vec<EngineOption>,
+    /// The kinds of endgame tablebases the engine can use, as in `syzygy`.
+    tablebases: Vec<String>,
+}
+
+impl Features {
+    /// Reads a line of the negotiation, and tells the answers to send and whether it is over.
+    fn read_line(&mut self, line: &str) -> (Vec<String>, bool) {
+        let Some(arguments) = line.strip_prefix("feature ") else {
+            return (Vec::new(), false);
+        };
+        let mut answers = Vec::new();
+        let mut done = false;
+        for (name, value) in parse_features(arguments) {
+            match name.as_str() {
+                "myname" => self.name = Some(value.clone()),
+                "usermove" => self.usermove = value == "1",
+                "setboard" => self.setboard = value == "1",
+                "option" => self.options.extend(parse_option(&value)),
+                "egt" => {
+                    self.tablebases = value
+                        .split(',')
+                        .map(|kind| kind.trim().to_string())
+                        .collect()
+                }
+                "done" => {
+                    done = value == "1";
+                    self.needs_time = !done;
+                }
+                _ => {}
+            }
+            let answer = if ACCEPTED_FEATURES.contains(&name.as_str()) {
+                "accepted"
+            } else {
+                "rejected"
+            };
+            answers.push(format!("{answer} {name}"));
+        }
+        (answers, done)
+    }
+
+    fn identity(&self, path: &Path) -> EngineIdentity {
+        EngineIdentity {
+            name: self.name.clone().unwrap_or_else(|| default_name(path)),
+            author: None,
+            options: self.options.clone(),
+        }
+    }
+}
+
+/// The state of the engine, as far as the adapter knows.
+#[derive(Default)]
+struct Session {
+    features: Features,
+    /// The start position known by the engine, and the moves played since.
+    /// None when the engine state is unknown, so that the next search sets the position again.
+    known_game: Option<(String, Vec<String>)>,
+    /// The searches started and not answered yet.
+    searches: usize,
+    /// The position of the last search, from which the thinking output is read.
+    searched_position: Option<Board>,
+    /// The position analysed by the engine, while it is in analysis mode.
+    analyzed_position: Option<Board>,
+    /// The first move of the last principal variation found in analysis mode.
+    analysis_best_move: Option<String>,
+}
+
+impl Session {
+    fn move_line(&self, played: &str) -> String {
+        if self.features.usermove {
+            format!("usermove {played}")
+        } else {
+            played.to_string()
+        }
+    }
+
+    /// The lines setting the start position, after `new` and `force`. Without the `setboard`
+    /// feature, the pieces are placed in the edit mode, which does not tell the en passant square
+    /// and lets the engine deduce the castling rights from the squares of the kings and rooks.
+    fn setup_lines(&self, start_fen: &str) -> Vec<String> {
+        let board = match Board::from_fen(start_fen) {
+            Ok(board) if !self.features.setboard => board,
+            _ => return vec![format!("setboard {start_fen}")],
+        };
+        let mut lines = Vec::new();
+        // The edit mode keeps the side to move, which is changed by a move from the start
+        // position.
+        if board.side() == Color::Black {
+            lines.push(self.move_line("a2a3"));
+        }
+        lines.push(String::from("edit"));
+        lines.push(String::from("#"));
+        for color in [Color::White, Color::Black] {
+            if color == Color::Black {
+                lines.push(String::from("c"));
+            }
+            for square in Coord::iter() {
+                let cell = board.get(square);
+                if cell.color() == Some(color) {
+                    lines.push(format!("{}{square}", cell.as_char().to_ascii_uppercase()));
+                }
+            }
+        }
+        lines.push(String::from("."));
+        lines
+    }
+
+    /// Converts a command into the lines to send to the engine.
+    fn command_lines(&mut self, command: &EngineCommand) -> Vec<String> {
+        match command {
+            EngineCommand::NewGame => {
+                self.known_game = Some((Board::initial().as_fen(), Vec::new()));
+                vec![String::from("new"), String::from("force")]
+            }
+            EngineCommand::SetOption { name, value: None } => vec![format!("option {name}")],
+            EngineCommand::SetOption {
+                name,
+                value: Some(value),
+            } => {
+                let is_check = self.features.options.iter().any(|option| {
+                    option.name == *name && matches!(option.kind, OptionKind::Check { .. })
+                });
+                let value = match value.as_str() {
+                    "true" if is_check => "1",
+                    "false" if is_check => "0",
+                    value => value,
+                };
+                vec![format!("option {name}={value}")]
+            }
+            EngineCommand::SetTablebasePath(path) => {
+                if self.features.tablebases.iter().any(|kind| kind == "syzygy") {
+                    vec![format!("egtpath syzygy {}", path.display())]
+                } else {
+                    Vec::new()
+                }
+            }
+            EngineCommand::Go {
+                start_fen,
+                moves,
+                limit,
+            } => {
+                let mut lines = Vec::new();
+                let known_moves = match &self.known_game {
+                    Some((known_fen, known_moves))
+                        if known_fen == start_fen && moves.starts_with(known_moves) =>
+                    {
+                        known_moves.len()
+                    }
+                    _ => {
+                        lines.push(String::from("new"));
+                        lines.push(String::from("force"));
+                        if *start_fen != Board::initial().as_fen() {
+                            lines.extend(self.setup_lines(start_fen));
+                        }
+                        0
+                    }
+                };
+                lines.extend(
+                    moves[known_moves..]
+                        .iter()
+                        .map(|played| self.move_line(played)),
+                );
+                self.searched_position =
+                    MoveChain::from_fen(start_fen).ok().and_then(|mut game| {
+                        game.push_uci_list(&moves.join(" ")).ok()?;
+                        Some(game.last().clone())
+                    });
+                // The thinking output gives the evaluation, in every search.
+                lines.push(String::from("post"));
+                match limit {
+                    SearchLimit::MoveTime(duration) => {
+                        lines.push(format!("st {}", duration.as_secs().max(1)));
+                        lines.push(String::from("go"));
+                    }
+                    SearchLimit::Depth(depth) => {
+                        lines.push(format!("sd {depth}"));
+                        lines.push(String::from("go"));
+                    }
+                    SearchLimit::Clock {
+                        white,
+                        black,
+                        increment,
+                    } => {
+                        let (own_time, opponent_time) =
+                            match self.searched_position.as_ref().map(Board::side) {
+                                Some(Color::Black) => (black, white),
+                                _ => (white, black),
+                            };
+                        // The base time of the level is the time left to the engine, which is
+                        // told again before each move anyway.
+                        let seconds = own_time.as_secs();
+                        lines.push(format!(
+                            "level 0 {}:{:02} {}",
+                            seconds / 60,
+                            seconds % 60,
+                            increment.as_secs_f64()
+                        ));
+                        lines.push(format!("time {}", own_time.as_millis() / 10));
+                        lines.push(format!("otim {}", opponent_time.as_millis() / 10));
+                        lines.push(String::from("go"));
+                    }
+                    SearchLimit::Infinite => {
+                        self.analyzed_position = self.searched_position.clone();
+                        self.analysis_best_move = None;
+                        lines.push(String::from("analyze"));
+                    }
+                }
+                self.known_game = Some((start_fen.clone(), moves.clone()));
+                self.searches += 1;
+                lines
+            }
+            EngineCommand::Stop => vec![String::from("?")],
+            EngineCommand::GameOver(outcome) => vec![format!(
+                "result {} {{{outcome}}}",
+                GameStatus::from(Some(*outcome))
+            )],
+            EngineCommand::Quit => vec![String::from("quit")],
+        }
+    }
+
+    /// The lines of the commands sent during the negotiation, once it is over.
+    fn start(&mut self, waiting_commands: impl Iterator<Item = EngineCommand>) -> Vec<String> {
+        waiting_commands
+            .flat_map(|command| self.command_lines(&command))
+            .collect()
+    }
+
+    /// Reads a thinking line of the last search.
+    fn read_thinking(&mut self, line: &str) -> Option<AnalysisInfo> {
+        let info = parse_thinking(line, self.searched_position.as_ref()?)?;
+        if let Some(best_move) = info.pv.first() {
+            self.analysis_best_move = Some(best_move.clone());
+        }
+        Some(info)
+    }
+
+    /// Leaves the analysis mode, if the engine is in it, with the lines to send.
+    /// The engine does not play a move then : the first move of the last principal variation
+    /// answers the search instead.
+    fn stop_analysis(&mut self) -> Option<(Vec<String>, String)> {
+        self.analyzed_position.take()?;
+        self.searches = self.searches.saturating_sub(1);
+        let best_move = self
+            .analysis_best_move
+            .take()
+            .unwrap_or_else(|| String::from("0000"));
+        Some((vec![String::from("exit")], best_move))
+    }
+
+    /// Reads the move played by the engine, as in `move e2e4` or `move Nf3`. The moves in SAN
+    /// are converted to UCI notation from the searched position.
+    fn read_move(&mut self, line: &str) -> Option<(String, Vec<String>)> {
+        let played = line
+            .strip_prefix("move ")
+            .or_else(|| line.strip_prefix("My move is: "))?
+            .trim();
+        let played = self
+            .searched_position
+            .as_ref()
+            .and_then(|position| {
+                Move::from_uci_legal(played, position)
+                    .or_else(|_| Move::from_san(&normalize_san(played), position))
+                    .ok()
+            })
+            .map_or_else(|| played.to_string(), |legal_move| legal_move.to_string());
+        self.searches = self.searches.saturating_sub(1);
+        let mut answers = Vec::new();
+        if self.searches == 0 {
+            // Playing the move leaves the engine waiting for the opponent : the next move is
+            // given by the application, so the engine is kept from thinking on its own.
+            answers.push(String::from("force"));
+            if let Some((_, moves)) = self.known_game.as_mut() {
+                moves.push(played.clone());
+            }
+        } else {
+            // The move answers an interrupted search : the engine state is not reliable anymore.
+            self.known_game = None;
+        }
+        Some((played, answers))
+    }
+}
+
+fn write_lines(stdin: &mut impl Write, lines: &[String]) -> Result<(), EngineError> {
+    lines
+        .iter()
+        .try_for_each(|line| writeln!(stdin, "{line}"))
+        .map_err(|err| EngineError::CannotWrite(err.to_string()))
+}
+
+const HANDSHAKE: [&str; 2] = ["xboard", "protover 2"];
+
+/// The inputs of the engine process, with the delays of the negotiation : the engines of the
+/// first version of the protocol send no feature, the others end with `done=1`.
+fn negotiation_inputs(
+    inputs: impl Stream<Item = Input> + Unpin,
+) -> impl Stream<Item = Input> + Unpin {
+    stream::select(
+        inputs,
+        stream::select(
+            handshake_timeout(),
+            timeout(FEATURES_TIMEOUT, || Input::FeaturesTimeout),
+        ),
+    )
+}
+
+pub async fn probe(path: PathBuf) -> Result<EngineIdentity, EngineError> {
+    let (_child, mut stdin, lines) = spawn(&path)?;
+    write_lines(&mut stdin, &HANDSHAKE.map(String::from))?;
+
+    let mut inputs = negotiation_inputs(output_lines(lines));
+    let mut features = Features::default();
+    while let Some(input) = inputs.next().await {
+        let done = match input {
+            Input::Line(line) => {
+                let (answers, done) = features.read_line(line.trim());
+                write_lines(&mut stdin, &answers)?;
+                done
+            }
+            Input::FeaturesTimeout => !features.needs_time,
+            Input::HandshakeTimeout => return Err(EngineError::NoAnswer(Protocol::Cecp)),
+            Input::Closed | Input::Command(_) => break,
+        };
+        if done {
+            let _ = writeln!(stdin, "quit");
+            return Ok(features.identity(&path));
+        }
+    }
+    Err(EngineError::Terminated)
+}
+
+/// Runs the engine process, forwarding its answers to the output until it stops.
+pub async fn run(path: PathBuf, mut output: Sender<EngineEvent>) {
+    let (_child, mut stdin, lines) = match spawn(&path) {
+        Ok(process) => process,
+        Err(err) => {
+            let _ = output.send(EngineEvent::Error(err)).await;
+            return;
+        }
+    };
+    let (commands_sender, commands) = mpsc::unbounded();
+    let _ = output
+        .send(EngineEvent::Started(EngineSender(commands_sender)))
+        .await;
+    if let Err(err) = write_lines(&mut stdin, &HANDSHAKE.map(String::from)) {
+        let _ = output.send(EngineEvent::Error(err)).await;
+        return;
+    }
+
+    let mut inputs = negotiation_inputs(stream::select(
+        output_lines(lines),
+        commands.map(Input::Command),
+    ));
+    let mut session = Session::default();
+    let mut ready = false;
+    // The commands sent before the end of the negotiation wait for it.
+    let mut waiting_commands = Vec::new();
+    while let Some(input) = inputs.next().await {
+        let (lines, event) = match input {
+            Input::Line(line) if !ready => {
+                let (mut answers, done) = session.features.read_line(line.trim());
+                if done {
+                    ready = true;
+                    answers.extend(session.start(waiting_commands.drain(..)));
+                    (
+                        answers,
+                        Some(EngineEvent::Ready(session.features.identity(&path))),
+                    )
+                } else {
+                    (answers, None)
+                }
+            }
+            Input::FeaturesTimeout if !ready && !session.features.needs_time => {
+                ready = true;
+                (
+                    session.start(waiting_commands.drain(..)),
+                    Some(EngineEvent::Ready(session.features.identity(&path))),
+                )
+            }
+            Input::Line(line) if session.analyzed_position.is_some() => (
+                Vec::new(),
+                session.read_thinking(line.trim()).map(EngineEvent::Info),
+            ),
+            Input::Line(line) => match session.read_move(line.trim()) {
+                Some((played, answers)) => (answers, Some(EngineEvent::BestMove(played))),
+                None => (
+                    Vec::new(),
+                    session.read_thinking(line.trim()).map(EngineEvent::Info),
+                ),
+            },
+            Input::Command(EngineCommand::Quit) => {
+                let _ = writeln!(stdin, "quit");
+                break;
+            }
+            Input::Command(command) if !ready => {
+                waiting_commands.push(command);
+                (Vec::new(), None)
+            }
+            Input::Command(EngineCommand::Stop) => match session.stop_analysis() {
+                Some((lines, best_move)) => (lines, Some(EngineEvent::BestMove(best_move))),
+                None => (session.command_lines(&EngineCommand::Stop), None),
+            },
+            Input::Command(command) => (session.command_lines(&command), None),
+            Input::HandshakeTimeout if !ready => {
+                let _ = output
+                    .send(EngineEvent::Error(EngineError::NoAnswer(Protocol::Cecp)))
+                    .await;
+                return;
+            }
+            Input::HandshakeTimeout | Input::FeaturesTimeout => (Vec::new(), None),
+            Input::Closed => break,
+        };
+
+        let event = match write_lines(&mut stdin, &lines) {
+            Ok(()) => event,
+            Err(err) => Some(EngineEvent::Error(err)),
+        };
+        if let Some(event) = event
+            && output.send(event).await.is_err()
+        {
+            return;
+        }
+    }
+    let _ = output.send(EngineEvent::Terminated).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use iced::futures::{executor::block_on, future};
+    use owlchess::movegen::legal;
+
+    use super::*;
+
+    /// The scripted engine of the examples, which cargo builds along with the tests.
+    fn stand_in() -> PathBuf {
+        let mut path = std::env::current_exe().unwrap();
+        // From target/debug/deps/ to target/debug/examples/.
+        path.pop();
+        path.pop();
+        let path = path
+            .join("examples")
+            .join(format!("cecp_stand_in{}", std::env::consts::EXE_SUFFIX));
+        assert!(path.exists(), "build the cecp_stand_in example first");
+        path
+    }
+
+    /// The move the stand-in engine plays in the position : its first legal move.
+    fn first_move(fen: &str, moves: &[&str]) -> String {
+        let mut game = MoveChain::from_fen(fen).unwrap();
+        game.push_uci_list(&moves.join(" ")).unwrap();
+        let position = game.last();
+        legal::gen_all(position).iter().next().unwrap().to_string()
+    }
+
+    fn go(fen: &str, moves: &[&str]) -> EngineCommand {
+        EngineCommand::Go {
+            start_fen: fen.to_string(),
+            moves: moves.iter().map(|played| played.to_string()).collect(),
+            limit: SearchLimit::Depth(1),
+        }
+    }
+
+    /// Runs the engine through the adapter : sends the commands, waiting for the answer to each
+    /// search, and gives the identity of the engine and its moves.
+    fn play(path: PathBuf, commands: Vec<EngineCommand>) -> (EngineIdentity, Vec<String>) {
+        let (output, mut events) = mpsc::channel(100);
+        let driver = async move {
+            let Some(EngineEvent::Started(sender)) = events.next().await else {
+                panic!("the engine did not start");
+            };
+            let mut identity = None;
+            let mut moves = Vec::new();
+            for command in commands {
+                let searches = matches!(command, EngineCommand::Go { .. });
+                sender.send(command);
+                while searches || identity.is_none() {
+                    match events.next().await {
+                        Some(EngineEvent::Ready(ready)) => {
+                            identity = Some(ready);
+                            if !searches {
+                                break;
+                            }
+                        }
+                        Some(EngineEvent::BestMove(best_move)) => {
+                            moves.push(best_move);
+                            break;
+                        }
+                        Some(EngineEvent::Info(_)) => {}
+                        event => panic!("unexpected event {event:?}"),
+                    }
+                }
+            }
+            sender.send(EngineCommand::Quit);
+            (identity.unwrap(), moves)
+        };
+        block_on(future::join(run(path, output), driver)).1
+    }
+
+    #[test]
+    fn negotiates_the_features() {
+        let identity = block_on(probe(stand_in())).unwrap();
+        assert_eq!(identity.name, "Stand-in engine");
+        let names: Vec<_> = identity
+            .options
+            .iter()
+            .map(|option| option.name.as_str())
+            .collect();
+        assert_eq!(names, ["Greedy", "Thinking lines", "Notation"]);
+    }
+
+    #[test]
+    fn plays_moves_in_uci_notation() {
+        let start = Board::initial().as_fen();
+        let (_, moves) = play(
+            stand_in(),
+            vec![EngineCommand::NewGame, go(&start, &["e2e4"])],
+        );
+        let reply = first_move(&start, &["e2e4"]);
+        assert_eq!(moves, [reply.as_str()]);
+
+        // The game goes on from the moves known by the engine.
+        let (_, moves) = play(
+            stand_in(),
+            vec![
+                EngineCommand::NewGame,
+                go(&start, &["e2e4"]),
+                go(&start, &["e2e4", &reply, "d2d4"]),
+            ],
+        );
+        assert_eq!(moves[1], first_move(&start, &["e2e4", &reply, "d2d4"]));
+    }
+
+    #[test]
+    fn converts_moves_in_san() {
+        let fen = "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1";
+        let (_, moves) = play(
+            stand_in(),
+            vec![
+                EngineCommand::SetOption {
+                    name: String::from("Notation"),
+                    value: Some(String::from("SAN")),
+                },
+                EngineCommand::NewGame,
+                go(fen, &[]),
+                go(fen, &["e1c1", "e8g8"]),
+            ],
+        );
+        assert_eq!(
+            moves,
+            [first_move(fen, &[]), first_move(fen, &["e1c1", "e8g8"])]
+        );
+    }
+
+    #[test]
+    fn plays_from_a_position_set_up() {
+        let fen = "4k3/8/8/3pP3/8/8/8/R3K2R w KQ d6 0 12";
+        let (_, moves) = play(stand_in(), vec![go(fen, &[]), go(fen, &["e5d6"])]);
+        assert_eq!(moves, [first_move(fen, &[]), first_move(fen, &["e5d6"])]);
+    }
+
+    /// An engine of the first version of the protocol : the stand-in, started by a script.
+    #[cfg(unix)]
+    fn first_version_stand_in() -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = std::env::temp_dir().join(format!("cecp_protover_1_{}", std::process::id()));
+        std::fs::write(
+            &script,
+            format!("#!/bin/sh\nexec '{}' --protover 1\n", stand_in().display()),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        script
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn falls_back_to_the_first_version() {
+        let script = first_version_stand_in();
+        let identity = block_on(probe(script.clone())).unwrap();
+        assert_eq!(identity.name, default_name(&script));
+        assert!(identity.options.is_empty());
+
+        // Without setboard, the positions are set up in the edit mode.
+        let fen = "r3k2r/ppp2ppp/8/8/8/8/PPP2PPP/R3K2R b KQkq - 4 20";
+        let start = Board::initial().as_fen();
+        let (_, moves) = play(
+            script.clone(),
+            vec![
+                go(&start, &["g1f3"]),
+                go(&start, &["g1f3", &first_move(&start, &["g1f3"]), "b1c3"]),
+                go(fen, &[]),
+                go(fen, &["e8c8", "e1g1"]),
+            ],
+        );
+        let _ = std::fs::remove_file(script);
+        assert_eq!(
+            moves,
+            [
+                first_move(&start, &["g1f3"]),
+                first_move(&start, &["g1f3", &first_move(&start, &["g1f3"]), "b1c3"]),
+                first_move(fen, &[]),
+                first_move(fen, &["e8c8", "e1g1"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn sets_up_positions_in_the_edit_mode_without_setboard() {
+        let session = Session::default();
+        assert_eq!(
+            session.setup_lines("4k3/8/8/8/8/8/8/4K2R b K - 0 1"),
+            ["a2a3", "edit", "#", "Ke1", "Rh1", "c", "Ke8", "."]
+        );
+        let mut session = Session::default();
+        session.features.setboard = true;
+        assert_eq!(
+            session.setup_lines("4k3/8/8/8/8/8/8/4K2R b K - 0 1"),
+            ["setboard 4k3/8/8/8/8/8/8/4K2R b K - 0 1"]
+        );
+    }
+
+    #[test]
+    fn waits_for_the_features_asked_for() {
+        let mut features = Features::default();
+        let (answers, done) = features.read_line("feature done=0");
+        assert_eq!(
+            (answers, done),
+            (vec![String::from("accepted done")], false)
+        );
+        assert!(features.needs_time);
+        let (answers, done) =
+            features.read_line("feature myname=\"Slow engine\" setboard=1 ping=1 done=1");
+        assert!(done);
+        assert!(features.setboard);
+        assert_eq!(
+            answers,
+            [
+                "accepted myname",
+                "accepted setboard",
+                "rejected ping",
+                "accepted done"
+            ]
+        );
+    }
+}
@@ -0,0 +1,137 @@
+//! How the engine opponent ends the games : resigning the lost positions, and answering the draw
+//! offers of the player, from the evaluations of its searches.
+use crate::engine::{analysis::Score, syzygy::Wdl};
+
+/// The default resign score, in centipawns.
+pub const DEFAULT_RESIGN_SCORE: i32 = 800;
+
+/// The engine must see its position lost for this many moves in a row before resigning.
+const RESIGN_MOVES: usize = 3;
+
+/// The score of a mate, for the decisions.
+const MATE_CENTIPAWNS: i32 = 100_000;
+
+/// Until this move, the engine only accepts a draw when it is clearly worse.
+const EARLY_DRAW_MOVE: u16 = 20;
+
+/// From this move, the engine accepts a draw when it is not better.
+const LATE_DRAW_MOVE: u16 = 40;
+
+/// The evaluations of the engine over the game, from its point of view.
+#[derive(Debug, Clone, Default)]
+pub struct Conduct {
+    /// The engine resigns once it evaluates its position below the opposite of this score.
+    /// None lets it play on to the end.
+    resign_score: Option<i32>,
+    /// The evaluation of the last search, while it goes on.
+    last_score: Option<Score>,
+    /// The moves in a row evaluated as lost.
+    lost_moves: usize,
+}
+
+impl Conduct {
+    pub fn new(resign_score: Option<i32>) -> Self {
+        Self {
+            resign_score,
+            ..Self::default()
+        }
+    }
+
+    pub fn resign_score(&self) -> Option<i32> {
+        self.resign_score
+    }
+
+    /// Forgets the evaluation of the previous search, as the engine starts a new one.
+    pub fn start_search(&mut self) {
+        self.last_score = None;
+    }
+
+    /// Keeps the evaluation reported by the engine during its search.
+    pub fn update(&mut self, score: Score) {
+        self.last_score = Some(score);
+    }
+
+    /// Keeps the result of the tablebases, for a move played from them without a search.
+    pub fn update_from_tablebases(&mut self, wdl: Wdl) {
+        let centipawns = match wdl {
+            Wdl::Win => MATE_CENTIPAWNS,
+            Wdl::Loss => -MATE_CENTIPAWNS,
+            Wdl::CursedWin | Wdl::Draw | Wdl::BlessedLoss => 0,
+        };
+        self.last_score = Some(Score::Centipawns(centipawns));
+    }
+
+    fn centipawns(&self) -> Option<i32> {
+        self.last_score.map(|score| match score {
+            Score::Centipawns(centipawns) => centipawns,
+            Score::Mate(moves) if moves > 0 => MATE_CENTIPAWNS,
+            Score::Mate(_) => -MATE_CENTIPAWNS,
+        })
+    }
+
+    /// Records the evaluation of the search which has just ended, and tells whether the engine
+    /// resigns rather than playing its move.
+    pub fn resigns(&mut self) -> bool {
+        let lost = self
+            .resign_score
+            .zip(self.centipawns())
+            .is_some_and(|(resign_score, centipawns)| centipawns <= -resign_score);
+        self.lost_moves = if lost { self.lost_moves + 1 } else { 0 };
+        self.lost_moves >= RESIGN_MOVES
+    }
+
+    /// Whether the engine accepts a draw at the given move : the longer the game, the less it
+    /// needs to be worse. It declines before having evaluated the game.
+    pub fn accepts_draw(&self, move_number: u16) -> bool {
+        let Some(centipawns) = self.centipawns() else {
+            return false;
+        };
+        match move_number {
+            ..EARLY_DRAW_MOVE => centipawns <= -150,
+            EARLY_DRAW_MOVE..LATE_DRAW_MOVE => centipawns <= -30,
+            _ => centipawns <= 30,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resigns_after_lost_moves_in_a_row() {
+        let mut conduct = Conduct::new(Some(DEFAULT_RESIGN_SCORE));
+        for _ in 1..RESIGN_MOVES {
+            conduct.start_search();
+            conduct.update(Score::Centipawns(-900));
+            assert!(!conduct.resigns());
+        }
+        conduct.start_search();
+        conduct.update(Score::Mate(-3));
+        assert!(conduct.resigns());
+    }
+
+    #[test]
+    fn stale_score_is_forgotten() {
+        let mut conduct = Conduct::new(Some(DEFAULT_RESIGN_SCORE));
+        for _ in 1..RESIGN_MOVES {
+            conduct.start_search();
+            conduct.update(Score::Centipawns(-900));
+            assert!(!conduct.resigns());
+        }
+        // The next search reports no score.
+        conduct.start_search();
+        assert!(!conduct.resigns());
+        assert!(!conduct.accepts_draw(60));
+    }
+
+    #[test]
+    fn never_resigns_without_resign_score() {
+        let mut conduct = Conduct::new(None);
+        for _ in 0..RESIGN_MOVES {
+            conduct.start_search();
+            conduct.update_from_tablebases(Wdl::Loss);
+            assert!(!conduct.resigns());
+        }
+    }
+}
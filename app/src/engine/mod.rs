@@ -0,0 +1,447 @@
+//! The chess engines, running as child processes.
+//!
+//! The application talks to an engine through [`EngineCommand`]s and receives its answers as
+//! [`EngineEvent`]s, whatever the protocol spoken by the engine.
+pub mod analysis;
+pub mod book;
+pub mod cecp;
+pub mod conduct;
+mod process;
+pub mod strength;
+pub mod syzygy;
+pub mod tournament;
+pub mod uci;
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use iced::{Subscription, futures::channel::mpsc::UnboundedSender};
+use owlchess::Outcome;
+use serde::{Deserialize, Serialize};
+
+use crate::{engine::analysis::AnalysisInfo, game::Game};
+
+/// How long the engine thinks on each move, when its strength is not limited by the depth.
+pub const DEFAULT_MOVE_TIME: Duration = Duration::from_secs(1);
+
+/// How long the engine searches for a hint to the player.
+pub const HINT_MOVE_TIME: Duration = Duration::from_millis(500);
+
+/// How long the engine searches each position of a reviewed game.
+pub const REVIEW_MOVE_TIME: Duration = Duration::from_millis(500);
+
+/// The type of an option, with its constraints and default value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionKind {
+    Spin {
+        default: i64,
+        min: i64,
+        max: i64,
+    },
+    Check {
+        default: bool,
+    },
+    Combo {
+        default: String,
+        values: Vec<String>,
+    },
+    String {
+        default: String,
+    },
+    Button,
+}
+
+/// An option advertised by the engine when it introduces itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineOption {
+    pub name: String,
+    pub kind: OptionKind,
+}
+
+impl OptionKind {
+    /// The default value, as given to `setoption`. Buttons have no value.
+    pub fn default_value(&self) -> Option<String> {
+        match self {
+            OptionKind::Spin { default, .. } => Some(default.to_string()),
+            OptionKind::Check { default } => Some(default.to_string()),
+            OptionKind::Combo { default, .. } | OptionKind::String { default } => {
+                Some(default.clone())
+            }
+            OptionKind::Button => None,
+        }
+    }
+
+    /// Whether the value can be given to `setoption` for this option.
+    pub fn accepts(&self, value: &str) -> bool {
+        match self {
+            OptionKind::Spin { min, max, .. } => value
+                .parse::<i64>()
+                .is_ok_and(|value| (*min..=*max).contains(&value)),
+            OptionKind::Check { .. } => value == "true" || value == "false",
+            OptionKind::Combo { values, .. } => values.iter().any(|allowed| allowed == value),
+            OptionKind::String { .. } => true,
+            OptionKind::Button => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchLimit {
+    MoveTime(Duration),
+    Depth(u32),
+    /// Searches until stopped, reporting the analysis along the way.
+    Infinite,
+    /// Plays with a clock : the times left to both sides, and the time added after each move.
+    Clock {
+        white: Duration,
+        black: Duration,
+        increment: Duration,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineCommand {
+    /// The next search belongs to another game.
+    NewGame,
+    /// Sets an engine option. Buttons have no value.
+    SetOption {
+        name: String,
+        value: Option<String>,
+    },
+    /// Searches the best move after the moves (in UCI notation) played from the start position.
+    Go {
+        start_fen: String,
+        moves: Vec<String>,
+        limit: SearchLimit,
+    },
+    /// Tells the engine where the Syzygy tablebases are.
+    SetTablebasePath(PathBuf),
+    /// Interrupts the search : the engine answers with its best move so far.
+    Stop,
+    /// Tells the engine how the game ended.
+    GameOver(Outcome),
+    Quit,
+}
+
+#[derive(Debug, Clone)]
+pub enum EngineEvent {
+    /// The engine process is running, and listens to the commands given to the sender.
+    Started(EngineSender),
+    /// The engine has introduced itself and is ready to play.
+    Ready(EngineIdentity),
+    /// The progress of a search.
+    Info(AnalysisInfo),
+    /// The answer to a search, in UCI notation.
+    BestMove(String),
+    Error(EngineError),
+    Terminated,
+}
+
+/// Reasons why an engine cannot be used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineError {
+    CannotStart(PathBuf, String),
+    CannotWrite(String),
+    /// The engine did not complete the handshake in time, in the protocol it was registered with.
+    NoAnswer(Protocol),
+    Terminated,
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::CannotStart(path, err) => {
+                write!(f, "Cannot start the engine {}: {err}.", path.display())
+            }
+            EngineError::CannotWrite(err) => {
+                write!(f, "Cannot send a command to the engine: {err}.")
+            }
+            EngineError::NoAnswer(protocol) => write!(
+                f,
+                "The engine did not answer within {} seconds : it may not speak the {protocol} \
+                 protocol.",
+                process::HANDSHAKE_TIMEOUT.as_secs()
+            ),
+            EngineError::Terminated => write!(f, "The engine has stopped."),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+#[derive(Debug, Clone)]
+pub struct EngineSender(UnboundedSender<EngineCommand>);
+
+impl EngineSender {
+    pub fn send(&self, command: EngineCommand) {
+        // A closed channel means the engine has stopped, which is reported as an event.
+        let _ = self.0.unbounded_send(command);
+    }
+}
+
+/// The protocol spoken by an engine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    #[default]
+    Uci,
+    /// The Chess Engine Communication Protocol, spoken by XBoard engines.
+    Cecp,
+}
+
+impl Protocol {
+    pub const ALL: [Protocol; 2] = [Protocol::Uci, Protocol::Cecp];
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Protocol::Uci => write!(f, "UCI"),
+            Protocol::Cecp => write!(f, "XBoard (CECP)"),
+        }
+    }
+}
+
+/// Runs the engine at the given path as long as the subscription is kept.
+/// The instance number tells apart the processes of the same engine running at once, and the
+/// attempt number allows restarting an engine which has stopped.
+pub fn connect(
+    path: PathBuf,
+    protocol: Protocol,
+    instance: usize,
+    attempt: usize,
+) -> Subscription<EngineEvent> {
+    Subscription::run_with_id(
+        (path.clone(), protocol, instance, attempt),
+        iced::stream::channel(100, move |output| async move {
+            match protocol {
+                Protocol::Uci => uci::run(path, output).await,
+                Protocol::Cecp => cecp::run(path, output).await,
+            }
+        }),
+    )
+}
+
+/// Starts the engine only to read its identity, then stops it.
+pub async fn probe(path: PathBuf, protocol: Protocol) -> Result<EngineIdentity, EngineError> {
+    match protocol {
+        Protocol::Uci => uci::probe(path).await,
+        Protocol::Cecp => cecp::probe(path).await,
+    }
+}
+
+/// The name of the engine, when it does not give one.
+pub fn default_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// The name and the options given by the engine during the handshake.
+#[derive(Debug, Clone)]
+pub struct EngineIdentity {
+    pub name: String,
+    pub author: Option<String>,
+    pub options: Vec<EngineOption>,
+}
+
+impl EngineIdentity {
+    /// The commands setting the given option values, skipping the options that the engine
+    /// does not know or whose values it would not accept.
+    pub fn option_commands(&self, values: &BTreeMap<String, String>) -> Vec<EngineCommand> {
+        values
+            .iter()
+            .filter(|(name, value)| {
+                self.options
+                    .iter()
+                    .any(|option| option.name == **name && option.kind.accepts(value))
+            })
+            .map(|(name, value)| EngineCommand::SetOption {
+                name: name.clone(),
+                value: Some(value.clone()),
+            })
+            .collect()
+    }
+}
+
+/// What the last search has found so far.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchUpdate {
+    Info(AnalysisInfo),
+    /// The search is over, with this move in UCI notation.
+    BestMove(String),
+}
+
+/// The engine used by the application, and the state of its process.
+pub struct Engine {
+    path: PathBuf,
+    protocol: Protocol,
+    instance: usize,
+    attempt: usize,
+    sender: Option<EngineSender>,
+    identity: Option<EngineIdentity>,
+    error: Option<String>,
+    /// The searches sent and not answered yet.
+    /// Only the answer to the last one is relevant, the others have been stopped.
+    pending_searches: usize,
+    /// Whether the engine was told that the games are Chess960 ones.
+    chess960: bool,
+}
+
+impl Engine {
+    pub fn new(path: PathBuf, protocol: Protocol) -> Self {
+        Self::new_instance(path, protocol, 0)
+    }
+
+    /// Another process of the engine, when several of them run at once.
+    pub fn new_instance(path: PathBuf, protocol: Protocol, instance: usize) -> Self {
+        Self {
+            path,
+            protocol,
+            instance,
+            attempt: 0,
+            sender: None,
+            identity: None,
+            error: None,
+            pending_searches: 0,
+            chess960: false,
+        }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Starts the engine process again, after it has stopped or failed.
+    pub fn restart(&mut self) {
+        *self = Self {
+            attempt: self.attempt + 1,
+            ..Self::new_instance(self.path.clone(), self.protocol, self.instance)
+        };
+    }
+
+    pub fn subscription(&self) -> Subscription<EngineEvent> {
+        connect(
+            self.path.clone(),
+            self.protocol,
+            self.instance,
+            self.attempt,
+        )
+    }
+
+    /// The engine identity, once it is ready to play.
+    pub fn identity(&self) -> Option<&EngineIdentity> {
+        self.identity.as_ref().filter(|_| self.sender.is_some())
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn is_thinking(&self) -> bool {
+        self.pending_searches > 0
+    }
+
+    pub fn send(&self, command: EngineCommand) {
+        if let Some(sender) = &self.sender {
+            sender.send(command);
+        }
+    }
+
+    /// Tells the engine where the Syzygy tablebases are, if it can use them. The CECP adapter
+    /// checks the features of the engine itself.
+    pub fn set_tablebase_path(&self, path: &Path) {
+        let Some(identity) = self.identity() else {
+            return;
+        };
+        let has_option = identity
+            .options
+            .iter()
+            .any(|option| option.name == uci::TABLEBASE_OPTION);
+        if self.protocol == Protocol::Cecp || has_option {
+            self.send(EngineCommand::SetTablebasePath(path.to_path_buf()));
+        }
+    }
+
+    /// Searches the best move in the position after the given number of moves of the game.
+    pub fn search(&mut self, game: &Game, plies: usize, limit: SearchLimit) {
+        if self.sender.is_none() {
+            return;
+        }
+        // The variant is told before the position, when it changes.
+        let plays_chess960 = self
+            .identity()
+            .is_some_and(|identity| uci::plays_chess960(&identity.options));
+        if plays_chess960 && game.is_chess960() != self.chess960 {
+            self.chess960 = game.is_chess960();
+            self.send(EngineCommand::SetOption {
+                name: uci::CHESS960_OPTION.to_string(),
+                value: Some(self.chess960.to_string()),
+            });
+        }
+        self.send(EngineCommand::Go {
+            start_fen: game.start_fen(),
+            moves: game
+                .moves()
+                .iter()
+                .take(plies)
+                .map(|played| played.to_string())
+                .collect(),
+            limit,
+        });
+        self.pending_searches += 1;
+    }
+
+    /// Searches the best move in the current position of the game.
+    pub fn go(&mut self, game: &Game, limit: SearchLimit) {
+        self.search(game, game.moves().len(), limit);
+    }
+
+    /// Analyses the position after the given number of moves of the game, until stopped.
+    pub fn analyze(&mut self, game: &Game, plies: usize) {
+        self.search(game, plies, SearchLimit::Infinite);
+    }
+
+    /// Interrupts the current search, whose answer will be ignored.
+    pub fn stop(&mut self) {
+        if self.is_thinking() {
+            self.send(EngineCommand::Stop);
+        }
+    }
+
+    /// Updates the engine state, and gives back what the last search has found.
+    pub fn handle_event(&mut self, event: EngineEvent) -> Option<SearchUpdate> {
+        match event {
+            EngineEvent::Started(sender) => self.sender = Some(sender),
+            EngineEvent::Ready(identity) => self.identity = Some(identity),
+            // The reports of the stopped searches come before their answers.
+            EngineEvent::Info(info) if self.pending_searches == 1 => {
+                return Some(SearchUpdate::Info(info));
+            }
+            EngineEvent::Info(_) => {}
+            EngineEvent::BestMove(best_move) => {
+                self.pending_searches = self.pending_searches.saturating_sub(1);
+                if self.pending_searches == 0 {
+                    return Some(SearchUpdate::BestMove(best_move));
+                }
+            }
+            EngineEvent::Error(error) => {
+                self.error = Some(error.to_string());
+                self.sender = None;
+                self.pending_searches = 0;
+            }
+            EngineEvent::Terminated => {
+                if self.error.is_none() {
+                    self.error = Some(EngineError::Terminated.to_string());
+                }
+                self.sender = None;
+                self.pending_searches = 0;
+            }
+        }
+        None
+    }
+}
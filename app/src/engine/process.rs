@@ -0,0 +1,88 @@
+//! The engine processes, whatever the protocol they speak.
+use std::{
+    io::{BufRead, BufReader},
+    path::Path,
+    process::{Child, ChildStdin, Command, Stdio},
+    time::Duration,
+};
+
+use iced::futures::{
+    Stream, StreamExt,
+    channel::{
+        mpsc::{self, UnboundedReceiver},
+        oneshot,
+    },
+    stream,
+};
+
+use crate::engine::{EngineCommand, EngineError};
+
+/// How long an engine may take to introduce itself, before it is considered broken.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Kills the engine process when the connection is dropped.
+pub struct ChildGuard(Child);
+
+impl Drop for ChildGuard {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+pub enum Input {
+    Line(String),
+    Command(EngineCommand),
+    /// The engine closed its output.
+    Closed,
+    HandshakeTimeout,
+    /// The CECP engine sent no feature in time : it speaks the first version of the protocol.
+    FeaturesTimeout,
+}
+
+/// Starts the engine process, and reads its output lines in their own thread, as reading blocks.
+pub fn spawn(
+    path: &Path,
+) -> Result<(ChildGuard, ChildStdin, UnboundedReceiver<String>), EngineError> {
+    let child = Command::new(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| EngineError::CannotStart(path.to_path_buf(), err.to_string()))?;
+    let mut child = ChildGuard(child);
+    let (Some(stdin), Some(stdout)) = (child.0.stdin.take(), child.0.stdout.take()) else {
+        return Err(EngineError::Terminated);
+    };
+
+    let (lines_sender, lines) = mpsc::unbounded();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if lines_sender.unbounded_send(line).is_err() {
+                break;
+            }
+        }
+    });
+    Ok((child, stdin, lines))
+}
+
+pub fn output_lines(lines: UnboundedReceiver<String>) -> impl Stream<Item = Input> + Unpin {
+    lines.map(Input::Line).chain(stream::iter([Input::Closed]))
+}
+
+/// Gives the input once, after the delay.
+pub fn timeout(delay: Duration, input: fn() -> Input) -> impl Stream<Item = Input> + Unpin {
+    let (sender, receiver) = oneshot::channel();
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        let _ = sender.send(());
+    });
+    stream::once(receiver).map(move |_| input())
+}
+
+pub fn handshake_timeout() -> impl Stream<Item = Input> + Unpin {
+    timeout(HANDSHAKE_TIMEOUT, || Input::HandshakeTimeout)
+}
@@ -0,0 +1,188 @@
+//! Limiting the engine strength, with the means offered by the engine.
+use crate::engine::{DEFAULT_MOVE_TIME, EngineCommand, EngineOption, OptionKind, SearchLimit};
+
+/// The options set according to the strength chosen for each game.
+pub const STRENGTH_OPTIONS: [&str; 3] = ["UCI_LimitStrength", "UCI_Elo", "Skill Level"];
+
+/// The deepest search offered when the engine has no option limiting its strength.
+pub const MAX_LIMITED_DEPTH: i64 = 20;
+
+/// The name of the option as the engine reports it : the names are matched whatever their case.
+fn reported_name<'a>(options: &'a [EngineOption], name: &'a str) -> &'a str {
+    options
+        .iter()
+        .find(|option| option.name.eq_ignore_ascii_case(name))
+        .map_or(name, |option| option.name.as_str())
+}
+
+/// The way the engine strength can be lowered, according to the options it advertises.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrengthControl {
+    /// `UCI_LimitStrength` and `UCI_Elo`.
+    Elo { min: i64, max: i64 },
+    /// `Skill Level`.
+    SkillLevel { min: i64, max: i64 },
+    /// The search depth, for engines without strength options.
+    Depth,
+}
+
+impl StrengthControl {
+    pub fn from_options(options: &[EngineOption]) -> Self {
+        let spin_range = |name: &str| {
+            options.iter().find_map(|option| match option.kind {
+                OptionKind::Spin { min, max, .. } if option.name.eq_ignore_ascii_case(name) => {
+                    Some((min, max))
+                }
+                _ => None,
+            })
+        };
+        let limits_strength = options.iter().any(|option| {
+            option.name.eq_ignore_ascii_case("UCI_LimitStrength")
+                && matches!(option.kind, OptionKind::Check { .. })
+        });
+
+        if limits_strength && let Some((min, max)) = spin_range("UCI_Elo") {
+            StrengthControl::Elo { min, max }
+        } else if let Some((min, max)) = spin_range("Skill Level") {
+            StrengthControl::SkillLevel { min, max }
+        } else {
+            StrengthControl::Depth
+        }
+    }
+
+    /// The range of the levels, from the weakest to the strongest.
+    pub fn range(&self) -> (i64, i64) {
+        match *self {
+            StrengthControl::Elo { min, max } | StrengthControl::SkillLevel { min, max } => {
+                (min, max)
+            }
+            StrengthControl::Depth => (1, MAX_LIMITED_DEPTH),
+        }
+    }
+
+    pub fn level_label(&self, level: i64) -> String {
+        match self {
+            StrengthControl::Elo { .. } => format!("{level} Elo"),
+            StrengthControl::SkillLevel { .. } => format!("Skill level {level}"),
+            StrengthControl::Depth => format!("Depth {level}"),
+        }
+    }
+}
+
+/// The strength chosen for the engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Strength {
+    pub control: StrengthControl,
+    /// The chosen level, or None for the full strength.
+    pub level: Option<i64>,
+}
+
+impl Strength {
+    /// The options to set before the game, named as in the options advertised by the engine.
+    /// The engine may have been limited during a previous game, so the full strength is set back
+    /// explicitly.
+    pub fn setup_commands(&self, options: &[EngineOption]) -> Vec<EngineCommand> {
+        let set_option = |name: &str, value: String| EngineCommand::SetOption {
+            name: reported_name(options, name).to_string(),
+            value: Some(value),
+        };
+        match (self.control, self.level) {
+            (StrengthControl::Elo { .. }, None) => {
+                vec![set_option("UCI_LimitStrength", String::from("false"))]
+            }
+            (StrengthControl::Elo { .. }, Some(elo)) => vec![
+                set_option("UCI_LimitStrength", String::from("true")),
+                set_option("UCI_Elo", elo.to_string()),
+            ],
+            (StrengthControl::SkillLevel { max, .. }, level) => {
+                vec![set_option("Skill Level", level.unwrap_or(max).to_string())]
+            }
+            (StrengthControl::Depth, _) => Vec::new(),
+        }
+    }
+
+    pub fn search_limit(&self) -> SearchLimit {
+        match (self.control, self.level) {
+            (StrengthControl::Depth, Some(depth)) => SearchLimit::Depth(depth as u32),
+            _ => SearchLimit::MoveTime(DEFAULT_MOVE_TIME),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spin(name: &str, min: i64, max: i64) -> EngineOption {
+        EngineOption {
+            name: name.to_string(),
+            kind: OptionKind::Spin {
+                default: max,
+                min,
+                max,
+            },
+        }
+    }
+
+    #[test]
+    fn sends_option_names_as_reported() {
+        let options = [
+            EngineOption {
+                name: String::from("uci_limitstrength"),
+                kind: OptionKind::Check { default: false },
+            },
+            spin("uci_elo", 1350, 2850),
+        ];
+        let control = StrengthControl::from_options(&options);
+        assert_eq!(
+            control,
+            StrengthControl::Elo {
+                min: 1350,
+                max: 2850
+            }
+        );
+        let strength = Strength {
+            control,
+            level: Some(1500),
+        };
+        assert_eq!(
+            strength.setup_commands(&options),
+            vec![
+                EngineCommand::SetOption {
+                    name: String::from("uci_limitstrength"),
+                    value: Some(String::from("true")),
+                },
+                EngineCommand::SetOption {
+                    name: String::from("uci_elo"),
+                    value: Some(String::from("1500")),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn skill_level_at_full_strength() {
+        let options = [spin("SKILL LEVEL", 0, 20)];
+        let strength = Strength {
+            control: StrengthControl::from_options(&options),
+            level: None,
+        };
+        assert_eq!(
+            strength.setup_commands(&options),
+            vec![EngineCommand::SetOption {
+                name: String::from("SKILL LEVEL"),
+                value: Some(String::from("20")),
+            }]
+        );
+    }
+
+    #[test]
+    fn depth_without_strength_options() {
+        let strength = Strength {
+            control: StrengthControl::from_options(&[spin("Hash", 1, 1024)]),
+            level: Some(4),
+        };
+        assert!(strength.setup_commands(&[]).is_empty());
+        assert!(matches!(strength.search_limit(), SearchLimit::Depth(4)));
+    }
+}
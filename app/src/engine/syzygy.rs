@@ -0,0 +1,1520 @@
+//! Probing of the Syzygy endgame tablebases : the result of the positions with few pieces, and
+//! the distance to the next capture or pawn move keeping it (DTZ).
+//!
+//! The tables are read as described by their generator : the position is turned into an index,
+//! whose value is decompressed from the blocks of the table. The positions where a capture
+//! decides are not stored reliably, so the captures are searched before the table is probed.
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use memmap2::Mmap;
+
+use owlchess::{Board, CastlingRights, Color, Coord, Move, Piece, movegen::legal, moves::MoveKind};
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+
+/// The most pieces of the positions found in tablebases.
+const MAX_PIECES: usize = 7;
+
+/// The rank of the moves winning within the fifty-move rule, above any DTZ.
+const MAX_RANK: i32 = 1 << 18;
+
+/// The flags of the tables and of their parts.
+const FLAG_SIDE_TO_MOVE: u8 = 1;
+const FLAG_MAPPED: u8 = 2;
+const FLAG_WIN_PLIES: u8 = 4;
+const FLAG_LOSS_PLIES: u8 = 8;
+const FLAG_WIDE: u8 = 16;
+const FLAG_SINGLE_VALUE: u8 = 128;
+
+/// The order of the pieces in the names of the tables.
+const PIECE_ORDER: [(Piece, char); 5] = [
+    (Piece::Queen, 'Q'),
+    (Piece::Rook, 'R'),
+    (Piece::Bishop, 'B'),
+    (Piece::Knight, 'N'),
+    (Piece::Pawn, 'P'),
+];
+
+#[derive(Debug)]
+pub enum TablebaseError {
+    Io(io::Error),
+    /// No table was found in the directory.
+    NoTables(PathBuf),
+    /// The position has more pieces than the tables.
+    TooManyPieces,
+    /// The tables do not store the positions where castling is still allowed.
+    CastlingRights,
+    MissingTable(String),
+    InvalidTable(String),
+}
+
+impl fmt::Display for TablebaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TablebaseError::Io(err) => write!(f, "{err}"),
+            TablebaseError::NoTables(path) => {
+                write!(f, "no Syzygy table found in {}", path.display())
+            }
+            TablebaseError::TooManyPieces => write!(f, "too many pieces for the tablebases"),
+            TablebaseError::CastlingRights => {
+                write!(f, "the tablebases do not cover castling rights")
+            }
+            TablebaseError::MissingTable(name) => write!(f, "the table {name} is missing"),
+            TablebaseError::InvalidTable(name) => write!(f, "the table {name} is invalid"),
+        }
+    }
+}
+
+impl std::error::Error for TablebaseError {}
+
+impl From<io::Error> for TablebaseError {
+    fn from(err: io::Error) -> Self {
+        TablebaseError::Io(err)
+    }
+}
+
+/// The result of a position for the side to move, with perfect play. The cursed wins and the
+/// blessed losses are draws by the fifty-move rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss = -2,
+    BlessedLoss = -1,
+    Draw = 0,
+    CursedWin = 1,
+    Win = 2,
+}
+
+impl Wdl {
+    fn from_value(value: i32) -> Self {
+        match value {
+            ..=-2 => Wdl::Loss,
+            -1 => Wdl::BlessedLoss,
+            0 => Wdl::Draw,
+            1 => Wdl::CursedWin,
+            _ => Wdl::Win,
+        }
+    }
+
+    /// The result for the other side.
+    fn opposite(self) -> Self {
+        Wdl::from_value(-(self as i32))
+    }
+
+    fn signum(self) -> i32 {
+        (self as i32).signum()
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Wdl::Loss => "Loss",
+            Wdl::BlessedLoss => "Blessed loss",
+            Wdl::Draw => "Draw",
+            Wdl::CursedWin => "Cursed win",
+            Wdl::Win => "Win",
+        }
+    }
+}
+
+/// The DTZ of the position before a capture or a pawn move of the given result.
+fn dtz_before_zeroing(wdl: Wdl) -> i32 {
+    match wdl {
+        Wdl::Win => 1,
+        Wdl::CursedWin => 101,
+        Wdl::Draw => 0,
+        Wdl::BlessedLoss => -101,
+        Wdl::Loss => -1,
+    }
+}
+
+/// A legal move, with the result it leads to for the side playing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveProbe {
+    pub chess_move: Move,
+    pub wdl: Wdl,
+    /// The plies until the next capture or pawn move, positive when winning.
+    pub dtz: i32,
+    /// Orders the moves : the wins which can be converted within the fifty-move rule first.
+    rank: i32,
+}
+
+/// The result of the position, and of each of its moves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionProbe {
+    pub wdl: Wdl,
+    pub dtz: i32,
+    /// The best move first.
+    pub moves: Vec<MoveProbe>,
+}
+
+/// The kind of a table : the results (WDL) or the distances to zeroing (DTZ).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum TableKind {
+    Wdl,
+    Dtz,
+}
+
+impl TableKind {
+    fn extension(self) -> &'static str {
+        match self {
+            TableKind::Wdl => "rtbw",
+            TableKind::Dtz => "rtbz",
+        }
+    }
+
+    fn magic(self) -> [u8; 4] {
+        match self {
+            TableKind::Wdl => WDL_MAGIC,
+            TableKind::Dtz => DTZ_MAGIC,
+        }
+    }
+}
+
+/// What a table gives back : a value, or nothing because the DTZ table stores the positions
+/// with the other side to move.
+enum TableValue {
+    Value(i32),
+    OtherSideToMove,
+}
+
+/// The squares are numbered from a1 (0) to h8 (63), rank by rank.
+fn square(coord: Coord) -> usize {
+    (7 - coord.rank().index()) * 8 + coord.file().index()
+}
+
+fn rank_of(square: usize) -> usize {
+    square >> 3
+}
+
+fn file_of(square: usize) -> usize {
+    square & 7
+}
+
+/// Negative below the a1-h8 diagonal, zero on it, positive above.
+fn off_diagonal(square: usize) -> i32 {
+    rank_of(square) as i32 - file_of(square) as i32
+}
+
+/// The code of a piece in the tables : 1 to 6 for the white pawn, knight, bishop, rook, queen
+/// and king, plus 8 for the black pieces.
+fn piece_code(piece: Piece, color: Color) -> u8 {
+    let code = match piece {
+        Piece::Pawn => 1,
+        Piece::Knight => 2,
+        Piece::Bishop => 3,
+        Piece::Rook => 4,
+        Piece::Queen => 5,
+        Piece::King => 6,
+    };
+    match color {
+        Color::White => code,
+        Color::Black => code | 8,
+    }
+}
+
+/// The tables mapping the squares and the pieces to the indices of the positions.
+struct IndexMaps {
+    /// The squares below the a1-h8 diagonal, from 0 to 27.
+    b1h1h7: [u64; 64],
+    /// The squares of the a1-d1-d4 triangle, from 0 to 9, the diagonal last.
+    a1d1d4: [u64; 64],
+    /// The 462 placements of two kings, the first one in the a1-d1-d4 triangle.
+    kk: [[u64; 64]; 10],
+    binomial: [[u64; 64]; MAX_PIECES],
+    /// The pawn squares, from 0 to 47 : the leading pawn is the one with the highest value.
+    pawns: [usize; 64],
+    lead_pawn_index: [[u64; 64]; MAX_PIECES],
+    lead_pawns_size: [[u64; 4]; MAX_PIECES],
+}
+
+static MAPS: LazyLock<IndexMaps> = LazyLock::new(IndexMaps::new);
+
+impl IndexMaps {
+    fn new() -> Self {
+        let mut maps = Self {
+            b1h1h7: [0; 64],
+            a1d1d4: [0; 64],
+            kk: [[0; 64]; 10],
+            binomial: [[0; 64]; MAX_PIECES],
+            pawns: [0; 64],
+            lead_pawn_index: [[0; 64]; MAX_PIECES],
+            lead_pawns_size: [[0; 4]; MAX_PIECES],
+        };
+
+        let mut code = 0;
+        for square in 0..64 {
+            if off_diagonal(square) < 0 {
+                maps.b1h1h7[square] = code;
+                code += 1;
+            }
+        }
+
+        let mut diagonal = Vec::new();
+        code = 0;
+        for square in 0..=27 {
+            if off_diagonal(square) < 0 && file_of(square) <= 3 {
+                maps.a1d1d4[square] = code;
+                code += 1;
+            } else if off_diagonal(square) == 0 && file_of(square) <= 3 {
+                diagonal.push(square);
+            }
+        }
+        for square in diagonal {
+            maps.a1d1d4[square] = code;
+            code += 1;
+        }
+
+        let mut both_on_diagonal = Vec::new();
+        code = 0;
+        for index in 0..10 {
+            // The squares out of the triangle are mapped to 0, as b1.
+            for first in (0..=27)
+                .filter(|first| maps.a1d1d4[*first] == index as u64 && (index != 0 || *first == 1))
+            {
+                for second in 0..64 {
+                    let adjacent = rank_of(first).abs_diff(rank_of(second)) <= 1
+                        && file_of(first).abs_diff(file_of(second)) <= 1;
+                    if adjacent || (off_diagonal(first) == 0 && off_diagonal(second) > 0) {
+                        continue;
+                    }
+                    if off_diagonal(first) == 0 && off_diagonal(second) == 0 {
+                        both_on_diagonal.push((index, second));
+                    } else {
+                        maps.kk[index][second] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (index, second) in both_on_diagonal {
+            maps.kk[index][second] = code;
+            code += 1;
+        }
+
+        maps.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..MAX_PIECES.min(n + 1) {
+                maps.binomial[k][n] = if k > 0 {
+                    maps.binomial[k - 1][n - 1]
+                } else {
+                    0
+                } + if k < n { maps.binomial[k][n - 1] } else { 0 };
+            }
+        }
+
+        let mut available_squares = 47;
+        for lead_pawns in 1..=5 {
+            for file in 0..4 {
+                let mut index = 0;
+                for rank in 1..=6 {
+                    let square = rank * 8 + file;
+                    if lead_pawns == 1 {
+                        maps.pawns[square] = available_squares;
+                        maps.pawns[square ^ 7] = available_squares - 1;
+                        available_squares = available_squares.saturating_sub(2);
+                    }
+                    maps.lead_pawn_index[lead_pawns][square] = index;
+                    index += maps.binomial[lead_pawns - 1][maps.pawns[square]];
+                }
+                maps.lead_pawns_size[lead_pawns][file] = index;
+            }
+        }
+        maps
+    }
+}
+
+/// The material of a table, as given by its name like `KRPvKR` : the first side is White.
+#[derive(Debug, Clone)]
+struct Material {
+    has_pawns: bool,
+    /// Whether a side has a single piece of a kind, the kings apart.
+    has_unique_pieces: bool,
+    /// The pawns of the leading side, which has the fewer pawns, then of the other side.
+    pawn_counts: [usize; 2],
+    piece_count: usize,
+    /// Both sides have the same pieces.
+    symmetric: bool,
+}
+
+impl Material {
+    fn from_name(name: &str) -> Option<Self> {
+        let (white, black) = name.split_once('v')?;
+        let count = |side: &str, piece: char| side.chars().filter(|c| *c == piece).count();
+        let has_unique_pieces = [white, black].iter().any(|side| {
+            PIECE_ORDER
+                .iter()
+                .any(|(_, piece)| count(side, *piece) == 1)
+        });
+        let (white_pawns, black_pawns) = (count(white, 'P'), count(black, 'P'));
+        let white_leads = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+        Some(Self {
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces,
+            pawn_counts: if white_leads {
+                [white_pawns, black_pawns]
+            } else {
+                [black_pawns, white_pawns]
+            },
+            piece_count: white.len() + black.len(),
+            symmetric: white == black,
+        })
+    }
+}
+
+/// The description of a part of a table : the pieces, how they are grouped into the index, and
+/// where the compressed values are.
+#[derive(Debug, Clone, Default)]
+struct PairsData {
+    flags: u8,
+    pieces: [u8; MAX_PIECES],
+    /// The lengths of the groups of pieces, ended by 0.
+    group_len: [usize; MAX_PIECES + 1],
+    group_index: [u64; MAX_PIECES + 1],
+    block_size: u64,
+    span: u64,
+    sparse_index_size: u64,
+    block_count: u64,
+    block_length_size: u64,
+    min_symbol_length: u8,
+    /// The offsets of the tables in the file.
+    lowest_symbols: usize,
+    btree: usize,
+    sparse_index: usize,
+    block_lengths: usize,
+    data: usize,
+    /// The lowest 64-bit code of each symbol length.
+    base64: Vec<u64>,
+    /// The number of values of each symbol, minus one.
+    symbol_lengths: Vec<u8>,
+    /// The offsets of the DTZ values of each result, in the map of the table.
+    map_index: [usize; 4],
+}
+
+/// The parts of a table, by side to move, then by file of the leading pawn.
+type Parts = [[PairsData; 4]; 2];
+
+/// A table mapped from its file : only the blocks probed are read from the disk.
+struct Table {
+    name: String,
+    bytes: Mmap,
+    kind: TableKind,
+    material: Material,
+    parts: Parts,
+    /// The offset of the DTZ values.
+    map: usize,
+}
+
+/// Reads the numbers of the table, failing on a truncated file.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    name: &'a str,
+}
+
+impl Reader<'_> {
+    fn slice<const N: usize>(&self, offset: usize) -> Result<[u8; N], TablebaseError> {
+        self.bytes
+            .get(offset..offset + N)
+            .and_then(|slice| slice.try_into().ok())
+            .ok_or_else(|| TablebaseError::InvalidTable(self.name.to_string()))
+    }
+
+    fn u8(&self, offset: usize) -> Result<u8, TablebaseError> {
+        Ok(self.slice::<1>(offset)?[0])
+    }
+
+    fn u16_le(&self, offset: usize) -> Result<u16, TablebaseError> {
+        Ok(u16::from_le_bytes(self.slice(offset)?))
+    }
+
+    fn u32_le(&self, offset: usize) -> Result<u32, TablebaseError> {
+        Ok(u32::from_le_bytes(self.slice(offset)?))
+    }
+
+    fn u32_be(&self, offset: usize) -> Result<u32, TablebaseError> {
+        Ok(u32::from_be_bytes(self.slice(offset)?))
+    }
+
+    fn u64_be(&self, offset: usize) -> Result<u64, TablebaseError> {
+        Ok(u64::from_be_bytes(self.slice(offset)?))
+    }
+}
+
+impl Table {
+    fn read(
+        name: &str,
+        bytes: Mmap,
+        kind: TableKind,
+        material: Material,
+    ) -> Result<Self, TablebaseError> {
+        let invalid = || TablebaseError::InvalidTable(name.to_string());
+        if bytes.get(0..4) != Some(&kind.magic()[..]) {
+            return Err(invalid());
+        }
+        let mut table = Self {
+            name: name.to_string(),
+            bytes,
+            kind,
+            material,
+            parts: Default::default(),
+            map: 0,
+        };
+        let end = table.read_parts()?;
+        if end > table.bytes.len() {
+            return Err(invalid());
+        }
+        Ok(table)
+    }
+
+    fn sides(&self) -> usize {
+        if self.kind == TableKind::Wdl && !self.material.symmetric {
+            2
+        } else {
+            1
+        }
+    }
+
+    fn files(&self) -> usize {
+        if self.material.has_pawns { 4 } else { 1 }
+    }
+
+    /// Reads the description of the parts of the table, and gives back the end of the table.
+    fn read_parts(&mut self) -> Result<usize, TablebaseError> {
+        let (parts, map, end) = self.read_parts_with(&self.reader())?;
+        self.parts = parts;
+        self.map = map;
+        Ok(end)
+    }
+
+    /// The parts of the table, the offset of the DTZ values and the end of the table.
+    // The parts are indexed by side and file, in the order of the file.
+    #[allow(clippy::needless_range_loop)]
+    fn read_parts_with(&self, reader: &Reader) -> Result<(Parts, usize, usize), TablebaseError> {
+        let (sides, files) = (self.sides(), self.files());
+        let material = &self.material;
+        let mut parts = Parts::default();
+        let mut map = 0;
+        let both_pawns = material.has_pawns && material.pawn_counts[1] > 0;
+
+        // The flags byte follows the magic number.
+        let mut data = 5;
+        for file in 0..files {
+            let first = reader.u8(data)?;
+            let second = if both_pawns {
+                reader.u8(data + 1)?
+            } else {
+                0xFF
+            };
+            let orders = [[first & 0xF, second & 0xF], [first >> 4, second >> 4]];
+            data += 1 + usize::from(both_pawns);
+            for index in 0..material.piece_count {
+                let byte = reader.u8(data)?;
+                for side in 0..sides {
+                    parts[side][file].pieces[index] =
+                        if side == 1 { byte >> 4 } else { byte & 0xF };
+                }
+                data += 1;
+            }
+            for (side, order) in orders.iter().enumerate().take(sides) {
+                set_groups(material, &mut parts[side][file], *order, file);
+            }
+        }
+        data += data & 1;
+
+        for file in 0..files {
+            for side in 0..sides {
+                data = set_sizes(&mut parts[side][file], reader, data)?;
+            }
+        }
+
+        if self.kind == TableKind::Dtz {
+            map = data;
+            for file in 0..files {
+                let part = &mut parts[0][file];
+                if part.flags & FLAG_MAPPED == 0 {
+                    continue;
+                }
+                if part.flags & FLAG_WIDE != 0 {
+                    data += data & 1;
+                    for index in 0..4 {
+                        part.map_index[index] = (data - map) / 2 + 1;
+                        data += 2 * usize::from(reader.u16_le(data)?) + 2;
+                    }
+                } else {
+                    for index in 0..4 {
+                        part.map_index[index] = data - map + 1;
+                        data += usize::from(reader.u8(data)?) + 1;
+                    }
+                }
+            }
+            data += data & 1;
+        }
+
+        for file in 0..files {
+            for side in 0..sides {
+                let part = &mut parts[side][file];
+                part.sparse_index = data;
+                data += part.sparse_index_size as usize * 6;
+            }
+        }
+        for file in 0..files {
+            for side in 0..sides {
+                let part = &mut parts[side][file];
+                part.block_lengths = data;
+                data += part.block_length_size as usize * 2;
+            }
+        }
+        for file in 0..files {
+            for side in 0..sides {
+                let part = &mut parts[side][file];
+                data = (data + 0x3F) & !0x3F;
+                part.data = data;
+                data += (part.block_count * part.block_size) as usize;
+            }
+        }
+        Ok((parts, map, data))
+    }
+
+    fn reader(&self) -> Reader<'_> {
+        Reader {
+            bytes: &self.bytes,
+            name: &self.name,
+        }
+    }
+
+    /// The value at the index, in the part of the table.
+    fn decompress(&self, part: &PairsData, index: u64) -> Result<i32, TablebaseError> {
+        if part.flags & FLAG_SINGLE_VALUE != 0 {
+            return Ok(i32::from(part.min_symbol_length));
+        }
+        let reader = self.reader();
+        let invalid = || TablebaseError::InvalidTable(self.name.clone());
+        let symbol_length = |symbol: usize| {
+            part.symbol_lengths
+                .get(symbol)
+                .map(|length| i64::from(*length))
+                .ok_or_else(invalid)
+        };
+        let btree = |symbol: usize| -> Result<(usize, usize), TablebaseError> {
+            let [first, second, third] = reader.slice::<3>(part.btree + 3 * symbol)?;
+            let left = (usize::from(second & 0xF) << 8) | usize::from(first);
+            let right = (usize::from(third) << 4) | usize::from(second >> 4);
+            Ok((left, right))
+        };
+
+        // The sparse index points to a block near the index, then the blocks are walked.
+        let sparse = (index / part.span) as usize;
+        let mut block = i64::from(reader.u32_le(part.sparse_index + 6 * sparse)?);
+        let mut offset = i64::from(reader.u16_le(part.sparse_index + 6 * sparse + 4)?);
+        offset += (index % part.span) as i64 - (part.span / 2) as i64;
+        let block_length = |block: i64| -> Result<i64, TablebaseError> {
+            let block = usize::try_from(block).map_err(|_| invalid())?;
+            Ok(i64::from(reader.u16_le(part.block_lengths + 2 * block)?))
+        };
+        while offset < 0 {
+            block -= 1;
+            offset += block_length(block)? + 1;
+        }
+        while offset > block_length(block)? {
+            offset -= block_length(block)? + 1;
+            block += 1;
+        }
+
+        // The block is a sequence of canonical Huffman codes, each one standing for several
+        // values.
+        let mut pointer = part.data + (block as u64 * part.block_size) as usize;
+        let mut buffer = reader.u64_be(pointer)?;
+        pointer += 8;
+        let mut buffer_size = 64;
+        let min_length = usize::from(part.min_symbol_length);
+        let mut symbol;
+        loop {
+            let mut length = 0;
+            while length + 1 < part.base64.len() && buffer < part.base64[length] {
+                length += 1;
+            }
+            symbol = (buffer - part.base64[length])
+                .checked_shr((64 - length - min_length) as u32)
+                .unwrap_or(0) as usize;
+            symbol += usize::from(reader.u16_le(part.lowest_symbols + 2 * length)?);
+            if offset < symbol_length(symbol)? + 1 {
+                break;
+            }
+            offset -= symbol_length(symbol)? + 1;
+            length += min_length;
+            buffer <<= length;
+            buffer_size -= length;
+            if buffer_size <= 32 {
+                buffer_size += 32;
+                buffer |= u64::from(reader.u32_be(pointer)?) << (64 - buffer_size);
+                pointer += 4;
+            }
+        }
+
+        // The symbol expands into pairs of symbols, down to the value.
+        while symbol_length(symbol)? != 0 {
+            let (left, right) = btree(symbol)?;
+            if offset < symbol_length(left)? + 1 {
+                symbol = left;
+            } else {
+                offset -= symbol_length(left)? + 1;
+                symbol = right;
+            }
+        }
+        Ok(btree(symbol)?.0 as i32)
+    }
+
+    /// The DTZ in plies, from the raw value of the table.
+    fn dtz_value(&self, file: usize, value: i32, wdl: Wdl) -> Result<i32, TablebaseError> {
+        let part = &self.parts[0][file];
+        let mut value = value;
+        if part.flags & FLAG_MAPPED != 0 {
+            let result_index = match wdl {
+                Wdl::Loss => 1,
+                Wdl::BlessedLoss => 3,
+                Wdl::Draw | Wdl::Win => 0,
+                Wdl::CursedWin => 2,
+            };
+            let index = part.map_index[result_index] + value as usize;
+            let reader = self.reader();
+            value = if part.flags & FLAG_WIDE != 0 {
+                i32::from(reader.u16_le(self.map + 2 * index)?)
+            } else {
+                i32::from(reader.u8(self.map + index)?)
+            };
+        }
+        let in_moves = match wdl {
+            Wdl::Win => part.flags & FLAG_WIN_PLIES == 0,
+            Wdl::Loss => part.flags & FLAG_LOSS_PLIES == 0,
+            Wdl::CursedWin | Wdl::BlessedLoss => true,
+            Wdl::Draw => false,
+        };
+        Ok(if in_moves { 2 * value + 1 } else { value + 1 })
+    }
+
+    /// Probes the position, whose pieces the table covers. The colors are swapped when the
+    /// table stores the position with Black as White.
+    fn probe(
+        &self,
+        position: &Board,
+        swap_colors: bool,
+        wdl: Wdl,
+    ) -> Result<TableValue, TablebaseError> {
+        let Some((part_side, file, index)) = self.locate(position, swap_colors) else {
+            return Ok(TableValue::OtherSideToMove);
+        };
+        let value = self.decompress(&self.parts[part_side][file], index)?;
+        Ok(TableValue::Value(match self.kind {
+            TableKind::Wdl => value - 2,
+            TableKind::Dtz => self.dtz_value(file, value, wdl)?,
+        }))
+    }
+
+    /// Where the value of the position is stored : the side and the file of the part, and the
+    /// index in the part. None when the DTZ table stores the positions with the other side to
+    /// move.
+    fn locate(&self, position: &Board, swap_colors: bool) -> Option<(usize, usize, u64)> {
+        let maps = &*MAPS;
+        let material = &self.material;
+        // The symmetric tables only store the positions with White to move.
+        let flip = swap_colors || (material.symmetric && position.side() == Color::Black);
+        let flip_color = if flip { 8 } else { 0 };
+        let flip_squares = if flip { 56 } else { 0 };
+        let side_to_move = usize::from(flip) ^ usize::from(position.side() == Color::Black);
+
+        let board_pieces: Vec<(usize, u8)> = Coord::iter()
+            .filter_map(|coord| {
+                let cell = position.get(coord);
+                Some((square(coord), piece_code(cell.piece()?, cell.color()?)))
+            })
+            .collect();
+        let mut squares = [0usize; MAX_PIECES];
+        let mut pieces = [0u8; MAX_PIECES];
+        let mut size = 0;
+        let mut lead_pawn = None;
+        let mut file = 0;
+
+        // The tables with pawns have a part for each file of the leading pawn, the nearest
+        // to the edge.
+        if material.has_pawns {
+            let pawn = self.parts[0][0].pieces[0] ^ flip_color;
+            for (board_square, _) in board_pieces.iter().filter(|(_, code)| *code == pawn) {
+                squares[size] = board_square ^ flip_squares;
+                size += 1;
+            }
+            let leading = (0..size)
+                .rev()
+                .max_by_key(|index| maps.pawns[squares[*index]])
+                .unwrap_or(0);
+            squares.swap(0, leading);
+            file = file_of(squares[0]).min(7 - file_of(squares[0]));
+            lead_pawn = Some(pawn);
+        }
+        let lead_pawns = size;
+
+        // The DTZ tables store a single side to move, but for the symmetric ones without pawns.
+        if self.kind == TableKind::Dtz
+            && usize::from(self.parts[0][file].flags & FLAG_SIDE_TO_MOVE) != side_to_move
+            && (material.has_pawns || !material.symmetric)
+        {
+            return None;
+        }
+
+        for (board_square, code) in &board_pieces {
+            if Some(*code) == lead_pawn {
+                continue;
+            }
+            squares[size] = board_square ^ flip_squares;
+            pieces[size] = code ^ flip_color;
+            size += 1;
+        }
+
+        let part_side = if self.kind == TableKind::Wdl {
+            side_to_move
+        } else {
+            0
+        };
+        let part = &self.parts[part_side][file];
+
+        // The pieces are sorted in the order of the table.
+        for i in lead_pawns..size.saturating_sub(1) {
+            for j in i + 1..size {
+                if part.pieces[i] == pieces[j] {
+                    pieces.swap(i, j);
+                    squares.swap(i, j);
+                    break;
+                }
+            }
+        }
+
+        // The leading piece is brought to the a1-d8 half of the board.
+        if file_of(squares[0]) > 3 {
+            for square in &mut squares[..size] {
+                *square ^= 7;
+            }
+        }
+
+        let mut index;
+        if material.has_pawns {
+            index = maps.lead_pawn_index[lead_pawns][squares[0]];
+            squares[1..lead_pawns].sort_by_key(|square| maps.pawns[*square]);
+            for (i, square) in squares.iter().enumerate().take(lead_pawns).skip(1) {
+                index += maps.binomial[i][maps.pawns[*square]];
+            }
+        } else {
+            // Without pawns, the leading piece is brought to the a1-d1-d4 triangle.
+            if rank_of(squares[0]) > 3 {
+                for square in &mut squares[..size] {
+                    *square ^= 56;
+                }
+            }
+            for i in 0..part.group_len[0] {
+                let off = off_diagonal(squares[i]);
+                if off == 0 {
+                    continue;
+                }
+                if off > 0 {
+                    for square in &mut squares[i..size] {
+                        *square = ((*square >> 3) | (*square << 3)) & 63;
+                    }
+                }
+                break;
+            }
+
+            let [first, second, third] = [squares[0], squares[1], squares[2]];
+            if material.has_unique_pieces {
+                let adjust1 = u64::from(second > first);
+                let adjust2 = u64::from(third > first) + u64::from(third > second);
+                let (first, second, third) = (first as u64, second as u64, third as u64);
+                index = if off_diagonal(squares[0]) != 0 {
+                    (maps.a1d1d4[squares[0]] * 63 + (second - adjust1)) * 62 + third - adjust2
+                } else if off_diagonal(squares[1]) != 0 {
+                    (6 * 63 + (first >> 3) * 28 + maps.b1h1h7[squares[1]]) * 62 + third - adjust2
+                } else if off_diagonal(squares[2]) != 0 {
+                    6 * 63 * 62
+                        + 4 * 28 * 62
+                        + (first >> 3) * 7 * 28
+                        + ((second >> 3) - adjust1) * 28
+                        + maps.b1h1h7[squares[2]]
+                } else {
+                    6 * 63 * 62
+                        + 4 * 28 * 62
+                        + 4 * 7 * 28
+                        + (first >> 3) * 7 * 6
+                        + ((second >> 3) - adjust1) * 6
+                        + ((third >> 3) - adjust2)
+                };
+            } else {
+                index = maps.kk[maps.a1d1d4[first] as usize][second];
+            }
+        }
+
+        // The other groups of pieces, each one on the squares left by the previous groups.
+        index *= part.group_index[0];
+        let mut group_start = part.group_len[0];
+        let mut remaining_pawns = material.has_pawns && material.pawn_counts[1] > 0;
+        let mut next = 1;
+        while part.group_len[next] != 0 {
+            let group_len = part.group_len[next];
+            squares[group_start..group_start + group_len].sort_unstable();
+            let mut group_index = 0;
+            for i in 0..group_len {
+                let square = squares[group_start + i];
+                let adjust = squares[..group_start]
+                    .iter()
+                    .filter(|previous| square > **previous)
+                    .count();
+                let pawn_shift = if remaining_pawns { 8 } else { 0 };
+                group_index += maps.binomial[i + 1][square - adjust - pawn_shift];
+            }
+            remaining_pawns = false;
+            index += group_index * part.group_index[next];
+            group_start += group_len;
+            next += 1;
+        }
+
+        Some((part_side, file, index))
+    }
+}
+
+/// Groups the pieces encoded together, and computes the factor of each group in the index.
+fn set_groups(material: &Material, part: &mut PairsData, order: [u8; 2], file: usize) {
+    let maps = &*MAPS;
+    let mut first_len: i32 = if material.has_pawns {
+        0
+    } else if material.has_unique_pieces {
+        3
+    } else {
+        2
+    };
+    let mut n = 0;
+    part.group_len[0] = 1;
+    for i in 1..material.piece_count {
+        first_len -= 1;
+        if first_len > 0 || part.pieces[i] == part.pieces[i - 1] {
+            part.group_len[n] += 1;
+        } else {
+            n += 1;
+            part.group_len[n] = 1;
+        }
+    }
+    n += 1;
+    part.group_len[n] = 0;
+
+    let both_pawns = material.has_pawns && material.pawn_counts[1] > 0;
+    let mut next = if both_pawns { 2 } else { 1 };
+    let mut free_squares = 64 - part.group_len[0] - if both_pawns { part.group_len[1] } else { 0 };
+    let mut index = 1u64;
+    let mut k = 0;
+    while next < n || k == order[0] || k == order[1] {
+        if k == order[0] {
+            part.group_index[0] = index;
+            index *= if material.has_pawns {
+                maps.lead_pawns_size[part.group_len[0]][file]
+            } else if material.has_unique_pieces {
+                31332
+            } else {
+                462
+            };
+        } else if k == order[1] {
+            part.group_index[1] = index;
+            index *= maps.binomial[part.group_len[1]][48 - part.group_len[0]];
+        } else {
+            part.group_index[next] = index;
+            index *= maps.binomial[part.group_len[next]][free_squares];
+            free_squares -= part.group_len[next];
+            next += 1;
+        }
+        k += 1;
+    }
+    part.group_index[n] = index;
+}
+
+/// Reads the sizes and the Huffman tables of a part, and gives back the offset following them.
+fn set_sizes(part: &mut PairsData, reader: &Reader, data: usize) -> Result<usize, TablebaseError> {
+    let mut data = data;
+    part.flags = reader.u8(data)?;
+    data += 1;
+    if part.flags & FLAG_SINGLE_VALUE != 0 {
+        part.min_symbol_length = reader.u8(data)?;
+        return Ok(data + 1);
+    }
+
+    let group_count = part.group_len.iter().position(|len| *len == 0).unwrap_or(0);
+    let table_size = part.group_index[group_count];
+    part.block_size = 1 << reader.u8(data)?;
+    part.span = 1 << reader.u8(data + 1)?;
+    part.sparse_index_size = table_size.div_ceil(part.span);
+    let padding = u64::from(reader.u8(data + 2)?);
+    part.block_count = u64::from(reader.u32_le(data + 3)?);
+    part.block_length_size = part.block_count + padding;
+    let max_symbol_length = reader.u8(data + 7)?;
+    part.min_symbol_length = reader.u8(data + 8)?;
+    data += 9;
+    if max_symbol_length < part.min_symbol_length {
+        return Err(TablebaseError::InvalidTable(reader.name.to_string()));
+    }
+    part.lowest_symbols = data;
+
+    let lengths = usize::from(max_symbol_length - part.min_symbol_length) + 1;
+    part.base64 = vec![0; lengths];
+    for i in (0..lengths - 1).rev() {
+        let lowest = u64::from(reader.u16_le(data + 2 * i)?);
+        let next_lowest = u64::from(reader.u16_le(data + 2 * i + 2)?);
+        part.base64[i] = part.base64[i + 1]
+            .wrapping_add(lowest)
+            .wrapping_sub(next_lowest)
+            / 2;
+    }
+    for (i, base) in part.base64.iter_mut().enumerate() {
+        let shift = 64 - i as u32 - u32::from(part.min_symbol_length);
+        *base = base.checked_shl(shift).unwrap_or(0);
+    }
+    data += 2 * lengths;
+
+    let symbol_count = usize::from(reader.u16_le(data)?);
+    data += 2;
+    part.btree = data;
+    part.symbol_lengths = vec![0; symbol_count];
+    let mut visited = vec![false; symbol_count];
+    for symbol in 0..symbol_count {
+        if !visited[symbol] {
+            part.symbol_lengths[symbol] = symbol_length(part, reader, symbol, &mut visited)?;
+        }
+    }
+    Ok(data + 3 * symbol_count + (symbol_count & 1))
+}
+
+/// The number of values of a symbol, minus one, found by expanding its pairs.
+fn symbol_length(
+    part: &mut PairsData,
+    reader: &Reader,
+    symbol: usize,
+    visited: &mut [bool],
+) -> Result<u8, TablebaseError> {
+    visited[symbol] = true;
+    let [first, second, third] = reader.slice::<3>(part.btree + 3 * symbol)?;
+    let right = (usize::from(third) << 4) | usize::from(second >> 4);
+    if right == 0xFFF {
+        return Ok(0);
+    }
+    let left = (usize::from(second & 0xF) << 8) | usize::from(first);
+    if left >= visited.len() || right >= visited.len() {
+        return Err(TablebaseError::InvalidTable(reader.name.to_string()));
+    }
+    for child in [left, right] {
+        if !visited[child] {
+            part.symbol_lengths[child] = symbol_length(part, reader, child, visited)?;
+        }
+    }
+    Ok(part.symbol_lengths[left]
+        .wrapping_add(part.symbol_lengths[right])
+        .wrapping_add(1))
+}
+
+/// The pieces of a side, as written in the names of the tables.
+fn side_name(position: &Board, color: Color) -> String {
+    let mut name = String::from("K");
+    for (piece, letter) in PIECE_ORDER {
+        let count = Coord::iter()
+            .filter(|coord| {
+                let cell = position.get(*coord);
+                cell.piece() == Some(piece) && cell.color() == Some(color)
+            })
+            .count();
+        name.extend(std::iter::repeat_n(letter, count));
+    }
+    name
+}
+
+fn is_zeroing(position: &Board, chess_move: &Move) -> bool {
+    chess_move.src_cell().piece() == Some(Piece::Pawn)
+        || position.get(chess_move.dst()).is_occupied()
+        || chess_move.kind() == MoveKind::Enpassant
+}
+
+fn is_capture(position: &Board, chess_move: &Move) -> bool {
+    position.get(chess_move.dst()).is_occupied() || chess_move.kind() == MoveKind::Enpassant
+}
+
+fn is_mate(position: &Board) -> bool {
+    position.is_check() && !position.has_legal_moves()
+}
+
+/// The tables mapped, by name and kind.
+type TableCache = HashMap<(String, TableKind), Arc<Table>>;
+
+/// The tables of a directory, mapped when first probed. The clones share the tables, so that
+/// the probes can run in the background.
+#[derive(Clone)]
+pub struct Tablebases {
+    directory: PathBuf,
+    /// The names of the tables found, by kind.
+    names: Arc<HashMap<TableKind, HashSet<String>>>,
+    max_pieces: usize,
+    tables: Arc<Mutex<TableCache>>,
+}
+
+impl Tablebases {
+    pub fn open(directory: &Path) -> Result<Self, TablebaseError> {
+        let mut names: HashMap<TableKind, HashSet<String>> = HashMap::new();
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            let (Some(stem), Some(extension)) = (path.file_stem(), path.extension()) else {
+                continue;
+            };
+            let kind = match extension.to_string_lossy().as_ref() {
+                "rtbw" => TableKind::Wdl,
+                "rtbz" => TableKind::Dtz,
+                _ => continue,
+            };
+            names
+                .entry(kind)
+                .or_default()
+                .insert(stem.to_string_lossy().to_string());
+        }
+        let max_pieces = names
+            .get(&TableKind::Wdl)
+            .into_iter()
+            .flatten()
+            .map(|name| name.len() - 1)
+            .max()
+            .unwrap_or(0)
+            .min(MAX_PIECES);
+        if max_pieces == 0 {
+            return Err(TablebaseError::NoTables(directory.to_path_buf()));
+        }
+        Ok(Self {
+            directory: directory.to_path_buf(),
+            names: Arc::new(names),
+            max_pieces,
+            tables: Arc::default(),
+        })
+    }
+
+    /// Whether the position has few enough pieces to be probed.
+    pub fn covers(&self, position: &Board) -> bool {
+        let pieces = Coord::iter()
+            .filter(|coord| position.get(*coord).is_occupied())
+            .count();
+        pieces <= self.max_pieces && position.raw().castling == CastlingRights::EMPTY
+    }
+
+    /// Probes the table of the position, mapping it first if needed.
+    fn probe_table(
+        &self,
+        position: &Board,
+        kind: TableKind,
+        wdl: Wdl,
+    ) -> Result<TableValue, TablebaseError> {
+        let (white, black) = (
+            side_name(position, Color::White),
+            side_name(position, Color::Black),
+        );
+        if white == "K" && black == "K" {
+            return Ok(TableValue::Value(0));
+        }
+        let names = self.names.get(&kind);
+        let has_table = |name: &String| names.is_some_and(|names| names.contains(name));
+        let (name, swap_colors) = [
+            (format!("{white}v{black}"), false),
+            (format!("{black}v{white}"), true),
+        ]
+        .into_iter()
+        .find(|(name, _)| has_table(name))
+        .ok_or_else(|| TablebaseError::MissingTable(format!("{white}v{black}")))?;
+        let table = self.table(name, kind)?;
+        table.probe(position, swap_colors, wdl)
+    }
+
+    /// The table of the name, mapped from its file when first probed.
+    fn table(&self, name: String, kind: TableKind) -> Result<Arc<Table>, TablebaseError> {
+        let mut tables = self.tables.lock().unwrap_or_else(|err| err.into_inner());
+        let key = (name, kind);
+        if let Some(table) = tables.get(&key) {
+            return Ok(Arc::clone(table));
+        }
+        let name = &key.0;
+        let file = File::open(self.directory.join(format!("{name}.{}", kind.extension())))?;
+        // SAFETY: the tables are only read, and are not expected to change while mapped.
+        let bytes = unsafe { Mmap::map(&file)? };
+        let material =
+            Material::from_name(name).ok_or_else(|| TablebaseError::InvalidTable(name.clone()))?;
+        let table = Arc::new(Table::read(name, bytes, kind, material)?);
+        tables.insert(key, Arc::clone(&table));
+        Ok(table)
+    }
+
+    /// The result of the position, searching the captures first since the tables do not store
+    /// the positions they decide reliably. Also tells whether the best move is a capture or a
+    /// pawn move, which the DTZ tables do not store either.
+    fn search(&self, position: &Board, check_zeroing: bool) -> Result<(Wdl, bool), TablebaseError> {
+        let moves = legal::gen_all(position);
+        let mut best = Wdl::Loss;
+        let mut searched = 0;
+        for chess_move in moves.iter() {
+            if !is_capture(position, chess_move)
+                && (!check_zeroing || chess_move.src_cell().piece() != Some(Piece::Pawn))
+            {
+                continue;
+            }
+            searched += 1;
+            let Ok(next) = position.make_move(*chess_move) else {
+                continue;
+            };
+            let value = self.search(&next, false)?.0.opposite();
+            if value > best {
+                best = value;
+                if value >= Wdl::Win {
+                    return Ok((value, true));
+                }
+            }
+        }
+
+        let no_more_moves = searched > 0 && searched == moves.len();
+        let value = if no_more_moves {
+            best
+        } else {
+            match self.probe_table(position, TableKind::Wdl, Wdl::Draw)? {
+                TableValue::Value(value) => Wdl::from_value(value),
+                TableValue::OtherSideToMove => Wdl::Draw,
+            }
+        };
+        if best >= value {
+            return Ok((best, best > Wdl::Draw || no_more_moves));
+        }
+        Ok((value, false))
+    }
+
+    fn wdl(&self, position: &Board) -> Result<Wdl, TablebaseError> {
+        Ok(self.search(position, false)?.0)
+    }
+
+    /// The DTZ of the position : positive when the side to move wins, negative when it loses.
+    fn dtz(&self, position: &Board) -> Result<i32, TablebaseError> {
+        let (wdl, zeroing_best_move) = self.search(position, true)?;
+        if wdl == Wdl::Draw {
+            return Ok(0);
+        }
+        if zeroing_best_move {
+            return Ok(dtz_before_zeroing(wdl));
+        }
+        if let TableValue::Value(dtz) = self.probe_table(position, TableKind::Dtz, wdl)? {
+            let fifty_moves = if matches!(wdl, Wdl::CursedWin | Wdl::BlessedLoss) {
+                100
+            } else {
+                0
+            };
+            return Ok((dtz + fifty_moves) * wdl.signum());
+        }
+
+        // The table stores the positions with the other side to move : the best move is the
+        // one leading to the best of them.
+        let mut min_dtz = i32::MAX;
+        for chess_move in legal::gen_all(position).iter() {
+            let zeroing = is_zeroing(position, chess_move);
+            let Ok(next) = position.make_move(*chess_move) else {
+                continue;
+            };
+            let mut dtz = if zeroing {
+                -dtz_before_zeroing(self.wdl(&next)?)
+            } else {
+                -self.dtz(&next)?
+            };
+            if dtz == 1 && is_mate(&next) {
+                min_dtz = 1;
+            }
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+            if dtz < min_dtz && dtz.signum() == wdl.signum() {
+                min_dtz = dtz;
+            }
+        }
+        Ok(if min_dtz == i32::MAX { -1 } else { min_dtz })
+    }
+
+    /// The result of the position and of its moves, the best one first.
+    pub fn probe(&self, position: &Board) -> Result<PositionProbe, TablebaseError> {
+        if position.raw().castling != CastlingRights::EMPTY {
+            return Err(TablebaseError::CastlingRights);
+        }
+        if !self.covers(position) {
+            return Err(TablebaseError::TooManyPieces);
+        }
+        let halfmoves = i32::from(position.raw().move_counter);
+        let mut moves = Vec::new();
+        for chess_move in legal::gen_all(position).iter() {
+            let Ok(next) = position.make_move(*chess_move) else {
+                continue;
+            };
+            let wdl = self.wdl(&next)?.opposite();
+            let mut dtz = if is_zeroing(position, chess_move) {
+                dtz_before_zeroing(wdl)
+            } else {
+                let dtz = -self.dtz(&next)?;
+                dtz + dtz.signum()
+            };
+            if dtz == 2 && is_mate(&next) {
+                dtz = 1;
+            }
+            // The wins beyond the fifty-move rule are ranked below the sure wins, and the losses
+            // within it below the others.
+            let rank = match dtz {
+                1.. if dtz + halfmoves <= 99 => MAX_RANK,
+                1.. => MAX_RANK - (dtz + halfmoves),
+                ..0 if -dtz * 2 + halfmoves < 100 => -MAX_RANK,
+                ..0 => -MAX_RANK + (-dtz + halfmoves),
+                0 => 0,
+            };
+            moves.push(MoveProbe {
+                chess_move: *chess_move,
+                wdl,
+                dtz,
+                rank,
+            });
+        }
+        // The quickest win, or the longest resistance.
+        moves.sort_by_key(|probe| (std::cmp::Reverse(probe.rank), probe.dtz));
+        Ok(PositionProbe {
+            wdl: self.wdl(position)?,
+            dtz: self.dtz(position)?,
+            moves,
+        })
+    }
+}
+
+#[cfg(test)]
+mod fixtures;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory of tables, written for the test.
+    fn directory(name: &str, tables: &[(&str, &[u8])]) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("syzygy_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        for (file_name, bytes) in tables {
+            fs::write(directory.join(file_name), bytes).unwrap();
+        }
+        directory
+    }
+
+    fn board(fen: &str) -> Board {
+        Board::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn index_maps() {
+        let maps = &*MAPS;
+        // The triangle is numbered first, then its diagonal.
+        let mut triangle: Vec<_> = (0..64)
+            .filter(|square| file_of(*square) <= 3 && off_diagonal(*square) <= 0)
+            .filter(|square| rank_of(*square) <= 3)
+            .map(|square| maps.a1d1d4[square])
+            .collect();
+        triangle.sort();
+        assert_eq!(triangle, (0..10).collect::<Vec<_>>());
+        assert_eq!(maps.a1d1d4[0], 6);
+        assert_eq!(maps.b1h1h7.iter().max(), Some(&27));
+        // The placements of two kings.
+        assert_eq!(maps.kk.iter().flatten().max(), Some(&461));
+        assert_eq!(maps.binomial[2][5], 10);
+        assert_eq!(maps.binomial[3][6], 20);
+    }
+
+    #[test]
+    fn material_of_the_names() {
+        let material = Material::from_name("KRPvKR").unwrap();
+        assert!(material.has_pawns && material.has_unique_pieces && !material.symmetric);
+        assert_eq!((material.pawn_counts, material.piece_count), ([1, 0], 5));
+        let material = Material::from_name("KPvKPP").unwrap();
+        assert_eq!(material.pawn_counts, [1, 2]);
+        let material = Material::from_name("KNNvKNN").unwrap();
+        assert!(!material.has_pawns && !material.has_unique_pieces && material.symmetric);
+        assert!(Material::from_name("KQK").is_none());
+    }
+
+    #[test]
+    fn opens_a_directory() {
+        let path = directory("open", &[("KQvK.rtbw", b""), ("KQvK.rtbz", b"")]);
+        let tablebases = Tablebases::open(&path).unwrap();
+        assert_eq!(tablebases.max_pieces, 3);
+        assert!(tablebases.covers(&board("8/8/8/4k3/8/8/8/3QK3 w - - 0 1")));
+        assert!(!tablebases.covers(&board("8/8/8/4k3/8/8/8/2RQK3 w - - 0 1")));
+
+        let empty = directory("empty", &[("notes.txt", b"")]);
+        assert!(matches!(
+            Tablebases::open(&empty),
+            Err(TablebaseError::NoTables(_))
+        ));
+    }
+
+    #[test]
+    fn bare_kings_are_drawn() {
+        let path = directory("bare_kings", &[("KQvK.rtbw", b"")]);
+        let probe = Tablebases::open(&path)
+            .unwrap()
+            .probe(&board("8/8/8/4k3/8/8/8/4K3 w - - 0 1"))
+            .unwrap();
+        assert_eq!((probe.wdl, probe.dtz), (Wdl::Draw, 0));
+        assert_eq!(probe.moves.len(), 5);
+        assert!(probe.moves.iter().all(|probe| probe.wdl == Wdl::Draw));
+    }
+
+    #[test]
+    fn positions_out_of_the_tables() {
+        let path = directory("out_of_tables", &[("KQvK.rtbw", b"")]);
+        let tablebases = Tablebases::open(&path).unwrap();
+        assert!(matches!(
+            tablebases.probe(&Board::initial()),
+            Err(TablebaseError::CastlingRights)
+        ));
+        assert!(matches!(
+            tablebases.probe(&board("4k3/8/8/8/8/8/8/2RQK3 w - - 0 1")),
+            Err(TablebaseError::TooManyPieces)
+        ));
+        assert!(matches!(
+            tablebases.probe(&board("4k3/8/8/8/8/8/8/3RK3 w - - 0 1")),
+            Err(TablebaseError::MissingTable(name)) if name == "KRvK"
+        ));
+    }
+
+    #[test]
+    fn invalid_tables() {
+        let path = directory("invalid", &[("KQvK.rtbw", b"not a table")]);
+        let tablebases = Tablebases::open(&path).unwrap();
+        // The tables of Black are those of White with the colors swapped.
+        assert!(matches!(
+            tablebases.probe(&board("4k3/8/8/8/8/8/q7/4K3 b - - 0 1")),
+            Err(TablebaseError::InvalidTable(name)) if name == "KQvK"
+        ));
+
+        // A table cut after its magic number.
+        let mut truncated = WDL_MAGIC.to_vec();
+        truncated.push(0);
+        let path = directory("truncated", &[("KQvK.rtbw", &truncated)]);
+        assert!(matches!(
+            Tablebases::open(&path)
+                .unwrap()
+                .probe(&board("4k3/8/8/8/8/8/8/3QK3 w - - 0 1")),
+            Err(TablebaseError::InvalidTable(_))
+        ));
+    }
+
+    /// The KQvK, KRvK and KNvK tables written for the tests.
+    fn fixture_tables() -> Tablebases {
+        Tablebases::open(&fixtures::directory()).unwrap()
+    }
+
+    #[test]
+    fn probes_mates() {
+        let tablebases = fixture_tables();
+        let probe = tablebases
+            .probe(&board("k7/8/1K6/8/8/8/8/7R w - - 0 1"))
+            .unwrap();
+        assert_eq!((probe.wdl, probe.dtz), (Wdl::Win, 1));
+        assert_eq!(probe.moves[0].chess_move.to_string(), "h1h8");
+        assert_eq!((probe.moves[0].wdl, probe.moves[0].dtz), (Wdl::Win, 1));
+
+        // Kb8 is forced, then Rh8 mates.
+        let probe = tablebases
+            .probe(&board("k7/8/1K6/8/8/8/8/7R b - - 0 1"))
+            .unwrap();
+        assert_eq!((probe.wdl, probe.dtz), (Wdl::Loss, -2));
+        assert_eq!(probe.moves.len(), 1);
+        assert_eq!(probe.moves[0].chess_move.to_string(), "a8b8");
+
+        // Kb6 first, as no check mates at once.
+        let probe = tablebases
+            .probe(&board("k7/8/2K5/8/8/8/8/7R w - - 0 1"))
+            .unwrap();
+        assert_eq!((probe.wdl, probe.dtz), (Wdl::Win, 3));
+        assert_eq!(probe.moves[0].dtz, 3);
+
+        let probe = tablebases
+            .probe(&board("k7/8/1K6/8/8/8/8/6Q1 w - - 0 1"))
+            .unwrap();
+        assert_eq!((probe.wdl, probe.dtz), (Wdl::Win, 1));
+        assert_eq!(probe.moves[0].dtz, 1);
+    }
+
+    #[test]
+    fn probes_draws() {
+        let tablebases = fixture_tables();
+        // Stalemate.
+        let probe = tablebases
+            .probe(&board("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"))
+            .unwrap();
+        assert_eq!((probe.wdl, probe.dtz), (Wdl::Draw, 0));
+        assert!(probe.moves.is_empty());
+
+        // The rook left alone is taken.
+        let probe = tablebases
+            .probe(&board("8/8/8/8/8/8/1R6/k3K3 b - - 0 1"))
+            .unwrap();
+        assert_eq!((probe.wdl, probe.dtz), (Wdl::Draw, 0));
+        assert_eq!(probe.moves[0].chess_move.to_string(), "a1b2");
+
+        let probe = tablebases
+            .probe(&board("k7/8/1K6/8/8/8/8/7N w - - 0 1"))
+            .unwrap();
+        assert_eq!((probe.wdl, probe.dtz), (Wdl::Draw, 0));
+    }
+
+    #[test]
+    fn probes_with_swapped_colors() {
+        let tablebases = fixture_tables();
+        // White is mated by the black queen.
+        let probe = tablebases
+            .probe(&board("8/8/8/8/8/2k5/1q6/K7 w - - 0 1"))
+            .unwrap();
+        assert_eq!((probe.wdl, probe.dtz), (Wdl::Loss, -1));
+        assert!(probe.moves.is_empty());
+
+        let probe = tablebases
+            .probe(&board("8/8/8/8/8/2k5/7r/K7 b - - 0 1"))
+            .unwrap();
+        assert_eq!(probe.wdl, Wdl::Win);
+        assert!(probe.dtz > 0 && probe.dtz % 2 == 1);
+        assert_eq!(probe.moves[0].dtz, probe.dtz);
+
+        // The clones share the tables mapped.
+        let clone = tablebases.clone();
+        clone
+            .probe(&board("8/8/8/3k4/8/8/8/R3K3 w - - 0 1"))
+            .unwrap();
+        assert!(tablebases.tables.lock().unwrap().len() >= 4);
+    }
+
+    #[test]
+    fn longest_wins_fit_the_known_maximum() {
+        let tablebases = fixture_tables();
+        // No mate takes longer than 10 moves with the queen, 16 moves with the rook.
+        for (fen, max_dtz) in [
+            ("8/8/8/3k4/8/8/8/Q3K3 w - - 0 1", 19),
+            ("8/8/8/3k4/8/8/8/R3K3 w - - 0 1", 31),
+        ] {
+            let probe = tablebases.probe(&board(fen)).unwrap();
+            assert_eq!(probe.wdl, Wdl::Win, "{fen}");
+            assert!(
+                probe.dtz > 1 && probe.dtz <= max_dtz,
+                "{fen}: {}",
+                probe.dtz
+            );
+            // The best move leads to a loss one ply shorter.
+            let next = board(fen).make_move(probe.moves[0].chess_move).unwrap();
+            let next_probe = tablebases.probe(&next).unwrap();
+            assert_eq!(next_probe.wdl, Wdl::Loss);
+            assert_eq!(next_probe.dtz, 1 - probe.dtz);
+        }
+    }
+}
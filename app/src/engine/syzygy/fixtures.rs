@@ -0,0 +1,474 @@
+//! Writes the tables of the tests : KQvK, KRvK and KNvK in the Syzygy format, solved by a
+//! retrograde analysis of every position.
+//!
+//! The values are placed at the indices computed by [`Table::locate`], then compressed as the
+//! generator of the tablebases does : canonical Huffman codes in blocks, with a sparse index.
+//! Each symbol stands for a single value, which the format allows.
+//!
+//! The tables are committed next to this file. To write them again :
+//! `cargo test --release -p chess_against_engine_rust -- --ignored write_fixtures`.
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use memmap2::Mmap;
+use owlchess::{Board, Cell, Color, Coord, Piece, RawBoard, movegen::legal};
+
+use super::{
+    FLAG_LOSS_PLIES, FLAG_SINGLE_VALUE, FLAG_WIN_PLIES, Material, Table, TableKind, piece_code,
+};
+
+/// The directory of the tables written for the tests.
+pub fn directory() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("src/engine/syzygy")
+}
+
+/// The size of the blocks, as a power of two.
+const BLOCK_SIZE_LOG: u8 = 6;
+
+/// The number of values between the entries of the sparse index, as a power of two.
+const SPAN_LOG: u8 = 10;
+
+/// The result of a position for the side to move, with the plies to the mate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Solved {
+    Unknown,
+    Win(u16),
+    /// Lost, 0 when mated.
+    Loss(u16),
+    Draw,
+}
+
+/// A position of the table : the squares of the white king, of the white piece and of the
+/// black king, and the side to move.
+fn position_key(side: Color, squares: [usize; 3]) -> usize {
+    let side = match side {
+        Color::White => 0,
+        Color::Black => 1,
+    };
+    ((side * 64 + squares[0]) * 64 + squares[1]) * 64 + squares[2]
+}
+
+fn position_board(side: Color, squares: [usize; 3], piece: Piece) -> Option<Board> {
+    if squares[0] == squares[1] || squares[0] == squares[2] || squares[1] == squares[2] {
+        return None;
+    }
+    let mut raw_board = RawBoard::empty();
+    let cells = [
+        Cell::from_parts(Color::White, Piece::King),
+        Cell::from_parts(Color::White, piece),
+        Cell::from_parts(Color::Black, Piece::King),
+    ];
+    for (square, cell) in squares.into_iter().zip(cells) {
+        raw_board.put(Coord::from_index(square), cell);
+    }
+    raw_board.side = side;
+    Board::try_from(&raw_board).ok()
+}
+
+/// The key of the position, None when the white piece has been taken.
+fn board_key(board: &Board, piece: Piece) -> Option<usize> {
+    let find = |color, piece| {
+        Coord::iter()
+            .find(|coord| board.get(*coord) == Cell::from_parts(color, piece))
+            .map(|coord| coord.index())
+    };
+    let squares = [
+        find(Color::White, Piece::King)?,
+        find(Color::White, piece)?,
+        find(Color::Black, Piece::King)?,
+    ];
+    Some(position_key(board.side(), squares))
+}
+
+/// Solves every position with a king and the piece against a king.
+fn solve(piece: Piece) -> Vec<(Board, Solved)> {
+    const SIZE: usize = 2 * 64 * 64 * 64;
+    let mut boards: Vec<Option<Board>> = vec![None; SIZE];
+    // The positions reached by each move, None when the move takes the piece.
+    let mut children: Vec<Vec<Option<usize>>> = vec![Vec::new(); SIZE];
+    let mut solved = vec![Solved::Unknown; SIZE];
+    for side in [Color::White, Color::Black] {
+        for white_king in 0..64 {
+            for white_piece in 0..64 {
+                for black_king in 0..64 {
+                    let squares = [white_king, white_piece, black_king];
+                    let Some(board) = position_board(side, squares, piece) else {
+                        continue;
+                    };
+                    let key = position_key(side, squares);
+                    let moves = legal::gen_all(&board);
+                    if moves.is_empty() {
+                        solved[key] = if board.is_check() {
+                            Solved::Loss(0)
+                        } else {
+                            Solved::Draw
+                        };
+                    }
+                    children[key] = moves
+                        .iter()
+                        .map(|chess_move| {
+                            let next = board.make_move(*chess_move).expect("the move is legal");
+                            board_key(&next, piece)
+                        })
+                        .collect();
+                    boards[key] = Some(board);
+                }
+            }
+        }
+    }
+
+    // The results found at a ply count are only known from the next one.
+    let mut plies = 1;
+    let mut unchanged = 0;
+    while unchanged < 2 {
+        let mut found = Vec::new();
+        for key in 0..SIZE {
+            if boards[key].is_none() || solved[key] != Solved::Unknown {
+                continue;
+            }
+            let mut results = children[key]
+                .iter()
+                .map(|child| child.map_or(Solved::Draw, |child| solved[child]));
+            if results
+                .clone()
+                .any(|result| result == Solved::Loss(plies - 1))
+            {
+                found.push((key, Solved::Win(plies)));
+            } else if results.all(|result| matches!(result, Solved::Win(_))) {
+                found.push((key, Solved::Loss(plies)));
+            }
+        }
+        unchanged = if found.is_empty() { unchanged + 1 } else { 0 };
+        for (key, result) in found {
+            solved[key] = result;
+        }
+        plies += 1;
+    }
+
+    boards
+        .into_iter()
+        .zip(solved)
+        .filter_map(|(board, solved)| {
+            let solved = if solved == Solved::Unknown {
+                Solved::Draw
+            } else {
+                solved
+            };
+            Some((board?, solved))
+        })
+        .collect()
+}
+
+/// The sections of a part of a table, in the order they are written.
+struct EncodedPart {
+    sizes: Vec<u8>,
+    sparse_index: Vec<u8>,
+    block_lengths: Vec<u8>,
+    data: Vec<u8>,
+}
+
+/// The lengths of the Huffman codes of the values, from their frequencies.
+fn code_lengths(frequencies: &BTreeMap<u16, u64>) -> BTreeMap<u16, u8> {
+    // The leaves are the values, the other nodes join two of them.
+    let mut parents: Vec<Option<usize>> = vec![None; frequencies.len()];
+    let mut heap: BinaryHeap<Reverse<(u64, usize)>> = frequencies
+        .values()
+        .enumerate()
+        .map(|(node, frequency)| Reverse((*frequency, node)))
+        .collect();
+    while heap.len() > 1 {
+        let Reverse((first_frequency, first)) = heap.pop().expect("two nodes are left");
+        let Reverse((second_frequency, second)) = heap.pop().expect("two nodes are left");
+        let parent = parents.len();
+        parents.push(None);
+        parents[first] = Some(parent);
+        parents[second] = Some(parent);
+        heap.push(Reverse((first_frequency + second_frequency, parent)));
+    }
+    frequencies
+        .keys()
+        .enumerate()
+        .map(|(leaf, value)| {
+            let mut length = 0;
+            let mut node = leaf;
+            while let Some(parent) = parents[node] {
+                length += 1;
+                node = parent;
+            }
+            (*value, length)
+        })
+        .collect()
+}
+
+/// Compresses the values of a part, or stores its single value.
+fn encode_part(flags: u8, values: &[u16]) -> EncodedPart {
+    let mut frequencies = BTreeMap::new();
+    for value in values {
+        *frequencies.entry(*value).or_insert(0u64) += 1;
+    }
+    if frequencies.len() == 1 {
+        return EncodedPart {
+            sizes: vec![flags | FLAG_SINGLE_VALUE, values[0] as u8],
+            sparse_index: Vec::new(),
+            block_lengths: Vec::new(),
+            data: Vec::new(),
+        };
+    }
+
+    // The symbols are numbered from the longest codes, and the codes of a length follow the
+    // numbers of their symbols.
+    let lengths = code_lengths(&frequencies);
+    let mut symbols: Vec<u16> = lengths.keys().copied().collect();
+    symbols.sort_by_key(|value| (Reverse(lengths[value]), *value));
+    let min_length = *lengths.values().min().expect("several values");
+    let max_length = *lengths.values().max().expect("several values");
+    let length_count = usize::from(max_length - min_length) + 1;
+    let count = |length: u8| lengths.values().filter(|other| **other == length).count() as u64;
+    let lowest_symbols: Vec<u64> = (0..length_count)
+        .map(|i| {
+            let length = min_length + i as u8;
+            lengths.values().filter(|other| **other > length).count() as u64
+        })
+        .collect();
+    let mut base = vec![0u64; length_count];
+    for i in (0..length_count - 1).rev() {
+        base[i] = (base[i + 1] + count(min_length + i as u8 + 1)) / 2;
+    }
+    let codes: BTreeMap<u16, (u64, u8)> = symbols
+        .iter()
+        .enumerate()
+        .map(|(symbol, value)| {
+            let length = lengths[value];
+            let i = usize::from(length - min_length);
+            let code = base[i] + symbol as u64 - lowest_symbols[i];
+            (*value, (code, length))
+        })
+        .collect();
+
+    let block_bits = 8usize << BLOCK_SIZE_LOG;
+    let mut blocks: Vec<(Vec<u8>, usize)> = Vec::new();
+    let mut bits: Vec<bool> = Vec::new();
+    let mut block_values = 0;
+    let mut write_block = |bits: &mut Vec<bool>, block_values: usize| {
+        let mut bytes = vec![0u8; 1 << BLOCK_SIZE_LOG];
+        for (position, bit) in bits.iter().enumerate() {
+            if *bit {
+                bytes[position / 8] |= 0x80 >> (position % 8);
+            }
+        }
+        blocks.push((bytes, block_values));
+        bits.clear();
+    };
+    for value in values {
+        let (code, length) = codes[value];
+        if bits.len() + usize::from(length) > block_bits {
+            write_block(&mut bits, block_values);
+            block_values = 0;
+        }
+        bits.extend((0..length).rev().map(|bit| code >> bit & 1 == 1));
+        block_values += 1;
+    }
+    write_block(&mut bits, block_values);
+
+    let span = 1usize << SPAN_LOG;
+    let mut block_starts = Vec::new();
+    let mut start = 0;
+    for (_, block_values) in &blocks {
+        block_starts.push(start);
+        start += block_values;
+    }
+    let mut sparse_index = Vec::new();
+    for entry in 0..values.len().div_ceil(span) {
+        let middle = entry * span + span / 2;
+        let block = block_starts
+            .iter()
+            .rposition(|start| *start <= middle)
+            .expect("the first block starts at 0");
+        sparse_index.extend((block as u32).to_le_bytes());
+        sparse_index.extend(((middle - block_starts[block]) as u16).to_le_bytes());
+    }
+
+    let mut sizes = vec![flags, BLOCK_SIZE_LOG, SPAN_LOG, 0];
+    sizes.extend((blocks.len() as u32).to_le_bytes());
+    sizes.extend([max_length, min_length]);
+    for lowest in lowest_symbols {
+        sizes.extend((lowest as u16).to_le_bytes());
+    }
+    sizes.extend((symbols.len() as u16).to_le_bytes());
+    for value in &symbols {
+        // A leaf : the value, and no right child.
+        sizes.extend([
+            (value & 0xFF) as u8,
+            ((value >> 8) & 0xF) as u8 | 0xF0,
+            0xFF,
+        ]);
+    }
+    if symbols.len() % 2 == 1 {
+        sizes.push(0);
+    }
+
+    EncodedPart {
+        sizes,
+        sparse_index,
+        block_lengths: blocks
+            .iter()
+            .flat_map(|(_, block_values)| ((block_values - 1) as u16).to_le_bytes())
+            .collect(),
+        data: blocks.into_iter().flat_map(|(bytes, _)| bytes).collect(),
+    }
+}
+
+/// A table without pawns, with its parts given by their flags and values.
+fn table_bytes(kind: TableKind, pieces: &[u8], parts: &[(u8, Vec<u16>)]) -> Vec<u8> {
+    let mut bytes = kind.magic().to_vec();
+    bytes.push(u8::from(parts.len() == 2));
+    // The order of the groups of pieces in the index.
+    bytes.push(0);
+    bytes.extend(pieces.iter().map(|piece| piece | piece << 4));
+    if bytes.len() % 2 == 1 {
+        bytes.push(0);
+    }
+    let parts: Vec<EncodedPart> = parts
+        .iter()
+        .map(|(flags, values)| encode_part(*flags, values))
+        .collect();
+    for part in &parts {
+        bytes.extend(&part.sizes);
+    }
+    if kind == TableKind::Dtz && bytes.len() % 2 == 1 {
+        bytes.push(0);
+    }
+    for part in &parts {
+        bytes.extend(&part.sparse_index);
+    }
+    for part in &parts {
+        bytes.extend(&part.block_lengths);
+    }
+    for part in &parts {
+        bytes.resize((bytes.len() + 0x3F) & !0x3F, 0);
+        bytes.extend(&part.data);
+    }
+    // The decompression reads a little beyond the last block.
+    bytes.extend([0; 16]);
+    bytes
+}
+
+/// Maps the bytes as a table, through a temporary file.
+fn read_table(name: &str, kind: TableKind, bytes: &[u8]) -> Table {
+    let path = std::env::temp_dir().join(format!(
+        "syzygy_fixture_{name}_{}.{}",
+        std::process::id(),
+        kind.extension()
+    ));
+    fs::write(&path, bytes).unwrap();
+    let file = fs::File::open(&path).unwrap();
+    // SAFETY: the file is only read, and removed once the table is dropped.
+    let bytes = unsafe { Mmap::map(&file).unwrap() };
+    let _ = fs::remove_file(&path);
+    Table::read(name, bytes, kind, Material::from_name(name).unwrap()).unwrap()
+}
+
+/// Places the values of the positions at their indices, the most frequent value filling the
+/// indices of the illegal positions.
+fn part_values(located: &[(usize, u64, u16)], part_side: usize, size: u64) -> Vec<u16> {
+    let mut values: Vec<Option<u16>> = vec![None; size as usize];
+    for (side, index, value) in located.iter().filter(|(side, ..)| *side == part_side) {
+        let stored = &mut values[*index as usize];
+        assert!(
+            stored.is_none_or(|stored| stored == *value),
+            "index {index} of side {side} holds two values"
+        );
+        *stored = Some(*value);
+    }
+    let mut frequencies = BTreeMap::new();
+    for value in values.iter().flatten() {
+        *frequencies.entry(*value).or_insert(0) += 1;
+    }
+    let filler = frequencies
+        .iter()
+        .max_by_key(|(_, count)| **count)
+        .map_or(0, |(value, _)| *value);
+    values
+        .into_iter()
+        .map(|value| value.unwrap_or(filler))
+        .collect()
+}
+
+/// Writes the WDL and the DTZ tables of a king and the piece against a king, checking the
+/// longest win found against the known one.
+fn write_tables(directory: &Path, piece: Piece, letter: char, longest_win: u16) {
+    let name = format!("K{letter}vK");
+    let pieces = [
+        piece_code(Piece::King, Color::White),
+        piece_code(piece, Color::White),
+        piece_code(Piece::King, Color::Black),
+    ];
+    let plies_flags = FLAG_WIN_PLIES | FLAG_LOSS_PLIES;
+    let wdl_layout = read_table(
+        &name,
+        TableKind::Wdl,
+        &table_bytes(TableKind::Wdl, &pieces, &[(0, vec![0]), (0, vec![0])]),
+    );
+    // The DTZ table stores the positions with White to move.
+    let dtz_layout = read_table(
+        &name,
+        TableKind::Dtz,
+        &table_bytes(TableKind::Dtz, &pieces, &[(plies_flags, vec![0])]),
+    );
+    let size = |table: &Table| table.parts[0][0].group_index[1];
+
+    let solved = solve(piece);
+    let longest = solved
+        .iter()
+        .filter_map(|(_, solved)| match solved {
+            Solved::Win(plies) => Some(*plies),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+    assert_eq!(longest, longest_win, "the longest win of {name}");
+
+    let mut wdl_values = Vec::new();
+    let mut dtz_values = Vec::new();
+    for (board, solved) in solved {
+        let (wdl, dtz) = match solved {
+            Solved::Win(plies) => (4, plies - 1),
+            Solved::Loss(plies) => (0, plies.max(1) - 1),
+            Solved::Draw | Solved::Unknown => (2, 0),
+        };
+        let (side, _, index) = wdl_layout.locate(&board, false).unwrap();
+        wdl_values.push((side, index, wdl));
+        if let Some((side, _, index)) = dtz_layout.locate(&board, false)
+            && wdl != 2
+        {
+            dtz_values.push((side, index, dtz));
+        }
+    }
+
+    let wdl_parts = [0, 1].map(|side| (0, part_values(&wdl_values, side, size(&wdl_layout))));
+    fs::write(
+        directory.join(format!("{name}.rtbw")),
+        table_bytes(TableKind::Wdl, &pieces, &wdl_parts),
+    )
+    .unwrap();
+    let dtz_part = (plies_flags, part_values(&dtz_values, 0, size(&dtz_layout)));
+    fs::write(
+        directory.join(format!("{name}.rtbz")),
+        table_bytes(TableKind::Dtz, &pieces, &[dtz_part]),
+    )
+    .unwrap();
+}
+
+#[test]
+#[ignore = "writes the tables of the tests again, best run with --release"]
+fn write_fixtures() {
+    let directory = directory();
+    // The longest mates take 10 moves with a queen, 16 with a rook.
+    write_tables(&directory, Piece::Queen, 'Q', 19);
+    write_tables(&directory, Piece::Rook, 'R', 31);
+    write_tables(&directory, Piece::Knight, 'N', 0);
+}
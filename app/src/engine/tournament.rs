@@ -0,0 +1,667 @@
+//! Matches between two engines : a series of games played with a clock, from a list of openings.
+use std::{
+    fmt,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use iced::Subscription;
+use owlchess::{Board, Color, DrawReason, Outcome, WinReason};
+
+use crate::{
+    engine::{Engine, EngineCommand, EngineEvent, SearchLimit, SearchUpdate, analysis::Score},
+    game::{Game, openings::Opening, pgn},
+    settings::EngineSettings,
+};
+
+/// The losing engine must see its position lost, and the winning engine see it won, for this many
+/// moves in a row before the game is adjudicated.
+const RESIGN_MOVES: usize = 3;
+
+/// Both engines must see the position drawn for this many moves in a row, before the game is
+/// adjudicated.
+const DRAW_MOVES: usize = 8;
+
+/// No draw is adjudicated before this move.
+const DRAW_FROM_MOVE: u16 = 30;
+
+/// The score of a mate, for the adjudication.
+const MATE_CENTIPAWNS: i32 = 100_000;
+
+/// The delay allowed beyond the clock, for the communication with the engine.
+const TIME_MARGIN: Duration = Duration::from_millis(100);
+
+/// The time given to each engine for the game, and the time added after each of its moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Duration,
+}
+
+/// As written in the PGN, in seconds : `300+2`.
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}+{}",
+            self.base.as_secs_f64(),
+            self.increment.as_secs_f64()
+        )
+    }
+}
+
+/// Ends the games whose result is clear to both engines, in centipawns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Adjudication {
+    /// The game is lost by the engine which evaluates its position below the opposite of this
+    /// score, while its opponent evaluates its own position above it.
+    pub resign_score: Option<i32>,
+    /// The game is drawn when both engines evaluate the position within this score.
+    pub draw_score: Option<i32>,
+}
+
+/// Counts the moves supporting an adjudication.
+#[derive(Debug, Default)]
+struct Adjudicator {
+    /// By side, the moves in a row evaluated as lost by the engine playing them.
+    lost_moves: [usize; 2],
+    /// By side, the moves in a row evaluated as won by the engine playing them.
+    won_moves: [usize; 2],
+    /// The moves in a row evaluated as drawn, by both engines.
+    drawn_moves: usize,
+}
+
+impl Adjudicator {
+    /// Records the evaluation given by the engine with its move, from its point of view, and
+    /// gives back the adjudicated outcome if any.
+    fn record(
+        &mut self,
+        rules: &Adjudication,
+        side: Color,
+        score: Option<Score>,
+        move_number: u16,
+    ) -> Option<Outcome> {
+        let Some(score) = score else {
+            *self = Self::default();
+            return None;
+        };
+        let centipawns = match score {
+            Score::Centipawns(centipawns) => centipawns,
+            Score::Mate(moves) if moves > 0 => MATE_CENTIPAWNS,
+            Score::Mate(_) => -MATE_CENTIPAWNS,
+        };
+
+        let index = side_index(side);
+        let (lost, won) = match rules.resign_score {
+            Some(resign_score) => (centipawns <= -resign_score, centipawns >= resign_score),
+            None => (false, false),
+        };
+        self.lost_moves[index] = if lost { self.lost_moves[index] + 1 } else { 0 };
+        self.won_moves[index] = if won { self.won_moves[index] + 1 } else { 0 };
+        for winner in [side, side.inv()] {
+            if self.won_moves[side_index(winner)] >= RESIGN_MOVES
+                && self.lost_moves[side_index(winner.inv())] >= RESIGN_MOVES
+            {
+                return Some(Outcome::Win {
+                    side: winner,
+                    reason: WinReason::Resign,
+                });
+            }
+        }
+
+        self.drawn_moves = if move_number >= DRAW_FROM_MOVE
+            && rules
+                .draw_score
+                .is_some_and(|draw_score| centipawns.abs() <= draw_score)
+        {
+            self.drawn_moves + 1
+        } else {
+            0
+        };
+        (self.drawn_moves >= 2 * DRAW_MOVES).then_some(Outcome::Draw(DrawReason::Agreement))
+    }
+}
+
+fn side_index(side: Color) -> usize {
+    match side {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+/// The Elo difference giving the expected score, as a ratio of the points. None when a side has
+/// scored every point, as the difference is then infinite.
+fn elo_difference(score_ratio: f64) -> Option<f64> {
+    (score_ratio > 0.0 && score_ratio < 1.0).then(|| -400.0 * (1.0 / score_ratio - 1.0).log10())
+}
+
+/// The results of the first engine against the second one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatchScore {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl MatchScore {
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    /// One point for a win, half a point for a draw.
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+
+    /// The Elo difference of the first engine, unless a side has scored every point.
+    pub fn elo_difference(&self) -> Option<f64> {
+        elo_difference(self.points() / self.games() as f64)
+    }
+
+    /// The margin of the Elo difference, for a confidence of 95%.
+    pub fn error_margin(&self) -> Option<f64> {
+        let games = self.games() as f64;
+        if games == 0.0 {
+            return None;
+        }
+        let ratio = self.points() / games;
+        let deviation = |result: f64, count: usize| count as f64 / games * (result - ratio).powi(2);
+        let variance =
+            deviation(1.0, self.wins) + deviation(0.5, self.draws) + deviation(0.0, self.losses);
+        let error = 1.959_964 * (variance / games).sqrt();
+        Some((elo_difference(ratio + error)? - elo_difference(ratio - error)?) / 2.0)
+    }
+}
+
+pub struct MatchSettings {
+    /// The engines, as registered : their options are set before each game.
+    pub engines: [EngineSettings; 2],
+    pub games: usize,
+    /// Each opening is played twice, the engines swapping sides. Without openings, the games
+    /// start from the initial position.
+    pub openings: Vec<Opening>,
+    pub time_control: TimeControl,
+    pub adjudication: Adjudication,
+    /// The directory of the Syzygy tablebases, given to the engines which can use them.
+    pub syzygy_path: Option<PathBuf>,
+}
+
+pub struct FinishedGame {
+    pub white: String,
+    pub black: String,
+    pub outcome: Option<Outcome>,
+    pub pgn: String,
+}
+
+pub struct Tournament {
+    settings: MatchSettings,
+    engines: [Engine; 2],
+    /// The number of the game being played, from 0.
+    round: usize,
+    game: Game,
+    /// Whether the engines have been told about the current game.
+    game_started: bool,
+    /// The times left to White and Black.
+    clocks: [Duration; 2],
+    /// The engine searching its move, and the time when it started.
+    search: Option<(usize, Instant)>,
+    /// The last evaluation given by the searching engine.
+    last_score: Option<Score>,
+    adjudicator: Adjudicator,
+    /// The time of the last event, for the clocks.
+    now: Instant,
+    score: MatchScore,
+    finished_games: Vec<FinishedGame>,
+    /// Why the match stopped before its end.
+    error: Option<String>,
+}
+
+impl Tournament {
+    /// Starts both engines. The first game begins once they are ready.
+    pub fn new(settings: MatchSettings) -> Self {
+        // The instances of the engines are apart from the engine of the application.
+        let engines = [0, 1].map(|index| {
+            let engine = &settings.engines[index];
+            Engine::new_instance(engine.path.clone(), engine.protocol, index + 1)
+        });
+        let mut tournament = Self {
+            engines,
+            round: 0,
+            game: Game::new(Board::initial()),
+            game_started: false,
+            clocks: [settings.time_control.base; 2],
+            search: None,
+            last_score: None,
+            adjudicator: Adjudicator::default(),
+            now: Instant::now(),
+            score: MatchScore::default(),
+            finished_games: Vec::new(),
+            error: None,
+            settings,
+        };
+        tournament.game = tournament.round_game();
+        tournament
+    }
+
+    pub fn subscription(&self) -> Subscription<(usize, EngineEvent)> {
+        Subscription::batch(
+            self.engines
+                .iter()
+                .enumerate()
+                .map(|(index, engine)| engine.subscription().with(index)),
+        )
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn engine_name(&self, index: usize) -> &str {
+        &self.settings.engines[index].name
+    }
+
+    /// The number of the game being played, from 1.
+    pub fn round(&self) -> usize {
+        (self.round + 1).min(self.settings.games)
+    }
+
+    pub fn games(&self) -> usize {
+        self.settings.games
+    }
+
+    /// The results of the first engine.
+    pub fn score(&self) -> MatchScore {
+        self.score
+    }
+
+    pub fn finished_games(&self) -> &[FinishedGame] {
+        &self.finished_games
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.round >= self.settings.games || self.error.is_some()
+    }
+
+    /// Whether an engine is searching its move, so that its clock runs.
+    pub fn is_searching(&self) -> bool {
+        self.search.is_some()
+    }
+
+    /// The engine playing the side in the current game : the engines swap sides after each game.
+    pub fn engine_of(&self, side: Color) -> usize {
+        let white_engine = self.round % 2;
+        match side {
+            Color::White => white_engine,
+            Color::Black => 1 - white_engine,
+        }
+    }
+
+    /// The time left to the side, its current search included.
+    pub fn time_left(&self, side: Color) -> Duration {
+        let clock = self.clocks[side_index(side)];
+        match self.search {
+            Some((_, started)) if self.game.position().side() == side => {
+                clock.saturating_sub(self.now - started)
+            }
+            _ => clock,
+        }
+    }
+
+    /// The PGN of the finished games.
+    pub fn pgn(&self) -> String {
+        self.finished_games
+            .iter()
+            .map(|game| game.pgn.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The game of the current round, from its opening.
+    fn round_game(&self) -> Game {
+        let openings = &self.settings.openings;
+        let mut game = match openings.get((self.round / 2) % openings.len().max(1)) {
+            Some(opening) => opening.game(),
+            None => Game::new(Board::initial()),
+        };
+        game.set_header("Event", String::from("Engine match"));
+        game.set_header("Round", (self.round + 1).to_string());
+        game.set_header(
+            "White",
+            self.engine_name(self.engine_of(Color::White)).to_string(),
+        );
+        game.set_header(
+            "Black",
+            self.engine_name(self.engine_of(Color::Black)).to_string(),
+        );
+        game.set_header("TimeControl", self.settings.time_control.to_string());
+        game
+    }
+
+    pub fn handle_event(&mut self, index: usize, event: EngineEvent) {
+        self.now = Instant::now();
+        let update = self.engines[index].handle_event(event);
+        if let Some(error) = self.engines[index].error()
+            && !self.is_over()
+        {
+            // The engine cannot play anymore : it loses the game being played, and the match
+            // stops.
+            self.error = Some(format!("{}: {error}", self.engine_name(index)));
+            if self.game_started {
+                let side = if self.engine_of(Color::White) == index {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+                self.end_game(Outcome::Win {
+                    side: side.inv(),
+                    reason: WinReason::EngineError,
+                });
+            }
+            return;
+        }
+
+        let searching = self.search.is_some_and(|(engine, _)| engine == index);
+        match update {
+            Some(SearchUpdate::Info(info)) if searching && info.score.is_some() => {
+                self.last_score = info.score;
+            }
+            Some(SearchUpdate::BestMove(best_move)) if searching => self.play(&best_move),
+            _ => {}
+        }
+        if !self.game_started
+            && !self.is_over()
+            && self
+                .engines
+                .iter()
+                .all(|engine| engine.identity().is_some())
+        {
+            self.start_game();
+        }
+    }
+
+    /// Ends the game of an engine which has run out of time without moving.
+    pub fn check_time(&mut self, now: Instant) {
+        self.now = now;
+        let side = self.game.position().side();
+        if let Some((index, started)) = self.search
+            && now - started > self.clocks[side_index(side)] + TIME_MARGIN
+        {
+            self.engines[index].stop();
+            self.end_game(Outcome::Win {
+                side: side.inv(),
+                reason: WinReason::TimeForfeit,
+            });
+        }
+    }
+
+    /// Stops the engines, when the match is closed.
+    pub fn quit(&self) {
+        for engine in &self.engines {
+            engine.send(EngineCommand::Quit);
+        }
+    }
+
+    fn start_game(&mut self) {
+        self.game = self.round_game();
+        self.clocks = [self.settings.time_control.base; 2];
+        self.adjudicator = Adjudicator::default();
+        for (index, engine) in self.engines.iter_mut().enumerate() {
+            let option_commands = engine
+                .identity()
+                .map(|identity| identity.option_commands(&self.settings.engines[index].options))
+                .unwrap_or_default();
+            engine.send(EngineCommand::NewGame);
+            for command in option_commands {
+                engine.send(command);
+            }
+            if let Some(path) = &self.settings.syzygy_path {
+                engine.set_tablebase_path(path);
+            }
+        }
+        self.game_started = true;
+        self.start_turn();
+    }
+
+    fn start_turn(&mut self) {
+        if self.game.is_finished() {
+            self.finish_game();
+            return;
+        }
+        let index = self.engine_of(self.game.position().side());
+        self.engines[index].go(
+            &self.game,
+            SearchLimit::Clock {
+                white: self.clocks[0],
+                black: self.clocks[1],
+                increment: self.settings.time_control.increment,
+            },
+        );
+        self.search = Some((index, self.now));
+        self.last_score = None;
+    }
+
+    fn play(&mut self, best_move: &str) {
+        let Some((_, started)) = self.search.take() else {
+            return;
+        };
+        let side = self.game.position().side();
+        let clock = &mut self.clocks[side_index(side)];
+        let elapsed = self.now - started;
+        if elapsed > *clock + TIME_MARGIN {
+            *clock = Duration::ZERO;
+            self.end_game(Outcome::Win {
+                side: side.inv(),
+                reason: WinReason::TimeForfeit,
+            });
+            return;
+        }
+        *clock = clock.saturating_sub(elapsed) + self.settings.time_control.increment;
+
+        let Some(legal_move) = self.game.legal_move(self.game.moves().len(), best_move) else {
+            self.end_game(Outcome::Win {
+                side: side.inv(),
+                reason: WinReason::InvalidMove,
+            });
+            return;
+        };
+        self.game.play_move(legal_move);
+        let move_number = self.game.position().raw().move_number;
+        if !self.game.is_finished()
+            && let Some(outcome) = self.adjudicator.record(
+                &self.settings.adjudication,
+                side,
+                self.last_score,
+                move_number,
+            )
+        {
+            self.end_game(outcome);
+        } else {
+            self.start_turn();
+        }
+    }
+
+    /// Ends the game before its natural end, telling why in the PGN.
+    fn end_game(&mut self, outcome: Outcome) {
+        let termination = match outcome {
+            Outcome::Win {
+                reason: WinReason::TimeForfeit,
+                ..
+            } => "time forfeit",
+            Outcome::Win {
+                reason: WinReason::InvalidMove | WinReason::EngineError,
+                ..
+            } => "rules infraction",
+            _ => "adjudication",
+        };
+        self.game.set_header("Termination", termination.to_string());
+        self.game.set_outcome(outcome);
+        self.finish_game();
+    }
+
+    /// Records the result of the game, then starts the next one.
+    fn finish_game(&mut self) {
+        self.search = None;
+        let outcome = self.game.outcome();
+        if let Some(outcome) = outcome {
+            for engine in &self.engines {
+                engine.send(EngineCommand::GameOver(outcome));
+            }
+        }
+        match outcome.and_then(|outcome| outcome.winner()) {
+            Some(side) if self.engine_of(side) == 0 => self.score.wins += 1,
+            Some(_) => self.score.losses += 1,
+            None => self.score.draws += 1,
+        }
+        self.finished_games.push(FinishedGame {
+            white: self.engine_name(self.engine_of(Color::White)).to_string(),
+            black: self.engine_name(self.engine_of(Color::Black)).to_string(),
+            outcome,
+            pgn: pgn::write(&self.game),
+        });
+        self.round += 1;
+        self.game_started = false;
+        if !self.is_over() {
+            self.start_game();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: Adjudication = Adjudication {
+        resign_score: Some(500),
+        draw_score: Some(10),
+    };
+
+    /// Records the scores given by White and Black in turn, from the given move number, and
+    /// gives back the first adjudicated outcome with the number of scores recorded.
+    fn adjudicate(
+        rules: &Adjudication,
+        first_move: u16,
+        scores: &[Option<Score>],
+    ) -> Option<(usize, Outcome)> {
+        let mut adjudicator = Adjudicator::default();
+        scores.iter().enumerate().find_map(|(ply, score)| {
+            let side = if ply % 2 == 0 {
+                Color::White
+            } else {
+                Color::Black
+            };
+            let move_number = first_move + (ply / 2) as u16;
+            adjudicator
+                .record(rules, side, *score, move_number)
+                .map(|outcome| (ply + 1, outcome))
+        })
+    }
+
+    fn black_wins() -> Outcome {
+        Outcome::Win {
+            side: Color::Black,
+            reason: WinReason::Resign,
+        }
+    }
+
+    #[test]
+    fn adjudicates_a_win_seen_by_both_engines() {
+        let scores = [Some(Score::Centipawns(-600)), Some(Score::Centipawns(700))].repeat(3);
+        assert_eq!(
+            adjudicate(&RULES, 10, &scores),
+            Some((2 * RESIGN_MOVES, black_wins()))
+        );
+        let scores = [Some(Score::Mate(-5)), Some(Score::Mate(5))].repeat(3);
+        assert_eq!(
+            adjudicate(&RULES, 10, &scores),
+            Some((2 * RESIGN_MOVES, black_wins()))
+        );
+    }
+
+    #[test]
+    fn needs_both_engines_to_agree_on_the_win() {
+        // Black does not see its position won.
+        let scores = [Some(Score::Centipawns(-600)), Some(Score::Centipawns(200))].repeat(5);
+        assert_eq!(adjudicate(&RULES, 10, &scores), None);
+        let no_resign = Adjudication {
+            resign_score: None,
+            ..RULES
+        };
+        let scores = [Some(Score::Mate(-2)), Some(Score::Mate(2))].repeat(5);
+        assert_eq!(adjudicate(&no_resign, 10, &scores), None);
+    }
+
+    #[test]
+    fn restarts_the_counts_after_an_interruption() {
+        let lost = [Some(Score::Centipawns(-600)), Some(Score::Centipawns(700))];
+        let mut scores = lost.repeat(RESIGN_MOVES - 1);
+        scores.extend([Some(Score::Centipawns(-100)), Some(Score::Centipawns(700))]);
+        scores.extend(lost.repeat(RESIGN_MOVES - 1));
+        assert_eq!(adjudicate(&RULES, 10, &scores), None);
+
+        let mut scores = lost.repeat(RESIGN_MOVES - 1);
+        scores.extend([None, None]);
+        scores.extend(lost.repeat(RESIGN_MOVES - 1));
+        assert_eq!(adjudicate(&RULES, 10, &scores), None);
+        scores.extend(lost);
+        assert!(adjudicate(&RULES, 10, &scores).is_some());
+    }
+
+    #[test]
+    fn adjudicates_draws_late_in_the_game() {
+        let scores = [Some(Score::Centipawns(5)), Some(Score::Centipawns(-8))].repeat(DRAW_MOVES);
+        assert_eq!(
+            adjudicate(&RULES, DRAW_FROM_MOVE, &scores),
+            Some((2 * DRAW_MOVES, Outcome::Draw(DrawReason::Agreement)))
+        );
+        assert_eq!(adjudicate(&RULES, DRAW_FROM_MOVE - 1, &scores), None);
+
+        let mut scores = scores;
+        scores[DRAW_MOVES] = Some(Score::Centipawns(50));
+        assert_eq!(adjudicate(&RULES, DRAW_FROM_MOVE, &scores), None);
+    }
+
+    #[test]
+    fn computes_the_elo_difference() {
+        let cases = [
+            (0.0, None),
+            (0.5, Some(0.0)),
+            (0.75, Some(190.85)),
+            (0.25, Some(-190.85)),
+            (1.0, None),
+        ];
+        for (ratio, difference) in cases {
+            let computed = elo_difference(ratio);
+            assert_eq!(computed.is_some(), difference.is_some(), "ratio {ratio}");
+            if let (Some(computed), Some(difference)) = (computed, difference) {
+                assert!((computed - difference).abs() < 0.01, "ratio {ratio}");
+            }
+        }
+    }
+
+    #[test]
+    fn gives_no_elo_difference_when_a_side_scored_everything() {
+        let score = |wins, draws, losses| MatchScore {
+            wins,
+            draws,
+            losses,
+        };
+        for all_or_nothing in [score(0, 0, 0), score(0, 0, 10), score(10, 0, 0)] {
+            assert_eq!(all_or_nothing.elo_difference(), None);
+            assert_eq!(all_or_nothing.error_margin(), None);
+        }
+
+        let even = score(3, 4, 3);
+        assert_eq!(even.points(), 5.0);
+        assert_eq!(even.elo_difference(), Some(0.0));
+        let margin = even.error_margin().unwrap();
+        assert!(margin > 100.0 && margin < 300.0, "margin {margin}");
+        // More games give a narrower margin.
+        assert!(score(30, 40, 30).error_margin().unwrap() < margin);
+        // The margin reaches the bounds.
+        assert_eq!(score(9, 0, 1).error_margin(), None);
+    }
+}
@@ -0,0 +1,408 @@
+//! The Universal Chess Interface protocol.
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use iced::futures::{
+    SinkExt, StreamExt,
+    channel::mpsc::{self, Sender},
+    stream,
+};
+
+use crate::engine::{
+    EngineCommand, EngineError, EngineEvent, EngineIdentity, EngineOption, EngineSender,
+    OptionKind, Protocol, SearchLimit,
+    analysis::{AnalysisInfo, Score},
+    default_name,
+    process::{Input, handshake_timeout, output_lines, spawn},
+};
+
+const OPTION_KEYWORDS: [&str; 6] = ["name", "type", "default", "min", "max", "var"];
+
+/// The option giving the directory of the Syzygy tablebases.
+pub const TABLEBASE_OPTION: &str = "SyzygyPath";
+
+/// The option telling the engine that the games are Chess960 ones, whose castlings are written
+/// as the king taking its rook.
+pub const CHESS960_OPTION: &str = "UCI_Chess960";
+
+/// Whether the engine plays Chess960.
+pub fn plays_chess960(options: &[EngineOption]) -> bool {
+    options.iter().any(|option| option.name == CHESS960_OPTION)
+}
+
+/// Parses an `option` line, as in
+/// `option name Skill Level type spin default 20 min 0 max 20`.
+pub fn parse_option(line: &str) -> Option<EngineOption> {
+    let mut words = line.split_whitespace();
+    if words.next()? != "option" {
+        return None;
+    }
+
+    // Values may contain spaces, so each value spans until the next keyword.
+    let mut fields: Vec<(&str, Vec<&str>)> = Vec::new();
+    for word in words {
+        match fields.last_mut() {
+            Some((_, value)) if !OPTION_KEYWORDS.contains(&word) => value.push(word),
+            None if !OPTION_KEYWORDS.contains(&word) => return None,
+            _ => fields.push((word, Vec::new())),
+        }
+    }
+    let field = |keyword: &str| {
+        fields
+            .iter()
+            .find(|(name, _)| *name == keyword)
+            .map(|(_, value)| value.join(" "))
+    };
+    let number = |keyword: &str| field(keyword)?.parse::<i64>().ok();
+
+    let name = field("name").filter(|name| !name.is_empty())?;
+    let kind = match field("type")?.as_str() {
+        "spin" => OptionKind::Spin {
+            default: number("default")?,
+            min: number("min")?,
+            max: number("max")?,
+        },
+        "check" => OptionKind::Check {
+            default: field("default")? == "true",
+        },
+        "combo" => OptionKind::Combo {
+            default: field("default")?,
+            values: fields
+                .iter()
+                .filter(|(name, _)| *name == "var")
+                .map(|(_, value)| value.join(" "))
+                .collect(),
+        },
+        "string" => {
+            let default = field("default").unwrap_or_default();
+            OptionKind::String {
+                default: if default == "<empty>" {
+                    String::new()
+                } else {
+                    default
+                },
+            }
+        }
+        "button" => OptionKind::Button,
+        _ => return None,
+    };
+    Some(EngineOption { name, kind })
+}
+
+/// Parses an `info` line, as in
+/// `info depth 12 score cp 35 nodes 120000 nps 800000 pv e2e4 e7e5`.
+/// The lines without anything to report, such as `info string` lines, are left out.
+pub fn parse_info(line: &str) -> Option<AnalysisInfo> {
+    let mut words = line.split_whitespace();
+    if words.next()? != "info" {
+        return None;
+    }
+
+    let mut info = AnalysisInfo::default();
+    while let Some(word) = words.next() {
+        match word {
+            "multipv" => info.multipv = words.next().and_then(|rank| rank.parse().ok()),
+            "depth" => info.depth = words.next().and_then(|depth| depth.parse().ok()),
+            "nodes" => info.nodes = words.next().and_then(|nodes| nodes.parse().ok()),
+            "nps" => info.nps = words.next().and_then(|nps| nps.parse().ok()),
+            "score" => {
+                let kind = words.next();
+                let value = words.next().and_then(|value| value.parse().ok());
+                info.score = match (kind, value) {
+                    (Some("cp"), Some(centipawns)) => Some(Score::Centipawns(centipawns)),
+                    (Some("mate"), Some(moves)) => Some(Score::Mate(moves)),
+                    _ => None,
+                };
+            }
+            "pv" => info.pv = words.by_ref().map(str::to_string).collect(),
+            // The rest of the line is free text, or moves of another line than the principal
+            // variation.
+            "string" | "refutation" | "currline" => break,
+            _ => {}
+        }
+    }
+    (!info.is_empty()).then_some(info)
+}
+
+/// Converts a command into the lines to send to the engine.
+fn command_lines(command: &EngineCommand) -> Vec<String> {
+    match command {
+        EngineCommand::NewGame => vec![String::from("ucinewgame")],
+        EngineCommand::SetOption { name, value: None } => vec![format!("setoption name {name}")],
+        EngineCommand::SetOption {
+            name,
+            value: Some(value),
+        } => vec![format!("setoption name {name} value {value}")],
+        EngineCommand::SetTablebasePath(path) => vec![format!(
+            "setoption name {TABLEBASE_OPTION} value {}",
+            path.display()
+        )],
+        EngineCommand::Go {
+            start_fen,
+            moves,
+            limit,
+        } => {
+            let position = if moves.is_empty() {
+                format!("position fen {start_fen}")
+            } else {
+                format!("position fen {start_fen} moves {}", moves.join(" "))
+            };
+            let go = match limit {
+                SearchLimit::MoveTime(duration) => format!("go movetime {}", duration.as_millis()),
+                SearchLimit::Depth(depth) => format!("go depth {depth}"),
+                SearchLimit::Infinite => String::from("go infinite"),
+                SearchLimit::Clock {
+                    white,
+                    black,
+                    increment,
+                } => format!(
+                    "go wtime {} btime {} winc {} binc {}",
+                    white.as_millis(),
+                    black.as_millis(),
+                    increment.as_millis(),
+                    increment.as_millis()
+                ),
+            };
+            vec![position, go]
+        }
+        EngineCommand::Stop => vec![String::from("stop")],
+        EngineCommand::GameOver(_) => Vec::new(),
+        EngineCommand::Quit => vec![String::from("quit")],
+    }
+}
+
+/// The answer to the `uci` command.
+#[derive(Default)]
+struct Handshake {
+    name: Option<String>,
+    author: Option<String>,
+    options: Vec<EngineOption>,
+}
+
+impl Handshake {
+    /// Reads a line of the answer, and tells whether it was the last one.
+    fn read_line(&mut self, line: &str) -> bool {
+        if let Some(name) = line.strip_prefix("id name ") {
+            self.name = Some(name.to_string());
+        } else if let Some(author) = line.strip_prefix("id author ") {
+            self.author = Some(author.to_string());
+        } else if line.starts_with("option ") {
+            self.options.extend(parse_option(line));
+        }
+        line == "uciok"
+    }
+
+    fn into_identity(self, path: &Path) -> EngineIdentity {
+        EngineIdentity {
+            name: self.name.unwrap_or_else(|| default_name(path)),
+            author: self.author,
+            options: self.options,
+        }
+    }
+}
+
+pub async fn probe(path: PathBuf) -> Result<EngineIdentity, EngineError> {
+    let (_child, mut stdin, lines) = spawn(&path)?;
+    writeln!(stdin, "uci").map_err(|err| EngineError::CannotWrite(err.to_string()))?;
+
+    let mut inputs = stream::select(output_lines(lines), handshake_timeout());
+    let mut handshake = Handshake::default();
+    while let Some(input) = inputs.next().await {
+        match input {
+            Input::Line(line) => {
+                if handshake.read_line(line.trim()) {
+                    let _ = writeln!(stdin, "quit");
+                    return Ok(handshake.into_identity(&path));
+                }
+            }
+            Input::HandshakeTimeout => return Err(EngineError::NoAnswer(Protocol::Uci)),
+            Input::FeaturesTimeout => {}
+            Input::Closed | Input::Command(_) => break,
+        }
+    }
+    Err(EngineError::Terminated)
+}
+
+/// Runs the engine process, forwarding its answers to the output until it stops.
+pub async fn run(path: PathBuf, mut output: Sender<EngineEvent>) {
+    let (_child, mut stdin, lines) = match spawn(&path) {
+        Ok(process) => process,
+        Err(err) => {
+            let _ = output.send(EngineEvent::Error(err)).await;
+            return;
+        }
+    };
+    let (commands_sender, commands) = mpsc::unbounded();
+    let _ = output
+        .send(EngineEvent::Started(EngineSender(commands_sender)))
+        .await;
+    if let Err(err) = writeln!(stdin, "uci") {
+        let _ = output
+            .send(EngineEvent::Error(EngineError::CannotWrite(
+                err.to_string(),
+            )))
+            .await;
+        return;
+    }
+
+    let mut inputs = stream::select(
+        stream::select(output_lines(lines), commands.map(Input::Command)),
+        handshake_timeout(),
+    );
+    let mut handshake = Some(Handshake::default());
+    while let Some(input) = inputs.next().await {
+        let event = match input {
+            Input::Line(line) => {
+                let line = line.trim();
+                if let Some(answer) = handshake.as_mut() {
+                    if answer.read_line(line) {
+                        handshake
+                            .take()
+                            .map(|answer| EngineEvent::Ready(answer.into_identity(&path)))
+                    } else {
+                        None
+                    }
+                } else if let Some(best_move) = line.strip_prefix("bestmove ") {
+                    best_move
+                        .split_whitespace()
+                        .next()
+                        .map(|best_move| EngineEvent::BestMove(best_move.to_string()))
+                } else {
+                    parse_info(line).map(EngineEvent::Info)
+                }
+            }
+            Input::Command(command) => {
+                let quit = matches!(command, EngineCommand::Quit);
+                let written = command_lines(&command)
+                    .iter()
+                    .try_for_each(|line| writeln!(stdin, "{line}"));
+                if quit {
+                    break;
+                }
+                written
+                    .err()
+                    .map(|err| EngineEvent::Error(EngineError::CannotWrite(err.to_string())))
+            }
+            Input::HandshakeTimeout if handshake.is_some() => {
+                let _ = output
+                    .send(EngineEvent::Error(EngineError::NoAnswer(Protocol::Uci)))
+                    .await;
+                return;
+            }
+            Input::HandshakeTimeout | Input::FeaturesTimeout => None,
+            Input::Closed => break,
+        };
+        if let Some(event) = event
+            && output.send(event).await.is_err()
+        {
+            return;
+        }
+    }
+    let _ = output.send(EngineEvent::Terminated).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_spin_option_with_spaces_in_name() {
+        assert_eq!(
+            parse_option("option name Skill Level type spin default 20 min 0 max 20"),
+            Some(EngineOption {
+                name: String::from("Skill Level"),
+                kind: OptionKind::Spin {
+                    default: 20,
+                    min: 0,
+                    max: 20
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn parses_check_combo_string_and_button_options() {
+        assert_eq!(
+            parse_option("option name UCI_LimitStrength type check default false").map(|o| o.kind),
+            Some(OptionKind::Check { default: false })
+        );
+        assert_eq!(
+            parse_option(
+                "option name Analysis Contempt type combo default Both var Off var White var Black var Both"
+            )
+            .map(|o| o.kind),
+            Some(OptionKind::Combo {
+                default: String::from("Both"),
+                values: vec![
+                    String::from("Off"),
+                    String::from("White"),
+                    String::from("Black"),
+                    String::from("Both"),
+                ],
+            })
+        );
+        assert_eq!(
+            parse_option("option name SyzygyPath type string default <empty>").map(|o| o.kind),
+            Some(OptionKind::String {
+                default: String::new()
+            })
+        );
+        assert_eq!(
+            parse_option("option name Debug Log File type string default my log.txt")
+                .map(|o| o.kind),
+            Some(OptionKind::String {
+                default: String::from("my log.txt")
+            })
+        );
+        assert_eq!(
+            parse_option("option name Clear Hash type button").map(|o| o.kind),
+            Some(OptionKind::Button)
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_options() {
+        assert_eq!(parse_option("id name Stockfish"), None);
+        assert_eq!(parse_option("option type spin default 1 min 0 max 2"), None);
+        assert_eq!(parse_option("option name Hash type spin default 16"), None);
+        assert_eq!(parse_option("option name Hash type unknown"), None);
+        assert_eq!(parse_option("option garbage name Hash type button"), None);
+    }
+
+    #[test]
+    fn parses_info_lines() {
+        assert_eq!(
+            parse_info(
+                "info depth 12 seldepth 18 multipv 2 score cp -35 nodes 120000 nps 800000 pv e2e4 e7e5"
+            ),
+            Some(AnalysisInfo {
+                multipv: Some(2),
+                depth: Some(12),
+                score: Some(Score::Centipawns(-35)),
+                nodes: Some(120000),
+                nps: Some(800000),
+                pv: vec![String::from("e2e4"), String::from("e7e5")],
+            })
+        );
+        assert_eq!(
+            parse_info("info depth 20 score mate -3 lowerbound pv h7h8q").map(|info| info.score),
+            Some(Some(Score::Mate(-3)))
+        );
+    }
+
+    #[test]
+    fn skips_info_lines_without_report() {
+        assert_eq!(
+            parse_info("info string NNUE evaluation enabled depth 3"),
+            None
+        );
+        assert_eq!(parse_info("info currmove e2e4 currmovenumber 1"), None);
+        assert_eq!(parse_info("bestmove e2e4"), None);
+        assert_eq!(
+            parse_info("info depth 5 currline 1 e2e4 e7e5").map(|info| info.depth),
+            Some(Some(5))
+        );
+    }
+}
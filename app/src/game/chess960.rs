@@ -0,0 +1,471 @@
+//! Chess960 (Fischer Random Chess) : its start positions, numbered from 0 to 959 as by Reinhard
+//! Scharnagl, and its castling.
+//!
+//! owlchess only knows castling with the king on the e file and the rooks on the a and h files.
+//! The boards of the Chess960 games therefore have no castling rights of their own : the rights
+//! are kept along them as [`CastlingRights`], and castling is played here. A castling is written
+//! as the king taking its rook in UCI (as engines expect with `UCI_Chess960`), and as `O-O` or
+//! `O-O-O` in SAN.
+use std::{
+    fmt,
+    hash::{BuildHasher, Hasher, RandomState},
+};
+
+use owlchess::{
+    Board, CastlingSide, Cell, Color, Coord, File, Piece, Rank, RawBoard,
+    board::{FenParseError, RawFenParseError},
+    types::CastlingRightsParseError,
+};
+
+/// The number of start positions.
+pub const POSITION_COUNT: u16 = 960;
+
+/// The number of the standard start position.
+pub const STANDARD_POSITION: u16 = 518;
+
+/// The value of the `Variant` header of the Chess960 games.
+pub const VARIANT: &str = "Chess960";
+
+/// The squares of the knights among the five squares left by the bishops and the queen.
+const KNIGHT_PLACEMENTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+/// Whether the value of a `Variant` header names Chess960.
+pub fn is_variant(variant: &str) -> bool {
+    ["chess960", "chess 960", "fischerandom", "fischer random"]
+        .iter()
+        .any(|name| variant.eq_ignore_ascii_case(name))
+}
+
+/// A start position taken at random.
+pub fn random_number() -> u16 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u16(POSITION_COUNT);
+    (hasher.finish() % u64::from(POSITION_COUNT)) as u16
+}
+
+/// The pieces of the first rank, from the a file to the h file, as in `RNBQKBNR`.
+pub fn back_rank(number: u16) -> String {
+    let mut rank = [None; 8];
+    let mut rest = usize::from(number % POSITION_COUNT);
+
+    // The bishops stand on squares of both colors.
+    rank[2 * (rest % 4) + 1] = Some('B');
+    rest /= 4;
+    rank[2 * (rest % 4)] = Some('B');
+    rest /= 4;
+
+    let place = |rank: &mut [Option<char>; 8], nth_empty: usize, piece: char| {
+        if let Some(file) = (0..8).filter(|file| rank[*file].is_none()).nth(nth_empty) {
+            rank[file] = Some(piece);
+        }
+    };
+    place(&mut rank, rest % 6, 'Q');
+    rest /= 6;
+    // The second knight is placed once the first one has taken its square.
+    let (first_knight, second_knight) = KNIGHT_PLACEMENTS[rest];
+    place(&mut rank, first_knight, 'N');
+    place(&mut rank, second_knight - 1, 'N');
+
+    // The king stands between the rooks.
+    for piece in ['R', 'K', 'R'] {
+        place(&mut rank, 0, piece);
+    }
+    rank.iter().flatten().collect()
+}
+
+/// The start position of the given number, with both castlings for both sides.
+pub fn start_position(number: u16) -> (Board, CastlingRights) {
+    let white = back_rank(number);
+    let fen = format!(
+        "{}/pppppppp/8/8/8/8/PPPPPPPP/{white} w KQkq - 0 1",
+        white.to_lowercase()
+    );
+    parse_fen(&fen).unwrap_or_else(|_| (Board::initial(), CastlingRights::default()))
+}
+
+fn side_index(side: CastlingSide) -> usize {
+    match side {
+        CastlingSide::King => 0,
+        CastlingSide::Queen => 1,
+    }
+}
+
+/// The files of the rooks which may still castle, by color then by side.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CastlingRights {
+    rooks: [[Option<File>; 2]; 2],
+}
+
+impl CastlingRights {
+    /// The file of the rook which may castle on the given side, if any.
+    pub fn rook(&self, color: Color, side: CastlingSide) -> Option<File> {
+        self.rooks[color as usize][side_index(side)]
+    }
+
+    /// Reads the castling field of an X-FEN (`KQkq`, the outermost rooks, or the file of the
+    /// rook when another rook stands further) or of a Shredder-FEN (`HAha`, the files of the
+    /// rooks). The rights without a king and a rook on the first rank are dropped.
+    pub fn parse(field: &str, board: &RawBoard) -> Result<Self, CastlingRightsParseError> {
+        let mut rights = Self::default();
+        if field == "-" {
+            return Ok(rights);
+        }
+        if field.is_empty() {
+            return Err(CastlingRightsParseError::EmptyString);
+        }
+        for letter in field.chars() {
+            let color = if letter.is_ascii_uppercase() {
+                Color::White
+            } else {
+                Color::Black
+            };
+            let Some(king) = king_file(board, color) else {
+                continue;
+            };
+            let (side, rook) = match letter.to_ascii_lowercase() {
+                'k' => (
+                    CastlingSide::King,
+                    outermost_rook(board, color, CastlingSide::King),
+                ),
+                'q' => (
+                    CastlingSide::Queen,
+                    outermost_rook(board, color, CastlingSide::Queen),
+                ),
+                file => {
+                    let file = File::from_char(file)
+                        .ok_or(CastlingRightsParseError::UnexpectedChar(letter))?;
+                    let side = if file > king {
+                        CastlingSide::King
+                    } else {
+                        CastlingSide::Queen
+                    };
+                    let rook = Cell::from_parts(color, Piece::Rook);
+                    (
+                        side,
+                        (board.get2(file, first_rank(color)) == rook).then_some(file),
+                    )
+                }
+            };
+            let right = &mut rights.rooks[color as usize][side_index(side)];
+            if right.is_some() {
+                return Err(CastlingRightsParseError::DuplicateChar(letter));
+            }
+            *right = rook;
+        }
+        Ok(rights)
+    }
+
+    /// The castling field of the X-FEN : `KQkq` for the outermost rooks, the file of the rook
+    /// otherwise.
+    pub fn field(&self, board: &RawBoard) -> String {
+        let mut field = String::new();
+        for color in [Color::White, Color::Black] {
+            for (side, letter) in [(CastlingSide::King, 'K'), (CastlingSide::Queen, 'Q')] {
+                let Some(file) = self.rook(color, side) else {
+                    continue;
+                };
+                let letter = if outermost_rook(board, color, side) == Some(file) {
+                    letter
+                } else {
+                    file.as_char().to_ascii_uppercase()
+                };
+                field.push(match color {
+                    Color::White => letter,
+                    Color::Black => letter.to_ascii_lowercase(),
+                });
+            }
+        }
+        if field.is_empty() {
+            field.push('-');
+        }
+        field
+    }
+
+    /// The rights left once the given move is played from the board : the king moving loses
+    /// both castlings, and a rook moving or taken loses its own.
+    pub fn after_move(&self, board: &Board, src: Coord, dst: Coord) -> Self {
+        let mut rights = *self;
+        for color in [Color::White, Color::Black] {
+            if board.get(src) == Cell::from_parts(color, Piece::King) {
+                rights.rooks[color as usize] = [None, None];
+            }
+            for right in rights.rooks[color as usize].iter_mut() {
+                if let Some(file) = *right {
+                    let rook = Coord::from_parts(file, first_rank(color));
+                    if rook == src || rook == dst {
+                        *right = None;
+                    }
+                }
+            }
+        }
+        rights
+    }
+
+    /// The castlings the side to move may play.
+    pub fn legal_castlings(&self, board: &Board) -> Vec<Castling> {
+        let color = board.side();
+        if board.is_check() {
+            return Vec::new();
+        }
+        let Some(king_file) = king_file(board.raw(), color) else {
+            return Vec::new();
+        };
+        [CastlingSide::King, CastlingSide::Queen]
+            .into_iter()
+            .filter_map(|side| {
+                let rook_file = self.rook(color, side)?;
+                let castling = Castling {
+                    side,
+                    king: Coord::from_parts(king_file, first_rank(color)),
+                    rook: Coord::from_parts(rook_file, first_rank(color)),
+                };
+                castling.is_legal(board).then_some(castling)
+            })
+            .collect()
+    }
+}
+
+/// A castling : the king and the rook leave their squares for the g and f files (on the king
+/// side) or the c and d files (on the queen side), as in the standard game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Castling {
+    pub side: CastlingSide,
+    pub king: Coord,
+    pub rook: Coord,
+}
+
+impl Castling {
+    /// The squares of the king and of the rook after the castling.
+    fn destinations(&self) -> (Coord, Coord) {
+        let rank = self.king.rank();
+        let (king, rook) = match self.side {
+            CastlingSide::King => (File::G, File::F),
+            CastlingSide::Queen => (File::C, File::D),
+        };
+        (Coord::from_parts(king, rank), Coord::from_parts(rook, rank))
+    }
+
+    /// The squares between the king or the rook and their destinations must be empty, and the
+    /// king must not pass through an attacked square.
+    fn is_legal(&self, board: &Board) -> bool {
+        let color = board.side();
+        let (king_destination, rook_destination) = self.destinations();
+        let files = [self.king, self.rook, king_destination, rook_destination]
+            .map(|coord| coord.file().index());
+        let (Some(first), Some(last)) = (files.iter().min(), files.iter().max()) else {
+            return false;
+        };
+        let rank = self.king.rank();
+        let crossed_is_empty = (*first..=*last).all(|file| {
+            let coord = Coord::from_parts(File::from_index(file), rank);
+            coord == self.king || coord == self.rook || board.get(coord) == Cell::EMPTY
+        });
+        if !crossed_is_empty {
+            return false;
+        }
+
+        // The king is put on each square of its way, without the rook, with the other side to
+        // move : owlchess refuses the board when the king is attacked.
+        let mut raw = *board.raw();
+        raw.put(self.king, Cell::EMPTY);
+        raw.put(self.rook, Cell::EMPTY);
+        raw.side = color.inv();
+        raw.ep_source = None;
+        let (from, to) = (
+            self.king
+                .file()
+                .index()
+                .min(king_destination.file().index()),
+            self.king
+                .file()
+                .index()
+                .max(king_destination.file().index()),
+        );
+        (from..=to).all(|file| {
+            let coord = Coord::from_parts(File::from_index(file), rank);
+            let mut raw = raw;
+            raw.put(coord, Cell::from_parts(color, Piece::King));
+            Board::try_from(&raw).is_ok()
+        })
+    }
+
+    /// The board after the castling.
+    pub fn make(&self, board: &Board) -> Option<Board> {
+        let color = board.side();
+        let (king_destination, rook_destination) = self.destinations();
+        let mut raw = *board.raw();
+        raw.put(self.king, Cell::EMPTY);
+        raw.put(self.rook, Cell::EMPTY);
+        raw.put(king_destination, Cell::from_parts(color, Piece::King));
+        raw.put(rook_destination, Cell::from_parts(color, Piece::Rook));
+        raw.side = color.inv();
+        raw.ep_source = None;
+        raw.move_counter += 1;
+        if color == Color::Black {
+            raw.move_number += 1;
+        }
+        Board::try_from(&raw).ok()
+    }
+}
+
+/// The king taking its rook, as in `e1h1`.
+impl fmt::Display for Castling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.king, self.rook)
+    }
+}
+
+fn first_rank(color: Color) -> Rank {
+    match color {
+        Color::White => Rank::R1,
+        Color::Black => Rank::R8,
+    }
+}
+
+fn king_file(board: &RawBoard, color: Color) -> Option<File> {
+    let king = Cell::from_parts(color, Piece::King);
+    File::iter().find(|file| board.get2(*file, first_rank(color)) == king)
+}
+
+/// The rook of the first rank the furthest from the king on the given side.
+fn outermost_rook(board: &RawBoard, color: Color, side: CastlingSide) -> Option<File> {
+    let king = king_file(board, color)?;
+    let rook = Cell::from_parts(color, Piece::Rook);
+    let mut files = File::iter().filter(|file| board.get2(*file, first_rank(color)) == rook);
+    match side {
+        CastlingSide::King => files.filter(|file| *file > king).last(),
+        CastlingSide::Queen => files.find(|file| *file < king),
+    }
+}
+
+/// Reads a position from an X-FEN or a Shredder-FEN. The board gets no castling rights, which
+/// are given back apart.
+pub fn parse_fen(fen: &str) -> Result<(Board, CastlingRights), FenParseError> {
+    let mut fields: Vec<&str> = fen.split_whitespace().collect();
+    let field = fields
+        .get(2)
+        .copied()
+        .ok_or(FenParseError::Fen(RawFenParseError::NoCastling))?;
+    fields[2] = "-";
+    let board = super::parse_fen(&fields.join(" "))?;
+    let rights = CastlingRights::parse(field, board.raw())
+        .map_err(|error| FenParseError::Fen(RawFenParseError::Castling(error)))?;
+    Ok((board, rights))
+}
+
+/// The X-FEN of the board with the given castling rights.
+pub fn fen(board: &Board, rights: &CastlingRights) -> String {
+    let fen = board.as_fen();
+    let mut fields: Vec<&str> = fen.split(' ').collect();
+    let field = rights.field(board.raw());
+    if let Some(castling) = fields.get_mut(2) {
+        *castling = &field;
+    }
+    fields.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn castling(fen: &str) -> (Board, Vec<String>) {
+        let (board, rights) = parse_fen(fen).unwrap();
+        let castlings = rights
+            .legal_castlings(&board)
+            .iter()
+            .map(Castling::to_string)
+            .collect();
+        (board, castlings)
+    }
+
+    #[test]
+    fn numbers_the_start_positions() {
+        assert_eq!(back_rank(STANDARD_POSITION), "RNBQKBNR");
+        assert_eq!(back_rank(0), "BBQNNRKR");
+        assert_eq!(back_rank(959), "RKRNNQBB");
+        assert!((0..POSITION_COUNT).all(|number| {
+            let rank = back_rank(number);
+            let king = rank.find('K').unwrap();
+            rank.len() == 8 && rank.find('R').unwrap() < king && rank.rfind('R').unwrap() > king
+        }));
+    }
+
+    #[test]
+    fn reads_and_writes_x_fen_and_shredder_fen() {
+        let (board, rights) = start_position(0);
+        assert_eq!(
+            fen(&board, &rights),
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+        );
+        let (_, shredder) =
+            parse_fen("bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1").unwrap();
+        assert_eq!(shredder, rights);
+        assert_eq!(board.raw().castling, owlchess::CastlingRights::EMPTY);
+
+        // Another rook beyond the castling one : the file of the rook tells which one castles.
+        let x_fen = "4k3/8/8/8/8/8/8/RR2K3 w B - 0 1";
+        let (board, rights) = parse_fen(x_fen).unwrap();
+        assert_eq!(
+            rights.rook(Color::White, CastlingSide::Queen),
+            Some(File::B)
+        );
+        assert_eq!(fen(&board, &rights), x_fen);
+        assert!(parse_fen("4k3/8/8/8/8/8/8/R3K3 w KX - 0 1").is_err());
+        assert!(parse_fen("4k3/8/8/8/8/8/8/R3K3 w QQ - 0 1").is_err());
+    }
+
+    #[test]
+    fn castles_the_king_onto_its_rook() {
+        // The king on b1 takes the rook on a1 and lands on c1, the rook on d1.
+        let (board, castlings) = castling("r5kr/8/8/8/8/8/8/RK5R w KQk - 0 1");
+        assert_eq!(castlings, ["b1h1", "b1a1"]);
+        let (_, rights) = parse_fen("r5kr/8/8/8/8/8/8/RK5R w KQk - 0 1").unwrap();
+        let queen_side = rights.legal_castlings(&board)[1];
+        let after = queen_side.make(&board).unwrap();
+        assert_eq!(after.as_fen(), "r5kr/8/8/8/8/8/8/2KR3R b - - 1 1");
+
+        // The king stays on g1 and the rook comes from h1 to f1.
+        let (board, castlings) = castling("6k1/8/8/8/8/8/8/6KR w K - 0 1");
+        assert_eq!(castlings, ["g1h1"]);
+        let (_, rights) = parse_fen("6k1/8/8/8/8/8/8/6KR w K - 0 1").unwrap();
+        let after = rights.legal_castlings(&board)[0].make(&board).unwrap();
+        assert_eq!(after.as_fen(), "6k1/8/8/8/8/8/8/5RK1 b - - 1 1");
+    }
+
+    #[test]
+    fn refuses_blocked_or_attacked_castlings() {
+        // A knight stands on the way of the rook.
+        assert!(castling("4k3/8/8/8/8/8/8/1RNK4 w Q - 0 1").1.is_empty());
+        // The king would pass through d1, attacked by the rook on d8.
+        assert!(castling("3rk3/8/8/8/8/8/8/4K2R w K - 0 1").1.len() == 1);
+        assert!(castling("3rk3/8/8/8/8/8/8/R3K3 w Q - 0 1").1.is_empty());
+        // The king would land on g1, next to the rook of h1.
+        assert!(castling("4k3/8/8/8/8/8/8/5KRr w K - 0 1").1.is_empty());
+        // No castling out of check.
+        assert!(castling("4k3/8/8/8/8/8/4r3/4K2R w K - 0 1").1.is_empty());
+    }
+
+    #[test]
+    fn loses_the_rights_of_the_moved_pieces() {
+        let (board, rights) = start_position(STANDARD_POSITION);
+        let coord = |name: &str| name.parse::<Coord>().unwrap();
+        let after_rook = rights.after_move(&board, coord("h1"), coord("h3"));
+        assert_eq!(after_rook.field(board.raw()), "Qkq");
+        let after_king = rights.after_move(&board, coord("e1"), coord("e2"));
+        assert_eq!(after_king.field(board.raw()), "kq");
+        // A rook taken on its square loses its castling too.
+        let after_capture = rights.after_move(&board, coord("b2"), coord("a8"));
+        assert_eq!(after_capture.field(board.raw()), "KQk");
+    }
+}
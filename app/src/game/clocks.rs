@@ -0,0 +1,79 @@
+//! The time spent by each side on its moves, in the games against the engine. The time is not
+//! limited : the clocks count up, and a resumed game keeps the time already spent.
+use std::time::{Duration, Instant};
+
+use owlchess::Color;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Clocks {
+    /// The time spent by White and Black, without the running clock.
+    spent: [Duration; 2],
+    /// The side thinking, and since when.
+    running: Option<(Color, Instant)>,
+}
+
+impl Clocks {
+    /// Stopped clocks, with the time already spent by White and Black.
+    pub fn new(spent: [Duration; 2]) -> Self {
+        Self {
+            spent,
+            running: None,
+        }
+    }
+
+    /// Runs the clock of the side, after stopping the running one. None stops both clocks.
+    pub fn run(&mut self, side: Option<Color>, now: Instant) {
+        if let Some((running, since)) = self.running.take() {
+            self.spent[running as usize] += now.saturating_duration_since(since);
+        }
+        self.running = side.map(|side| (side, now));
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// The time spent by the side up to now.
+    pub fn spent(&self, side: Color, now: Instant) -> Duration {
+        let running = match self.running {
+            Some((running, since)) if running == side => now.saturating_duration_since(since),
+            _ => Duration::ZERO,
+        };
+        self.spent[side as usize] + running
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_the_time_of_the_side_thinking() {
+        let start = Instant::now();
+        let second = |seconds| start + Duration::from_secs(seconds);
+        let mut clocks = Clocks::new([Duration::from_secs(60), Duration::ZERO]);
+        assert!(!clocks.is_running());
+
+        clocks.run(Some(Color::White), start);
+        assert_eq!(
+            clocks.spent(Color::White, second(5)),
+            Duration::from_secs(65)
+        );
+        clocks.run(Some(Color::Black), second(10));
+        assert_eq!(
+            clocks.spent(Color::White, second(30)),
+            Duration::from_secs(70)
+        );
+        assert_eq!(
+            clocks.spent(Color::Black, second(30)),
+            Duration::from_secs(20)
+        );
+
+        clocks.run(None, second(40));
+        assert!(!clocks.is_running());
+        assert_eq!(
+            clocks.spent(Color::Black, second(100)),
+            Duration::from_secs(30)
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! The names of the openings, with their code of the Encyclopaedia of Chess Openings (ECO),
+//! recognized from the positions of the game.
+use std::{collections::HashMap, fmt, sync::LazyLock};
+
+use owlchess::{Board, Move};
+
+use crate::game::polyglot::polyglot_key;
+
+/// The openings, one per line after the column names : the code, the name and the moves.
+const TABLE: &str = include_str!("eco.tsv");
+
+/// The headers recording the opening in the PGN.
+pub const ECO_HEADER: &str = "ECO";
+pub const OPENING_HEADER: &str = "Opening";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EcoOpening {
+    /// As `B90`.
+    pub code: String,
+    /// As `Sicilian Defense: Najdorf Variation`.
+    pub name: String,
+}
+
+impl fmt::Display for EcoOpening {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.code, self.name)
+    }
+}
+
+/// The openings by the key of the position their moves lead to. A position reached by several
+/// lines of the table keeps the first one. The Polyglot key counts the en passant square only
+/// when a pawn can take, so that a line ending with a double pawn push matches its transpositions.
+static OPENINGS: LazyLock<HashMap<u64, EcoOpening>> = LazyLock::new(|| read_table(TABLE));
+
+/// The position after the moves in SAN, or None when a move cannot be played.
+fn play_line(moves: &str) -> Option<Board> {
+    moves
+        .split_whitespace()
+        .filter(|token| !token.ends_with('.'))
+        .try_fold(Board::initial(), |board, san| {
+            let line_move = Move::from_san(san, &board).ok()?;
+            board.make_move(line_move).ok()
+        })
+}
+
+fn read_table(table: &str) -> HashMap<u64, EcoOpening> {
+    let mut openings = HashMap::new();
+    for line in table.lines().skip(1) {
+        let mut fields = line.split('\t');
+        let (Some(code), Some(name), Some(moves)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if let Some(board) = play_line(moves) {
+            openings
+                .entry(polyglot_key(&board))
+                .or_insert_with(|| EcoOpening {
+                    code: code.to_string(),
+                    name: name.to_string(),
+                });
+        }
+    }
+    openings
+}
+
+/// The opening of the position reached after the previous one : the opening of the table
+/// leading to the position, or else the previous opening. As the positions are compared rather
+/// than the moves, the openings reached by transposition are recognized.
+pub fn identify(
+    board: &Board,
+    previous: Option<&'static EcoOpening>,
+) -> Option<&'static EcoOpening> {
+    OPENINGS.get(&polyglot_key(board)).or(previous)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    fn game(moves: &[&str]) -> Game {
+        let mut game = Game::new(Board::initial());
+        for san in moves {
+            let legal_move = Move::from_san(san, game.position()).unwrap();
+            game.play_move(legal_move.into());
+        }
+        game
+    }
+
+    fn code(opening: Option<&EcoOpening>) -> Option<&str> {
+        opening.map(|opening| opening.code.as_str())
+    }
+
+    #[test]
+    fn names_the_opening_after_each_move() {
+        let mut game = game(&["e4", "c5", "Nf3", "a6", "h3"]);
+        let codes: Vec<_> = game.openings().iter().copied().map(code).collect();
+        assert_eq!(codes.len(), 6);
+        assert_eq!(codes[..3], [None, Some("B00"), Some("B20")]);
+        // Out of the table, the game keeps its last opening.
+        assert_eq!(codes[5], codes[4]);
+        assert_eq!(code(game.opening()), codes[5]);
+
+        // The openings of the moves taken back are forgotten.
+        game.truncate(2);
+        assert_eq!(game.openings().len(), 3);
+        assert_eq!(code(game.opening()), Some("B20"));
+    }
+
+    #[test]
+    fn recognizes_transpositions() {
+        let game = game(&["c4", "e6", "d4", "d5"]);
+        let opening = game.opening().unwrap();
+        assert_eq!(opening.code, "D30");
+        assert_eq!(opening.to_string(), "D30 Queen's Gambit Declined");
+    }
+
+    #[test]
+    fn every_line_of_the_table_is_legal() {
+        let lines = TABLE.lines().skip(1).count();
+        let legal = TABLE
+            .lines()
+            .skip(1)
+            .filter_map(|line| play_line(line.split('\t').nth(2)?))
+            .count();
+        assert_eq!(legal, lines);
+    }
+}
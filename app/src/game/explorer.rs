@@ -0,0 +1,222 @@
+//! The opening explorer : the moves played from each position in a collection of PGN games,
+//! with their results. The positions are keyed by their Polyglot key, which counts the en passant
+//! square only when a pawn can take, so that the games transposing into a line ending with a
+//! double pawn push meet.
+use std::{cmp::Reverse, collections::HashMap, fmt, fs, io, path::Path};
+
+use owlchess::{Board, GameStatus, Move};
+
+use crate::game::{GameMove, pgn, polyglot::polyglot_key};
+
+/// The moves are indexed up to this number of plies in each game, the explorer being about the
+/// openings.
+const MAX_PLIES: usize = 50;
+
+#[derive(Debug)]
+pub enum ExplorerError {
+    Io(io::Error),
+    /// No game of the collection could be read.
+    NoGames,
+}
+
+impl fmt::Display for ExplorerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExplorerError::Io(err) => write!(f, "{err}"),
+            ExplorerError::NoGames => write!(f, "no game found in the collection"),
+        }
+    }
+}
+
+impl std::error::Error for ExplorerError {}
+
+impl From<io::Error> for ExplorerError {
+    fn from(err: io::Error) -> Self {
+        ExplorerError::Io(err)
+    }
+}
+
+/// The games in which a move was played, by result. The unfinished games only count in `games`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MoveStats {
+    pub games: u32,
+    pub white_wins: u32,
+    pub draws: u32,
+    pub black_wins: u32,
+}
+
+impl MoveStats {
+    fn record(&mut self, result: GameStatus) {
+        self.games += 1;
+        match result {
+            GameStatus::White => self.white_wins += 1,
+            GameStatus::Draw => self.draws += 1,
+            GameStatus::Black => self.black_wins += 1,
+            GameStatus::Running => {}
+        }
+    }
+
+    /// The share of the games, from 0 to 100.
+    pub fn percent(&self, count: u32) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            f64::from(count) * 100.0 / f64::from(self.games)
+        }
+    }
+}
+
+/// A move played from the position, with its statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Continuation {
+    pub chess_move: Move,
+    pub stats: MoveStats,
+}
+
+#[derive(Debug, Default)]
+pub struct OpeningExplorer {
+    /// The moves played from each position, by key of the position.
+    positions: HashMap<u64, Vec<Continuation>>,
+    games: usize,
+    /// The games of the collection which could not be read.
+    skipped: usize,
+}
+
+impl OpeningExplorer {
+    pub fn open(path: &Path) -> Result<Self, ExplorerError> {
+        let explorer = Self::from_pgn(&fs::read_to_string(path)?);
+        if explorer.games == 0 {
+            return Err(ExplorerError::NoGames);
+        }
+        Ok(explorer)
+    }
+
+    /// Indexes the games of the PGN text, skipping those which cannot be read.
+    pub fn from_pgn(text: &str) -> Self {
+        let mut explorer = Self::default();
+        for game in pgn::read_all(text) {
+            let Ok(game) = game else {
+                explorer.skipped += 1;
+                continue;
+            };
+            explorer.games += 1;
+            let result = GameStatus::from(game.outcome());
+            for (board, played) in game.walk().take(MAX_PLIES) {
+                // The explorer is about the openings of the standard game.
+                let GameMove::Standard(played) = played else {
+                    break;
+                };
+                let continuations = explorer.positions.entry(polyglot_key(board)).or_default();
+                match continuations
+                    .iter_mut()
+                    .find(|continuation| continuation.chess_move == played)
+                {
+                    Some(continuation) => continuation.stats.record(result),
+                    None => {
+                        let mut stats = MoveStats::default();
+                        stats.record(result);
+                        continuations.push(Continuation {
+                            chess_move: played,
+                            stats,
+                        });
+                    }
+                }
+            }
+        }
+        explorer
+    }
+
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// The moves played from the position, the most played first.
+    pub fn continuations(&self, position: &Board) -> Vec<Continuation> {
+        let mut continuations = self
+            .positions
+            .get(&polyglot_key(position))
+            .cloned()
+            .unwrap_or_default();
+        continuations.sort_by_key(|continuation| Reverse(continuation.stats.games));
+        continuations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLLECTION: &str = "\
+[Result \"1-0\"]\n\n1. e4 e5 2. Nf3 Nc6 1-0\n\n\
+[Result \"0-1\"]\n\n1. e4 c5 2. Nf3 d6 0-1\n\n\
+[Result \"1/2-1/2\"]\n\n1. e4 e5 2. Nf3 Nf6 1/2-1/2\n\n\
+[Result \"*\"]\n\n1. d4 e5 *\n\n\
+[Result \"1-0\"]\n\n1. Nf3 e5 2. e4 Nc6 1-0\n\n\
+[Result \"1-0\"]\n\n1. e4 e4 1-0\n";
+
+    fn stats(games: u32, white_wins: u32, draws: u32, black_wins: u32) -> MoveStats {
+        MoveStats {
+            games,
+            white_wins,
+            draws,
+            black_wins,
+        }
+    }
+
+    /// The moves played from the position after the moves in UCI, with their statistics.
+    fn continuations(explorer: &OpeningExplorer, moves: &[&str]) -> Vec<(String, MoveStats)> {
+        let position = moves.iter().fold(Board::initial(), |board, uci| {
+            let legal_move = Move::from_uci_legal(uci, &board).unwrap();
+            board.make_move(legal_move).unwrap()
+        });
+        explorer
+            .continuations(&position)
+            .into_iter()
+            .map(|continuation| (continuation.chess_move.to_string(), continuation.stats))
+            .collect()
+    }
+
+    #[test]
+    fn counts_the_moves_and_their_results() {
+        let explorer = OpeningExplorer::from_pgn(COLLECTION);
+        assert_eq!(explorer.games(), 5);
+        assert_eq!(explorer.skipped(), 1);
+
+        assert_eq!(
+            continuations(&explorer, &[]),
+            [
+                (String::from("e2e4"), stats(3, 1, 1, 1)),
+                (String::from("d2d4"), stats(1, 0, 0, 0)),
+                (String::from("g1f3"), stats(1, 1, 0, 0)),
+            ]
+        );
+        assert_eq!(
+            continuations(&explorer, &["e2e4"]),
+            [
+                (String::from("e7e5"), stats(2, 1, 1, 0)),
+                (String::from("c7c5"), stats(1, 0, 0, 1)),
+            ]
+        );
+        // The games reaching the position by transposition count together.
+        assert_eq!(
+            continuations(&explorer, &["e2e4", "e7e5", "g1f3"]),
+            [
+                (String::from("b8c6"), stats(2, 2, 0, 0)),
+                (String::from("g8f6"), stats(1, 0, 1, 0)),
+            ]
+        );
+        assert!(continuations(&explorer, &["a2a4"]).is_empty());
+    }
+
+    #[test]
+    fn gives_the_shares_of_the_results() {
+        let e4 = stats(4, 2, 1, 1);
+        assert_eq!(e4.percent(e4.white_wins), 50.0);
+        assert_eq!(e4.percent(e4.draws), 25.0);
+        assert_eq!(MoveStats::default().percent(0), 0.0);
+    }
+}
@@ -0,0 +1,176 @@
+//! Material odds, given by a side to a weaker opponent by removing pieces from the start
+//! position.
+use std::fmt;
+
+use owlchess::{Board, CastlingSide, Cell, Color, Coord, File, Piece, Rank};
+
+/// The header recording the handicap of the game.
+pub const HEADER: &str = "Handicap";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Handicap {
+    #[default]
+    None,
+    /// The f pawn, and the first move : the other side starts the game.
+    PawnAndMove,
+    /// The queen's knight.
+    Knight,
+    /// The queen's rook, and the queenside castling with it.
+    Rook,
+    Queen,
+}
+
+impl Handicap {
+    pub const ALL: [Handicap; 5] = [
+        Handicap::None,
+        Handicap::PawnAndMove,
+        Handicap::Knight,
+        Handicap::Rook,
+        Handicap::Queen,
+    ];
+
+    /// The piece removed, and its file.
+    fn removed_piece(self) -> Option<(Piece, File)> {
+        match self {
+            Handicap::None => None,
+            Handicap::PawnAndMove => Some((Piece::Pawn, File::F)),
+            Handicap::Knight => Some((Piece::Knight, File::B)),
+            Handicap::Rook => Some((Piece::Rook, File::A)),
+            Handicap::Queen => Some((Piece::Queen, File::D)),
+        }
+    }
+
+    /// The start position with the odds given by the side, or None when the piece to remove is
+    /// not on its square or the position becomes invalid.
+    pub fn apply(self, start_position: &Board, giver: Color) -> Option<Board> {
+        let Some((piece, file)) = self.removed_piece() else {
+            return Some(start_position.clone());
+        };
+        let rank = match (giver, piece) {
+            (Color::White, Piece::Pawn) => Rank::R2,
+            (Color::White, _) => Rank::R1,
+            (Color::Black, Piece::Pawn) => Rank::R7,
+            (Color::Black, _) => Rank::R8,
+        };
+        let square = Coord::from_parts(file, rank);
+        let mut raw = *start_position.raw();
+        if raw.get(square) != Cell::from_parts(giver, piece) {
+            return None;
+        }
+        raw.put(square, Cell::EMPTY);
+        match self {
+            Handicap::Rook => raw.castling.unset(giver, CastlingSide::Queen),
+            Handicap::PawnAndMove => raw.side = giver.inv(),
+            _ => {}
+        }
+        Board::try_from(raw).ok()
+    }
+
+    /// The value of the PGN header, as in `Knight odds given by White`.
+    pub fn header_value(self, giver: Color) -> String {
+        let giver = match giver {
+            Color::White => "White",
+            Color::Black => "Black",
+        };
+        format!("{self} given by {giver}")
+    }
+}
+
+impl fmt::Display for Handicap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Handicap::None => "None",
+            Handicap::PawnAndMove => "Pawn and move",
+            Handicap::Knight => "Knight odds",
+            Handicap::Rook => "Rook odds",
+            Handicap::Queen => "Queen odds",
+        };
+        write!(f, "{label}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(handicap: Handicap, giver: Color) -> Option<String> {
+        handicap
+            .apply(&Board::initial(), giver)
+            .map(|board| board.as_fen())
+    }
+
+    #[test]
+    fn removes_the_piece_of_the_giver() {
+        let cases = [
+            (
+                Handicap::None,
+                Color::White,
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            ),
+            (
+                Handicap::Knight,
+                Color::White,
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R1BQKBNR w KQkq - 0 1",
+            ),
+            (
+                Handicap::Queen,
+                Color::Black,
+                "rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            ),
+            // The other side moves first.
+            (
+                Handicap::PawnAndMove,
+                Color::White,
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPP1PP/RNBQKBNR b KQkq - 0 1",
+            ),
+            (
+                Handicap::PawnAndMove,
+                Color::Black,
+                "rnbqkbnr/ppppp1pp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            ),
+        ];
+        for (handicap, giver, fen) in cases {
+            assert_eq!(apply(handicap, giver).as_deref(), Some(fen), "{handicap}");
+        }
+    }
+
+    #[test]
+    fn clears_the_castling_with_the_removed_rook() {
+        assert_eq!(
+            apply(Handicap::Rook, Color::White).as_deref(),
+            Some("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/1NBQKBNR w Kkq - 0 1")
+        );
+        assert_eq!(
+            apply(Handicap::Rook, Color::Black).as_deref(),
+            Some("1nbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQk - 0 1")
+        );
+    }
+
+    #[test]
+    fn needs_the_piece_on_its_start_square() {
+        let without_knight = Handicap::Knight
+            .apply(&Board::initial(), Color::White)
+            .unwrap();
+        assert_eq!(Handicap::Knight.apply(&without_knight, Color::White), None);
+        assert!(
+            Handicap::Knight
+                .apply(&without_knight, Color::Black)
+                .is_some()
+        );
+
+        let moved_pawn =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/8/5P2/PPPPP1PP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_eq!(Handicap::PawnAndMove.apply(&moved_pawn, Color::White), None);
+        let moved_rook =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/P7/R7/1PPPPPPP/1NBQKBNR b Kkq - 0 1").unwrap();
+        assert_eq!(Handicap::Rook.apply(&moved_rook, Color::White), None);
+    }
+
+    #[test]
+    fn names_the_giver_in_the_header() {
+        assert_eq!(
+            Handicap::Rook.header_value(Color::Black),
+            "Rook odds given by Black"
+        );
+    }
+}
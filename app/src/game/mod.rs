@@ -0,0 +1,536 @@
+//! The game being played : its moves history and its PGN headers.
+pub mod chess960;
+pub mod clocks;
+pub mod eco;
+pub mod explorer;
+pub mod handicap;
+pub mod openings;
+pub mod pgn;
+pub mod polyglot;
+pub mod review;
+
+use std::{collections::BTreeMap, fmt};
+
+use owlchess::{
+    Board, CastlingSide, Cell, Color, Coord, DrawReason, Move, Outcome, Piece, RawBoard,
+    board::{FenParseError, ValidateError},
+    moves::san,
+    types::OutcomeFilter,
+};
+
+use crate::game::{
+    chess960::{Castling, CastlingRights},
+    eco::EcoOpening,
+};
+
+/// The header counting the hints given to the player.
+const HINTS_HEADER: &str = "Hints";
+
+/// What is written after a move in the PGN.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Annotation {
+    /// Numeric annotation glyph, as `2` for `$2` (a mistake).
+    pub nag: Option<u8>,
+    pub comment: Option<String>,
+}
+
+/// Reads a position from a FEN. owlchess panics on a position without black king instead of
+/// reporting it, so the kings are checked first.
+pub fn parse_fen(fen: &str) -> Result<Board, FenParseError> {
+    let raw_board = RawBoard::from_fen(fen)?;
+    for color in [Color::White, Color::Black] {
+        if !raw_board
+            .cells
+            .contains(&Cell::from_parts(color, Piece::King))
+        {
+            return Err(FenParseError::Valid(ValidateError::NoKing(color)));
+        }
+    }
+    Ok(Board::try_from(&raw_board)?)
+}
+
+/// A move of the game : a move of the standard rules, or a castling of Chess960.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMove {
+    Standard(Move),
+    Castling(Castling),
+}
+
+impl GameMove {
+    pub fn src(&self) -> Coord {
+        match self {
+            GameMove::Standard(standard) => standard.src(),
+            GameMove::Castling(castling) => castling.king,
+        }
+    }
+
+    /// The destination of the move, the square of the rook for a castling.
+    pub fn dst(&self) -> Coord {
+        match self {
+            GameMove::Standard(standard) => standard.dst(),
+            GameMove::Castling(castling) => castling.rook,
+        }
+    }
+
+    /// The board after the move, None when the move cannot be played on the board.
+    pub fn make(&self, board: &Board) -> Option<Board> {
+        match self {
+            GameMove::Standard(standard) => board.make_move(*standard).ok(),
+            GameMove::Castling(castling) => castling.make(board),
+        }
+    }
+
+    /// The move in SAN, or in UCI when it cannot be played on the board.
+    pub fn san(&self, board: &Board) -> String {
+        match self {
+            GameMove::Standard(standard) => match standard.san(board) {
+                Ok(san) => san.to_string(),
+                Err(_) => standard.to_string(),
+            },
+            GameMove::Castling(castling) => {
+                let mut san = String::from(match castling.side {
+                    CastlingSide::King => "O-O",
+                    CastlingSide::Queen => "O-O-O",
+                });
+                if let Some(after) = castling.make(board)
+                    && after.is_check()
+                {
+                    san.push(if after.has_legal_moves() { '+' } else { '#' });
+                }
+                san
+            }
+        }
+    }
+}
+
+impl From<Move> for GameMove {
+    fn from(standard: Move) -> Self {
+        GameMove::Standard(standard)
+    }
+}
+
+/// The move in UCI, a castling being the king taking its rook.
+impl fmt::Display for GameMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameMove::Standard(standard) => write!(f, "{standard}"),
+            GameMove::Castling(castling) => write!(f, "{castling}"),
+        }
+    }
+}
+
+pub struct Game {
+    /// The positions, from the start position to the current one.
+    positions: Vec<Board>,
+    moves: Vec<GameMove>,
+    /// The castling rights of each position in the Chess960 games, whose boards have none.
+    castling: Option<Vec<CastlingRights>>,
+    /// The opening after each position, named once as the moves are played.
+    openings: Vec<Option<&'static EcoOpening>>,
+    outcome: Option<Outcome>,
+    /// PGN tag pairs, in export order.
+    /// The result and the setup tags are computed from the moves on export.
+    headers: Vec<(String, String)>,
+    /// The annotations of the moves, by index of the move.
+    annotations: BTreeMap<usize, Annotation>,
+}
+
+impl Game {
+    pub fn new(start: Board) -> Self {
+        let mut game = Self {
+            openings: vec![eco::identify(&start, None)],
+            positions: vec![start],
+            moves: Vec::new(),
+            castling: None,
+            outcome: None,
+            headers: pgn::default_headers(),
+            annotations: BTreeMap::new(),
+        };
+        game.outcome = game.auto_outcome();
+        game
+    }
+
+    /// A Chess960 game, from a board without castling rights and the rights of its rooks.
+    pub fn new_chess960(start: Board, castling: CastlingRights) -> Self {
+        let mut game = Self::new(start);
+        game.castling = Some(vec![castling]);
+        game.set_header("Variant", String::from(chess960::VARIANT));
+        game.outcome = game.auto_outcome();
+        game
+    }
+
+    /// A new game starting from the position after the given number of moves, with its
+    /// castling rights, as for playing the line of an engine from it.
+    pub fn continuation(&self, plies: usize) -> Game {
+        let start = self.position_at(plies);
+        match &self.castling {
+            Some(castling) => {
+                let rights = castling.get(plies).or(castling.last()).copied();
+                Game::new_chess960(start, rights.unwrap_or_default())
+            }
+            None => Game::new(start),
+        }
+    }
+
+    pub fn is_chess960(&self) -> bool {
+        self.castling.is_some()
+    }
+
+    pub fn position(&self) -> &Board {
+        &self.positions[self.moves.len()]
+    }
+
+    /// The position after the given number of moves.
+    pub fn position_at(&self, plies: usize) -> Board {
+        self.positions
+            .get(plies)
+            .unwrap_or_else(|| self.position())
+            .clone()
+    }
+
+    /// The opening after each number of moves, from the start position to the current one.
+    pub fn openings(&self) -> &[Option<&'static EcoOpening>] {
+        &self.openings
+    }
+
+    /// The opening of the current position : the last position of the table reached.
+    pub fn opening(&self) -> Option<&'static EcoOpening> {
+        self.openings[self.moves.len()]
+    }
+
+    pub fn start_position(&self) -> &Board {
+        &self.positions[0]
+    }
+
+    /// The FEN of the start position, an X-FEN in the Chess960 games.
+    pub fn start_fen(&self) -> String {
+        match &self.castling {
+            Some(castling) => chess960::fen(self.start_position(), &castling[0]),
+            None => self.start_position().as_fen(),
+        }
+    }
+
+    pub fn moves(&self) -> &[GameMove] {
+        &self.moves
+    }
+
+    /// The moves, each with the position it is played from.
+    pub fn walk(&self) -> impl Iterator<Item = (&Board, GameMove)> {
+        self.positions.iter().zip(self.moves.iter().copied())
+    }
+
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    /// The Chess960 castlings which may be played after the given number of moves.
+    pub fn castlings(&self, plies: usize) -> Vec<Castling> {
+        match (&self.castling, self.positions.get(plies)) {
+            (Some(castling), Some(board)) => castling[plies].legal_castlings(board),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The legal move given in UCI notation, after the given number of moves.
+    pub fn legal_move(&self, plies: usize, uci_move: &str) -> Option<GameMove> {
+        let board = self.positions.get(plies)?;
+        match self
+            .castlings(plies)
+            .into_iter()
+            .find(|castling| castling.to_string() == uci_move)
+        {
+            Some(castling) => Some(GameMove::Castling(castling)),
+            None => Move::from_uci_legal(uci_move, board)
+                .ok()
+                .map(GameMove::Standard),
+        }
+    }
+
+    /// The legal move given in SAN, in the current position.
+    pub fn legal_san_move(&self, san_move: &str) -> Result<GameMove, san::ParseError> {
+        let side = match san_move.trim_end_matches(['+', '#']) {
+            "O-O" => Some(CastlingSide::King),
+            "O-O-O" => Some(CastlingSide::Queen),
+            _ => None,
+        };
+        match side {
+            Some(side) if self.is_chess960() => self
+                .castlings(self.moves.len())
+                .into_iter()
+                .find(|castling| castling.side == side)
+                .map(GameMove::Castling)
+                .ok_or(san::ParseError::Convert(san::IntoMoveError::NotFound)),
+            _ => Move::from_san(san_move, self.position()).map(GameMove::Standard),
+        }
+    }
+
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header_name, _)| header_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Replaces the value of the header, or adds it at the end of the headers.
+    pub fn set_header(&mut self, name: &str, value: String) {
+        match self
+            .headers
+            .iter_mut()
+            .find(|(header_name, _)| header_name == name)
+        {
+            Some((_, old_value)) => *old_value = value,
+            None => self.headers.push((name.to_string(), value)),
+        }
+    }
+
+    /// Counts a hint given to the player, in the `Hints` header.
+    pub fn count_hint(&mut self) {
+        let hints = self
+            .header(HINTS_HEADER)
+            .and_then(|hints| hints.parse::<u32>().ok())
+            .unwrap_or(0);
+        self.set_header(HINTS_HEADER, (hints + 1).to_string());
+    }
+
+    /// The annotation of the move, given by its index.
+    pub fn annotation(&self, index: usize) -> Option<&Annotation> {
+        self.annotations.get(&index)
+    }
+
+    /// Replaces the annotations of the moves, by index of the move.
+    pub fn set_annotations(&mut self, annotations: BTreeMap<usize, Annotation>) {
+        self.annotations = annotations;
+    }
+
+    /// Plays the move, unless the game is already over.
+    /// The move must be legal in the current position.
+    pub fn play_move(&mut self, played: GameMove) -> bool {
+        let board = self.position().clone();
+        if self.is_finished()
+            || matches!(played, GameMove::Castling(castling)
+                if !self.castlings(self.moves.len()).contains(&castling))
+        {
+            return false;
+        }
+        let Some(next) = played.make(&board) else {
+            return false;
+        };
+        if let Some(castling) = &mut self.castling
+            && let Some(rights) = castling.last()
+        {
+            castling.push(rights.after_move(&board, played.src(), played.dst()));
+        }
+        self.openings.push(eco::identify(&next, self.opening()));
+        self.positions.push(next);
+        self.moves.push(played);
+        self.outcome = self.auto_outcome();
+        true
+    }
+
+    /// The end of the game shown by the position : checkmate, stalemate, insufficient material,
+    /// 75 moves without capture nor pawn move or fivefold repetition.
+    fn auto_outcome(&self) -> Option<Outcome> {
+        let plies = self.moves.len();
+        let board = self.position();
+        let outcome = match board.calc_outcome() {
+            // A castling may be the only move left.
+            Some(Outcome::Draw(DrawReason::Stalemate)) if !self.castlings(plies).is_empty() => {
+                board.calc_draw_simple().map(Outcome::Draw)
+            }
+            outcome => outcome,
+        };
+        if let Some(outcome) = outcome
+            && outcome.passes(OutcomeFilter::Strict)
+        {
+            return Some(outcome);
+        }
+        let rights = self.castling.as_ref().map(|castling| castling[plies]);
+        let repetitions = (0..=plies)
+            .filter(|index| {
+                self.positions[*index].zobrist_hash() == board.zobrist_hash()
+                    && self.castling.as_ref().map(|castling| castling[*index]) == rights
+            })
+            .count();
+        (repetitions >= 5).then_some(Outcome::Draw(DrawReason::Repeat5))
+    }
+
+    /// Takes back the moves played after the given number of moves.
+    pub fn truncate(&mut self, plies: usize) {
+        if self.moves.len() <= plies {
+            return;
+        }
+        self.positions.truncate(plies + 1);
+        self.openings.truncate(plies + 1);
+        self.moves.truncate(plies);
+        if let Some(castling) = &mut self.castling {
+            castling.truncate(plies + 1);
+        }
+        self.annotations.split_off(&plies);
+        self.outcome = self.auto_outcome();
+    }
+
+    /// Plays the legal move leading to the given position, as given back by the chessboard
+    /// component after a move, from the position after the given number of moves : the moves
+    /// played after it are taken back. Returns whether such a move was found.
+    pub fn play_to_position_from(&mut self, plies: usize, fen: &str) -> bool {
+        let Ok(target) = parse_fen(fen) else {
+            return false;
+        };
+        let position = self.position_at(plies);
+        let matching_move = owlchess::movegen::legal::gen_all(&position)
+            .iter()
+            .copied()
+            .find(|legal_move| {
+                position
+                    .make_move(*legal_move)
+                    .is_ok_and(|board| board == target)
+            });
+        match matching_move {
+            Some(matching_move) => {
+                self.truncate(plies);
+                self.play_move(GameMove::Standard(matching_move))
+            }
+            None => false,
+        }
+    }
+
+    /// Plays the Chess960 castling of the king onto the rook, as dropped on the chessboard, from
+    /// the position after the given number of moves : the moves played after it are taken back.
+    /// Returns whether such a castling may be played.
+    pub fn castle(&mut self, plies: usize, king: Coord, rook: Coord) -> bool {
+        let Some(castling) = self
+            .castlings(plies)
+            .into_iter()
+            .find(|castling| castling.king == king && castling.rook == rook)
+        else {
+            return false;
+        };
+        self.truncate(plies);
+        self.play_move(GameMove::Castling(castling))
+    }
+
+    /// Plays the moves (in UCI notation) from the position after the given number of moves : the
+    /// moves played after it are taken back. Stops at the first illegal move, and returns whether
+    /// a move was played. A finished game is left as it is.
+    pub fn play_line(&mut self, plies: usize, moves: &[String]) -> bool {
+        if self.is_finished() {
+            return false;
+        }
+        let Some(first) = moves.first() else {
+            return false;
+        };
+        if self.legal_move(plies, first).is_none() {
+            return false;
+        }
+        self.truncate(plies);
+        let mut played = false;
+        for uci_move in moves {
+            match self.legal_move(self.moves.len(), uci_move) {
+                Some(legal_move) if self.play_move(legal_move) => played = true,
+                _ => break,
+            }
+        }
+        played
+    }
+
+    /// Ends the game with the given outcome, if it is not already over.
+    pub fn set_outcome(&mut self, outcome: Outcome) {
+        if !self.is_finished() {
+            self.outcome = Some(outcome);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use owlchess::{DrawReason, WinReason};
+
+    use super::*;
+
+    fn line(moves: &[&str]) -> Vec<String> {
+        moves.iter().map(|uci_move| uci_move.to_string()).collect()
+    }
+
+    #[test]
+    fn plays_a_line_replacing_the_later_moves() {
+        let mut game = Game::new(Board::initial());
+        assert!(game.play_line(0, &line(&["e2e4", "e7e5", "g1f3"])));
+        assert!(game.play_line(1, &line(&["c7c5", "g1f3", "e1e3"])));
+        let moves: Vec<String> = game.moves().iter().map(GameMove::to_string).collect();
+        assert_eq!(moves, ["e2e4", "c7c5", "g1f3"]);
+    }
+
+    #[test]
+    fn leaves_the_game_when_no_move_is_played() {
+        let mut game = Game::new(Board::initial());
+        assert!(game.play_line(0, &line(&["e2e4", "e7e5"])));
+        assert!(!game.play_line(1, &line(&["e7e4"])));
+        assert!(!game.play_line(1, &[]));
+        assert_eq!(game.moves().len(), 2);
+    }
+
+    #[test]
+    fn refuses_lines_in_a_finished_game() {
+        let mut game = Game::new(Board::initial());
+        // Fool's mate.
+        assert!(game.play_line(0, &line(&["f2f3", "e7e5", "g2g4", "d8h4"])));
+        assert!(game.is_finished());
+        assert!(!game.play_line(4, &line(&["a2a3"])));
+        assert!(!game.play_line(2, &line(&["g1h3"])));
+        assert_eq!(game.moves().len(), 4);
+
+        let mut game = Game::new(Board::initial());
+        assert!(game.play_line(0, &line(&["d2d4"])));
+        game.set_outcome(Outcome::Win {
+            side: Color::Black,
+            reason: WinReason::Resign,
+        });
+        assert!(!game.play_line(1, &line(&["d7d5"])));
+        game.set_outcome(Outcome::Draw(DrawReason::Agreement));
+        assert_eq!(game.moves().len(), 1);
+        assert!(matches!(game.outcome(), Some(Outcome::Win { .. })));
+    }
+
+    #[test]
+    fn castles_in_chess960_games_only() {
+        let coord = |name: &str| name.parse::<Coord>().unwrap();
+        let (start, castling) = chess960::parse_fen("4k3/8/8/8/8/8/8/RK5R w KQ - 0 1").unwrap();
+        let mut game = Game::new_chess960(start, castling);
+        assert_eq!(game.header("Variant"), Some(chess960::VARIANT));
+        // The king takes its rook, which is also how the engines write castling.
+        assert_eq!(
+            game.legal_move(0, "b1h1")
+                .map(|castling| castling.to_string()),
+            Some(String::from("b1h1"))
+        );
+        assert!(!game.castle(0, coord("b1"), coord("e1")));
+        assert!(game.castle(0, coord("b1"), coord("a1")));
+        assert_eq!(game.position().as_fen(), "4k3/8/8/8/8/8/8/2KR3R b - - 1 1");
+        assert_eq!(game.moves()[0].san(game.start_position()), "O-O-O");
+        // Both rights are lost with the king.
+        assert!(game.play_line(1, &line(&["e8e7"])));
+        assert!(game.castlings(2).is_empty());
+        assert_eq!(game.start_fen(), "4k3/8/8/8/8/8/8/RK5R w KQ - 0 1");
+
+        let mut game = Game::new(Board::from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap());
+        assert!(game.castlings(0).is_empty());
+        assert!(!game.castle(0, coord("e1"), coord("h1")));
+        assert!(game.play_line(0, &line(&["e1g1"])));
+    }
+
+    #[test]
+    fn counts_hints_in_a_header() {
+        let mut game = Game::new(Board::initial());
+        assert_eq!(game.header("Hints"), None);
+        game.count_hint();
+        game.count_hint();
+        assert_eq!(game.header("Hints"), Some("2"));
+    }
+}
@@ -0,0 +1,110 @@
+//! The openings played from in engine matches, read from EPD or PGN files.
+use std::fmt;
+
+use owlchess::Board;
+
+use crate::game::{
+    Game, GameMove,
+    pgn::{self, PgnError},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpeningsError {
+    Empty,
+    /// An EPD line is not a position, given with its line number.
+    InvalidEpd {
+        line: usize,
+        error: owlchess::board::FenParseError,
+    },
+    /// A game of the PGN cannot be read, given with its number.
+    InvalidPgn {
+        game: usize,
+        error: PgnError,
+    },
+}
+
+impl fmt::Display for OpeningsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpeningsError::Empty => write!(f, "no opening found"),
+            OpeningsError::InvalidEpd { line, error } => {
+                write!(f, "invalid position at line {line}: {error}")
+            }
+            OpeningsError::InvalidPgn { game, error } => write!(f, "invalid game {game}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for OpeningsError {}
+
+/// A start position, and the moves played from it.
+#[derive(Debug, Clone)]
+pub struct Opening {
+    pub start: Board,
+    pub moves: Vec<GameMove>,
+}
+
+impl Opening {
+    fn from_game(game: &Game) -> Self {
+        Self {
+            start: game.position_at(0),
+            moves: game.moves().to_vec(),
+        }
+    }
+
+    /// A new game, with the moves of the opening already played.
+    pub fn game(&self) -> Game {
+        let mut game = Game::new(self.start.clone());
+        for opening_move in &self.moves {
+            game.play_move(*opening_move);
+        }
+        game
+    }
+}
+
+/// Reads an EPD line : the first four fields of a FEN, followed by operations which are ignored.
+fn read_epd_line(line: &str) -> Result<Board, owlchess::board::FenParseError> {
+    let fields = line.split_whitespace().take(4).collect::<Vec<_>>();
+    super::parse_fen(&format!("{} 0 1", fields.join(" ")))
+}
+
+/// Reads the openings of a PGN collection, or of an EPD file with one position per line.
+pub fn read(text: &str) -> Result<Vec<Opening>, OpeningsError> {
+    let is_pgn = text
+        .lines()
+        .map(str::trim)
+        .any(|line| line.starts_with('[') || line.starts_with("1."));
+    let openings = if is_pgn {
+        pgn::read_all(text)
+            .into_iter()
+            .enumerate()
+            .map(|(index, game)| match game {
+                Ok(game) => Ok(Opening::from_game(&game)),
+                Err(error) => Err(OpeningsError::InvalidPgn {
+                    game: index + 1,
+                    error,
+                }),
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        text.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(index, line)| {
+                read_epd_line(line)
+                    .map(|start| Opening {
+                        start,
+                        moves: Vec::new(),
+                    })
+                    .map_err(|error| OpeningsError::InvalidEpd {
+                        line: index + 1,
+                        error,
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    if openings.is_empty() {
+        return Err(OpeningsError::Empty);
+    }
+    Ok(openings)
+}
@@ -0,0 +1,582 @@
+//! Reading and writing games in the Portable Game Notation.
+use std::{
+    fmt::{self, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use owlchess::{Board, Color, DrawReason, GameStatus, Outcome, RawBoard, WinReason, moves::san};
+
+use crate::game::{Game, chess960, eco};
+
+const MAX_LINE_LENGTH: usize = 80;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgnError {
+    /// Neither tag pairs nor moves were found.
+    Empty,
+    InvalidTag(String),
+    InvalidFen(owlchess::board::FenParseError),
+    IllegalMove {
+        label: String,
+        error: san::ParseError,
+    },
+    MoveAfterEnd(String),
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::Empty => write!(f, "no game found"),
+            PgnError::InvalidTag(line) => write!(f, "invalid tag pair {line}"),
+            PgnError::InvalidFen(err) => write!(f, "invalid FEN tag: {err}"),
+            PgnError::IllegalMove { label, error } => write!(f, "cannot play {label}: {error}"),
+            PgnError::MoveAfterEnd(label) => {
+                write!(f, "cannot play {label}: the game is already over")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+/// The seven tag roster, with the values of a new game.
+pub fn default_headers() -> Vec<(String, String)> {
+    [
+        ("Event", String::from("Casual game")),
+        ("Site", String::from("?")),
+        ("Date", today()),
+        ("Round", String::from("-")),
+        ("White", String::from("?")),
+        ("Black", String::from("?")),
+        ("Result", String::from("*")),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value))
+    .collect()
+}
+
+/// The current date, in the PGN format (YYYY.MM.DD).
+fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    // Converts days since the epoch into a civil date (Howard Hinnant's algorithm).
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}.{month:02}.{day:02}")
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn unescape(value: &str) -> String {
+    value.replace("\\\"", "\"").replace("\\\\", "\\")
+}
+
+/// The move number and the move, as shown in error messages.
+fn move_label(board: &Board, san: &str) -> String {
+    let dots = match board.side() {
+        Color::White => ".",
+        Color::Black => "...",
+    };
+    format!("{}{dots} {san}", board.raw().move_number)
+}
+
+pub fn write(game: &Game) -> String {
+    let mut pgn = String::new();
+    let result = GameStatus::from(game.outcome()).to_string();
+    for (name, value) in game.headers() {
+        let value = if name == "Result" { &result } else { value };
+        let _ = writeln!(pgn, "[{name} \"{}\"]", escape(value));
+    }
+    // The opening is named from the moves, unless the game already names it.
+    if game.header(eco::ECO_HEADER).is_none()
+        && game.header(eco::OPENING_HEADER).is_none()
+        && let Some(opening) = game.opening()
+    {
+        let _ = writeln!(pgn, "[{} \"{}\"]", eco::ECO_HEADER, opening.code);
+        let _ = writeln!(
+            pgn,
+            "[{} \"{}\"]",
+            eco::OPENING_HEADER,
+            escape(&opening.name)
+        );
+    }
+    if game.is_chess960() || *game.start_position().raw() != RawBoard::initial() {
+        let _ = writeln!(pgn, "[SetUp \"1\"]");
+        let _ = writeln!(pgn, "[FEN \"{}\"]", game.start_fen());
+    }
+    pgn.push('\n');
+
+    let movetext = movetext(game);
+    let mut line_length = 0;
+    for word in movetext.split(' ') {
+        if line_length > 0 && line_length + 1 + word.len() > MAX_LINE_LENGTH {
+            pgn.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            pgn.push(' ');
+            line_length += 1;
+        }
+        pgn.push_str(word);
+        line_length += word.len();
+    }
+    pgn.push('\n');
+    pgn
+}
+
+/// The moves with their numbers and annotations, then the result.
+fn movetext(game: &Game) -> String {
+    let mut words = Vec::new();
+    // Black moves get their number at the start, and after a comment.
+    let mut needs_number = true;
+    for (index, (board, played)) in game.walk().enumerate() {
+        let number = board.raw().move_number;
+        match board.side() {
+            Color::White => words.push(format!("{number}.")),
+            Color::Black if needs_number => words.push(format!("{number}...")),
+            Color::Black => {}
+        }
+        words.push(played.san(board));
+        needs_number = false;
+        if let Some(annotation) = game.annotation(index) {
+            if let Some(nag) = annotation.nag {
+                words.push(format!("${nag}"));
+            }
+            if let Some(comment) = &annotation.comment {
+                words.push(format!("{{{}}}", comment.replace('}', ")")));
+                needs_number = true;
+            }
+        }
+    }
+    // The results not shown by the position are explained before the result.
+    match game.outcome() {
+        Some(Outcome::Win {
+            side,
+            reason: WinReason::Resign,
+        }) => words.push(match side {
+            Color::White => String::from("{Black resigns}"),
+            Color::Black => String::from("{White resigns}"),
+        }),
+        Some(Outcome::Draw(DrawReason::Agreement)) => {
+            words.push(String::from("{Draw by agreement}"))
+        }
+        _ => {}
+    }
+    words.push(GameStatus::from(game.outcome()).to_string());
+    words.join(" ")
+}
+
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let content = line.strip_prefix('[')?.strip_suffix(']')?.trim();
+    let (name, value) = content.split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((name.to_string(), unescape(value)))
+}
+
+/// Splits the movetext into tokens (moves, move numbers and result),
+/// skipping comments, variations and annotations.
+fn tokenize(movetext: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut chars = movetext.chars();
+    let mut variations_depth = 0;
+
+    while let Some(c) = chars.next() {
+        let is_separator = c.is_whitespace() || matches!(c, '{' | ';' | '(' | ')');
+        if is_separator && !current.is_empty() {
+            if variations_depth == 0 {
+                tokens.push(std::mem::take(&mut current));
+            } else {
+                current.clear();
+            }
+        }
+        match c {
+            '{' => {
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            ';' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' => variations_depth += 1,
+            ')' => variations_depth = (variations_depth - 1).max(0),
+            c if c.is_whitespace() => {}
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() && variations_depth == 0 {
+        tokens.push(current);
+    }
+
+    tokens.retain(|token| !token.starts_with('$'));
+    tokens
+}
+
+fn parse_result(token: &str) -> Option<GameStatus> {
+    match token {
+        "1-0" => Some(GameStatus::White),
+        "0-1" => Some(GameStatus::Black),
+        "1/2-1/2" => Some(GameStatus::Draw),
+        "*" => Some(GameStatus::Running),
+        _ => None,
+    }
+}
+
+/// Removes the annotations of a SAN move, and spells castling with letters.
+pub fn normalize_san(token: &str) -> String {
+    token
+        .trim_end_matches(['!', '?'])
+        .replace("0-0-0", "O-O-O")
+        .replace("0-0", "O-O")
+}
+
+/// Splits a PGN text holding several games : a game starts with its tag pairs, after the
+/// movetext of the previous game.
+fn split_games(text: &str) -> Vec<String> {
+    let mut games = Vec::new();
+    let mut current = String::new();
+    let mut in_movetext = false;
+    for line in text.lines() {
+        let is_tag = parse_tag(line.trim()).is_some();
+        if is_tag && in_movetext {
+            games.push(std::mem::take(&mut current));
+            in_movetext = false;
+        } else if !is_tag && !line.trim().is_empty() {
+            in_movetext = true;
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    if !current.trim().is_empty() {
+        games.push(current);
+    }
+    games
+}
+
+/// Reads every game of the PGN text, such as a collection of games.
+pub fn read_all(text: &str) -> Vec<Result<Game, PgnError>> {
+    split_games(text).iter().map(|game| read(game)).collect()
+}
+
+/// Reads the first game of the PGN text.
+pub fn read(text: &str) -> Result<Game, PgnError> {
+    let mut headers = Vec::new();
+    let mut lines = text.lines().peekable();
+    while let Some(line) = lines.peek() {
+        let line = line.trim();
+        if line.is_empty() {
+            lines.next();
+        } else if line.starts_with('[') {
+            headers.push(parse_tag(line).ok_or_else(|| PgnError::InvalidTag(line.to_string()))?);
+            lines.next();
+        } else {
+            break;
+        }
+    }
+    let movetext = lines.collect::<Vec<_>>().join("\n");
+    if headers.is_empty() && movetext.trim().is_empty() {
+        return Err(PgnError::Empty);
+    }
+
+    let fen = headers
+        .iter()
+        .find(|(name, _)| name == "FEN")
+        .map(|(_, fen)| fen.as_str());
+    let is_chess960 = headers
+        .iter()
+        .any(|(name, value)| name == "Variant" && chess960::is_variant(value));
+    let mut game = match (fen, is_chess960) {
+        (Some(fen), true) => {
+            let (start, castling) = chess960::parse_fen(fen).map_err(PgnError::InvalidFen)?;
+            Game::new_chess960(start, castling)
+        }
+        (None, true) => {
+            let (start, castling) = chess960::start_position(chess960::STANDARD_POSITION);
+            Game::new_chess960(start, castling)
+        }
+        (Some(fen), false) => Game::new(super::parse_fen(fen).map_err(PgnError::InvalidFen)?),
+        (None, false) => Game::new(Board::initial()),
+    };
+    for (name, value) in headers {
+        if name != "FEN" && name != "SetUp" {
+            game.set_header(&name, value);
+        }
+    }
+
+    let mut result = None;
+    for token in tokenize(&movetext) {
+        if let Some(status) = parse_result(&token) {
+            result = Some(status);
+            break;
+        }
+        // Move numbers may be stuck to the move (as in "1.e4").
+        let token = match token.find(|c: char| !c.is_ascii_digit()) {
+            Some(index) if index > 0 && token[index..].starts_with('.') => {
+                token[index..].trim_start_matches('.')
+            }
+            _ => token.as_str(),
+        };
+        if token.is_empty() {
+            continue;
+        }
+        let san = normalize_san(token);
+        let board = game.position().clone();
+        let label = move_label(&board, &san);
+        if game.is_finished() {
+            return Err(PgnError::MoveAfterEnd(label));
+        }
+        let legal_move = game
+            .legal_san_move(&san)
+            .map_err(|error| PgnError::IllegalMove {
+                label: label.clone(),
+                error,
+            })?;
+        game.play_move(legal_move);
+    }
+
+    let result = result.or_else(|| game.header("Result").and_then(parse_result));
+    match result {
+        Some(GameStatus::White) => game.set_outcome(Outcome::Win {
+            side: Color::White,
+            reason: WinReason::Unknown,
+        }),
+        Some(GameStatus::Black) => game.set_outcome(Outcome::Win {
+            side: Color::Black,
+            reason: WinReason::Unknown,
+        }),
+        Some(GameStatus::Draw) => game.set_outcome(Outcome::Draw(DrawReason::Unknown)),
+        _ => {}
+    }
+
+    Ok(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use owlchess::Move;
+
+    use super::*;
+    use crate::game::{Annotation, GameMove};
+
+    fn uci_moves(game: &Game) -> Vec<String> {
+        game.moves().iter().map(GameMove::to_string).collect()
+    }
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for uci in moves {
+            let legal_move = Move::from_uci_legal(uci, game.position()).unwrap();
+            assert!(game.play_move(legal_move.into()));
+        }
+    }
+
+    #[test]
+    fn round_trips_a_game_from_a_fen() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 3 20";
+        let mut game = Game::new(Board::from_fen(fen).unwrap());
+        game.set_header("White", String::from("Player"));
+        play(&mut game, &["e8c8", "e1g1", "h8h1", "g1h1"]);
+
+        let pgn = write(&game);
+        assert!(pgn.contains("[SetUp \"1\"]"));
+        assert!(pgn.contains(&format!("[FEN \"{fen}\"]")));
+        assert!(pgn.contains("20... O-O-O 21. O-O Rh1+ 22. Kxh1 *"));
+
+        let read_back = read(&pgn).unwrap();
+        assert_eq!(read_back.start_position().as_fen(), fen);
+        assert_eq!(uci_moves(&read_back), uci_moves(&game));
+        assert_eq!(read_back.header("White"), Some("Player"));
+        assert_eq!(read_back.header("FEN"), None);
+        assert_eq!(write(&read_back), pgn);
+    }
+
+    #[test]
+    fn round_trips_a_chess960_game() {
+        // The rooks on b1 and h1, the king on g1: O-O leaves the king and the rook in place.
+        let fen = "1r4kr/pppppppp/8/8/8/8/PPPPPPPP/1R4KR w KQkq - 0 1";
+        let (start, castling) = chess960::parse_fen(fen).unwrap();
+        let mut game = Game::new_chess960(start, castling);
+        play(&mut game, &["e2e4", "e7e5"]);
+        assert!(game.castle(2, "g1".parse().unwrap(), "h1".parse().unwrap()));
+        assert!(game.castle(3, "g8".parse().unwrap(), "b8".parse().unwrap()));
+
+        let pgn = write(&game);
+        assert!(pgn.contains("[Variant \"Chess960\"]"));
+        assert!(pgn.contains(&format!("[FEN \"{fen}\"]")));
+        assert!(pgn.contains("1. e4 e5 2. O-O O-O-O *"));
+
+        let read_back = read(&pgn).unwrap();
+        assert!(read_back.is_chess960());
+        assert_eq!(read_back.start_fen(), fen);
+        assert_eq!(uci_moves(&read_back), ["e2e4", "e7e5", "g1h1", "g8b8"]);
+        assert_eq!(write(&read_back), pgn);
+    }
+
+    #[test]
+    fn round_trips_annotations_and_result() {
+        let mut game = Game::new(Board::initial());
+        play(&mut game, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+        game.set_annotations(
+            [
+                (
+                    1,
+                    Annotation {
+                        nag: None,
+                        comment: Some(String::from("Black {strikes}")),
+                    },
+                ),
+                (
+                    2,
+                    Annotation {
+                        nag: Some(4),
+                        comment: None,
+                    },
+                ),
+            ]
+            .into(),
+        );
+
+        let pgn = write(&game);
+        assert!(pgn.contains("1. f3 e5 {Black {strikes)} 2. g4 $4 Qh4# 0-1"));
+        assert!(pgn.contains("[Result \"0-1\"]"));
+
+        let read_back = read(&pgn).unwrap();
+        assert_eq!(uci_moves(&read_back), uci_moves(&game));
+        assert_eq!(
+            GameStatus::from(read_back.outcome()),
+            GameStatus::from(game.outcome())
+        );
+    }
+
+    #[test]
+    fn round_trips_escaped_quotes() {
+        let mut game = Game::new(Board::initial());
+        game.set_header("Event", String::from(r#"The "big" one \ final"#));
+        let pgn = write(&game);
+        assert!(pgn.contains(r#"[Event "The \"big\" one \\ final"]"#));
+        assert_eq!(
+            read(&pgn).unwrap().header("Event"),
+            Some(r#"The "big" one \ final"#)
+        );
+    }
+
+    #[test]
+    fn keeps_resignations_and_agreements() {
+        let mut game = Game::new(Board::initial());
+        play(&mut game, &["e2e4"]);
+        game.set_outcome(Outcome::Win {
+            side: Color::White,
+            reason: WinReason::Resign,
+        });
+        let pgn = write(&game);
+        assert!(pgn.contains("1. e4 {Black resigns} 1-0"));
+        assert_eq!(
+            GameStatus::from(read(&pgn).unwrap().outcome()),
+            GameStatus::White
+        );
+
+        let mut game = Game::new(Board::initial());
+        game.set_outcome(Outcome::Draw(DrawReason::Agreement));
+        let read_back = read(&write(&game)).unwrap();
+        assert_eq!(GameStatus::from(read_back.outcome()), GameStatus::Draw);
+    }
+
+    #[test]
+    fn reads_comments_nags_and_variations() {
+        let game = read(
+            "[Event \"Test\"]\n\n\
+             1. e4 {Best by test} e5!? $1 (1... c5 2. Nf3 (2. c3) d6) 2. Nf3 ; a rest of line comment\n\
+             Nc6?! $6 3. Bb5 a6 *\n",
+        )
+        .unwrap();
+        assert_eq!(
+            uci_moves(&game),
+            ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6"]
+        );
+        assert!(!game.is_finished());
+    }
+
+    #[test]
+    fn reads_stuck_move_numbers_and_zero_castling() {
+        let game = read(
+            "1.e4 e5 2.Nf3 Nc6 3.Bc4 Bc5 4.0-0 Nf6 5.d3 d6 6.Bg5 h6 7.Bh4 Be6 8.Nc3 Qd7 \
+             9.Qd2 0-0-0 10.a3 10...Kb8 1/2-1/2",
+        )
+        .unwrap();
+        let moves = uci_moves(&game);
+        assert_eq!(moves[6], "e1g1");
+        assert_eq!(moves[17], "e8c8");
+        assert_eq!(moves.last().map(String::as_str), Some("c8b8"));
+        assert_eq!(GameStatus::from(game.outcome()), GameStatus::Draw);
+    }
+
+    #[test]
+    fn reports_illegal_moves_and_moves_after_the_end() {
+        assert_eq!(read("").err(), Some(PgnError::Empty));
+        assert!(matches!(
+            read("1. e4 e5 2. Ke3").err(),
+            Some(PgnError::IllegalMove { label, .. }) if label == "2. Ke3"
+        ));
+        assert!(matches!(
+            read("1. f3 e5 2. g4 Qh4# 3. a3").err(),
+            Some(PgnError::MoveAfterEnd(label)) if label == "3. a3"
+        ));
+        assert!(matches!(
+            read("[Event \"Unfinished]\n1. e4").err(),
+            Some(PgnError::InvalidTag(_))
+        ));
+        // owlchess would panic on the missing black king.
+        assert!(matches!(
+            read("[FEN \"8/8/8/8/8/8/8/K6R w - - 0 1\"]\n1. Rh8").err(),
+            Some(PgnError::InvalidFen(_))
+        ));
+    }
+
+    #[test]
+    fn splits_collections() {
+        let text = "[Event \"First\"]\n[Result \"1-0\"]\n\n1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n\n\
+                    [Event \"Second\"]\n[SetUp \"1\"]\n[FEN \"8/8/8/8/8/8/k7/4K2R w K - 0 1\"]\n\n1. O-O *\n\
+                    [Event \"Third\"]\n\n1. e4 Ke7 Ke2 *\n\
+                    [Event \"Fourth\"]\n1. d4\n";
+        let games = read_all(text);
+        assert_eq!(games.len(), 4);
+
+        let first = games[0].as_ref().unwrap();
+        assert_eq!(first.header("Event"), Some("First"));
+        assert_eq!(GameStatus::from(first.outcome()), GameStatus::White);
+        let second = games[1].as_ref().unwrap();
+        assert_eq!(second.header("Event"), Some("Second"));
+        assert_eq!(uci_moves(second), ["e1g1"]);
+        assert!(matches!(&games[2], Err(PgnError::IllegalMove { .. })));
+        let fourth = games[3].as_ref().unwrap();
+        assert_eq!(uci_moves(fourth), ["d2d4"]);
+    }
+
+    #[test]
+    fn normalizes_san() {
+        assert_eq!(normalize_san("0-0-0!?"), "O-O-O");
+        assert_eq!(normalize_san("Nf3?"), "Nf3");
+        assert_eq!(normalize_san("0-0+"), "O-O+");
+    }
+}
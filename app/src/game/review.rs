@@ -0,0 +1,426 @@
+//! The review of a game : how much each move has lost, according to the engine.
+use std::collections::BTreeMap;
+
+use owlchess::{Color, Outcome, types::OutcomeFilter};
+
+use crate::{
+    engine::analysis::Score,
+    game::{Annotation, Game, GameMove},
+};
+
+/// The evaluations are bounded, so that a won position spoiled into a less won one does not
+/// weigh like a blunder.
+pub const MAX_CENTIPAWNS: i32 = 1000;
+
+/// The engine verdict on a position of the game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionEval {
+    /// From the point of view of the side to move.
+    pub score: Score,
+    /// The best move, in UCI notation. None when the game is over in the position.
+    pub best_move: Option<String>,
+}
+
+impl PositionEval {
+    /// The evaluation of the position after the given number of moves, when it needs no search :
+    /// the game is over in it.
+    pub fn without_search(game: &Game, plies: usize) -> Option<Self> {
+        if plies < game.moves().len() {
+            return None;
+        }
+        let score = match game.outcome()? {
+            outcome if !outcome.passes(OutcomeFilter::Strict) => return None,
+            Outcome::Win { .. } => Score::Mate(0),
+            Outcome::Draw(_) => Score::Centipawns(0),
+        };
+        Some(Self {
+            score,
+            best_move: None,
+        })
+    }
+
+    /// The evaluation in centipawns from the point of view of White, within the bounds.
+    pub fn white_centipawns(&self, side_to_move: Color) -> i32 {
+        let centipawns = match self.score {
+            Score::Centipawns(centipawns) => centipawns.clamp(-MAX_CENTIPAWNS, MAX_CENTIPAWNS),
+            Score::Mate(moves) if moves > 0 => MAX_CENTIPAWNS,
+            // The side to move is mated, or gets mated.
+            Score::Mate(_) => -MAX_CENTIPAWNS,
+        };
+        match side_to_move {
+            Color::White => centipawns,
+            Color::Black => -centipawns,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MoveClass {
+    Best,
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl MoveClass {
+    fn from_loss(loss: i32) -> Self {
+        match loss {
+            ..=10 => MoveClass::Best,
+            11..50 => MoveClass::Good,
+            50..100 => MoveClass::Inaccuracy,
+            100..300 => MoveClass::Mistake,
+            _ => MoveClass::Blunder,
+        }
+    }
+
+    /// The numeric annotation glyph of the bad moves.
+    pub fn nag(self) -> Option<u8> {
+        match self {
+            MoveClass::Best | MoveClass::Good => None,
+            MoveClass::Inaccuracy => Some(6),
+            MoveClass::Mistake => Some(2),
+            MoveClass::Blunder => Some(4),
+        }
+    }
+
+    /// The glyph, as written after the move.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            MoveClass::Best | MoveClass::Good => "",
+            MoveClass::Inaccuracy => "?!",
+            MoveClass::Mistake => "?",
+            MoveClass::Blunder => "??",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MoveClass::Best => "Best",
+            MoveClass::Good => "Good",
+            MoveClass::Inaccuracy => "Inaccuracy",
+            MoveClass::Mistake => "Mistake",
+            MoveClass::Blunder => "Blunder",
+        }
+    }
+}
+
+/// The winning chances of White, from 0 to 100, as estimated from the evaluation.
+fn white_win_percent(centipawns: i32) -> f64 {
+    50.0 + 50.0 * (2.0 / (1.0 + (-0.003_682_08 * f64::from(centipawns)).exp()) - 1.0)
+}
+
+#[derive(Debug, Clone)]
+pub struct MoveReview {
+    pub side: Color,
+    pub played: GameMove,
+    /// The evaluations before and after the move, from the point of view of White.
+    pub eval_before: i32,
+    pub eval_after: i32,
+    /// The centipawns lost by the move, compared to the best move.
+    pub loss: i32,
+    pub class: MoveClass,
+    /// From 0 to 100, according to the winning chances lost by the move.
+    pub accuracy: f64,
+    /// The best move, when another move was played.
+    pub best_move: Option<GameMove>,
+}
+
+impl MoveReview {
+    /// The comment written after the move in the PGN.
+    fn comment(&self, game: &Game, index: usize) -> String {
+        let mut comment = format!(
+            "({:+.2} -> {:+.2}) {}.",
+            f64::from(self.eval_before) / 100.0,
+            f64::from(self.eval_after) / 100.0,
+            self.class.label()
+        );
+        if let Some(best_move) = self.best_move {
+            let san = best_move.san(&game.position_at(index));
+            comment.push_str(&format!(" {san} was best."));
+        }
+        comment
+    }
+}
+
+/// Reviews the moves of the game, from the evaluations of every position, starting from the
+/// start position.
+pub fn review_moves(game: &Game, evals: &[PositionEval]) -> Vec<MoveReview> {
+    let mut reviews = Vec::new();
+    for (index, (board, played)) in game.walk().enumerate() {
+        let (Some(before), Some(after)) = (evals.get(index), evals.get(index + 1)) else {
+            break;
+        };
+        let side = board.side();
+        let eval_before = before.white_centipawns(side);
+        let eval_after = after.white_centipawns(side.inv());
+        let best_move = before
+            .best_move
+            .as_ref()
+            .and_then(|best_move| game.legal_move(index, best_move))
+            .filter(|best_move| *best_move != played);
+        let (loss, win_percent_loss) = match side {
+            Color::White => (
+                eval_before - eval_after,
+                white_win_percent(eval_before) - white_win_percent(eval_after),
+            ),
+            Color::Black => (
+                eval_after - eval_before,
+                white_win_percent(eval_after) - white_win_percent(eval_before),
+            ),
+        };
+        // The evaluations of two searches differ a little, even for the best move.
+        let loss = if best_move.is_none() { 0 } else { loss.max(0) };
+        let accuracy = if best_move.is_none() {
+            100.0
+        } else {
+            (103.1668 * (-0.04354 * win_percent_loss.max(0.0)).exp() - 3.1669).clamp(0.0, 100.0)
+        };
+        reviews.push(MoveReview {
+            side,
+            played,
+            eval_before,
+            eval_after,
+            loss,
+            class: MoveClass::from_loss(loss),
+            accuracy,
+            best_move,
+        });
+    }
+    reviews
+}
+
+/// The NAGs and the comments of the inaccuracies, mistakes and blunders.
+pub fn annotations(game: &Game, reviews: &[MoveReview]) -> BTreeMap<usize, Annotation> {
+    reviews
+        .iter()
+        .enumerate()
+        .filter_map(|(index, review)| {
+            let nag = review.class.nag()?;
+            Some((
+                index,
+                Annotation {
+                    nag: Some(nag),
+                    comment: Some(review.comment(game, index)),
+                },
+            ))
+        })
+        .collect()
+}
+
+/// The results of a side over the game.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SideSummary {
+    pub average_loss: f64,
+    pub accuracy: f64,
+    pub inaccuracies: usize,
+    pub mistakes: usize,
+    pub blunders: usize,
+}
+
+impl SideSummary {
+    pub fn new(reviews: &[MoveReview], side: Color) -> Self {
+        let moves = reviews
+            .iter()
+            .filter(|review| review.side == side)
+            .collect::<Vec<_>>();
+        if moves.is_empty() {
+            return Self::default();
+        }
+        let count = moves.len() as f64;
+        let class_count =
+            |class: MoveClass| moves.iter().filter(|review| review.class == class).count();
+        Self {
+            average_loss: moves
+                .iter()
+                .map(|review| f64::from(review.loss))
+                .sum::<f64>()
+                / count,
+            accuracy: moves.iter().map(|review| review.accuracy).sum::<f64>() / count,
+            inaccuracies: class_count(MoveClass::Inaccuracy),
+            mistakes: class_count(MoveClass::Mistake),
+            blunders: class_count(MoveClass::Blunder),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use owlchess::Board;
+
+    use super::*;
+
+    #[test]
+    fn classifies_losses_at_the_thresholds() {
+        let cases = [
+            (-20, MoveClass::Best),
+            (0, MoveClass::Best),
+            (10, MoveClass::Best),
+            (11, MoveClass::Good),
+            (49, MoveClass::Good),
+            (50, MoveClass::Inaccuracy),
+            (99, MoveClass::Inaccuracy),
+            (100, MoveClass::Mistake),
+            (299, MoveClass::Mistake),
+            (300, MoveClass::Blunder),
+            (2000, MoveClass::Blunder),
+        ];
+        for (loss, class) in cases {
+            assert_eq!(MoveClass::from_loss(loss), class, "loss {loss}");
+        }
+    }
+
+    #[test]
+    fn bounds_evaluations_from_white_point_of_view() {
+        let cases = [
+            (Score::Centipawns(250), Color::White, 250),
+            (Score::Centipawns(250), Color::Black, -250),
+            (Score::Centipawns(5000), Color::White, MAX_CENTIPAWNS),
+            (Score::Centipawns(-5000), Color::Black, MAX_CENTIPAWNS),
+            (Score::Mate(3), Color::White, MAX_CENTIPAWNS),
+            (Score::Mate(3), Color::Black, -MAX_CENTIPAWNS),
+            (Score::Mate(-2), Color::White, -MAX_CENTIPAWNS),
+            // Black is mated.
+            (Score::Mate(0), Color::Black, MAX_CENTIPAWNS),
+        ];
+        for (score, side, centipawns) in cases {
+            let eval = PositionEval {
+                score,
+                best_move: None,
+            };
+            assert_eq!(
+                eval.white_centipawns(side),
+                centipawns,
+                "{score:?} with {side:?} to move"
+            );
+        }
+    }
+
+    fn eval(score: Score, best_move: &str) -> PositionEval {
+        PositionEval {
+            score,
+            best_move: Some(best_move.to_string()),
+        }
+    }
+
+    /// Reviews the last of the moves, with the given evaluations before and after it.
+    fn review_last(moves: &[&str], before: PositionEval, after: Score) -> MoveReview {
+        let mut game = Game::new(Board::initial());
+        let moves: Vec<String> = moves.iter().map(|uci_move| uci_move.to_string()).collect();
+        assert!(game.play_line(0, &moves));
+        let mut evals: Vec<PositionEval> = moves[..moves.len() - 1]
+            .iter()
+            .map(|uci_move| eval(Score::Centipawns(0), uci_move))
+            .collect();
+        evals.push(before);
+        evals.push(PositionEval {
+            score: after,
+            best_move: None,
+        });
+        review_moves(&game, &evals)
+            .pop()
+            .expect("the move is reviewed")
+    }
+
+    #[test]
+    fn reviews_moves_of_both_sides() {
+        // (moves, evaluation before the last move, best move, evaluation after it,
+        // loss, class), the evaluations being from the point of view of the side to move.
+        let cases = [
+            (
+                &["e2e4"][..],
+                Score::Centipawns(30),
+                "e2e4",
+                Score::Centipawns(-25),
+                0,
+                MoveClass::Best,
+            ),
+            (
+                &["e2e4"][..],
+                Score::Centipawns(30),
+                "d2d4",
+                Score::Centipawns(-5),
+                25,
+                MoveClass::Good,
+            ),
+            (
+                &["e2e4"][..],
+                Score::Centipawns(30),
+                "d2d4",
+                Score::Centipawns(100),
+                130,
+                MoveClass::Mistake,
+            ),
+            (
+                &["e2e4", "e7e5"][..],
+                Score::Centipawns(-30),
+                "c7c5",
+                Score::Centipawns(100),
+                70,
+                MoveClass::Inaccuracy,
+            ),
+            (
+                &["e2e4", "e7e5"][..],
+                Score::Centipawns(-30),
+                "c7c5",
+                Score::Mate(2),
+                MAX_CENTIPAWNS - 30,
+                MoveClass::Blunder,
+            ),
+            (
+                &["e2e4", "e7e5"][..],
+                Score::Mate(-4),
+                "c7c5",
+                Score::Mate(3),
+                0,
+                MoveClass::Best,
+            ),
+        ];
+        for (moves, before, best_move, after, loss, class) in cases {
+            let review = review_last(moves, eval(before, best_move), after);
+            assert_eq!(review.loss, loss, "{moves:?} instead of {best_move}");
+            assert_eq!(review.class, class, "{moves:?} instead of {best_move}");
+            assert_eq!(
+                review.side,
+                if moves.len() % 2 == 1 {
+                    Color::White
+                } else {
+                    Color::Black
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn accuracy_follows_the_lost_winning_chances() {
+        let best = review_last(
+            &["e2e4"],
+            eval(Score::Centipawns(30), "e2e4"),
+            Score::Centipawns(-10),
+        );
+        assert_eq!(best.accuracy, 100.0);
+        assert_eq!(best.best_move, None);
+
+        let accuracies: Vec<f64> = [
+            Score::Centipawns(-20),
+            Score::Centipawns(100),
+            Score::Mate(1),
+        ]
+        .into_iter()
+        .map(|after| review_last(&["e2e4"], eval(Score::Centipawns(30), "d2d4"), after).accuracy)
+        .collect();
+        assert!(accuracies[0] > 90.0 && accuracies[0] < 100.0);
+        assert!(accuracies[1] < accuracies[0]);
+        assert!(accuracies[2] < accuracies[1] && accuracies[2] < 10.0);
+
+        // The search of the position after the move may find it a little better than the
+        // search before it did.
+        let lucky = review_last(
+            &["e2e4"],
+            eval(Score::Centipawns(30), "d2d4"),
+            Score::Centipawns(-60),
+        );
+        assert_eq!(lucky.loss, 0);
+        assert!(lucky.accuracy > 99.99);
+    }
+}
@@ -0,0 +1,227 @@
+//! The panel showing the engine analysis of the position on the board.
+use chessboard::Arrow;
+use iced::{
+    Color, Element, Length,
+    widget::{button, column, pick_list, row, text},
+};
+use owlchess::movegen::legal;
+
+use crate::{
+    engine::{
+        analysis::{AnalysisInfo, Score, san_line},
+        book::BookMove,
+        syzygy::{PositionProbe, Wdl},
+    },
+    game::Game,
+};
+
+/// The color of the arrow showing the best move. The arrows of the next lines fade out.
+const ARROW_COLOR: Color = Color::from_rgba(0.0, 0.5, 0.25, 0.8);
+
+/// Shortens large counts, as in `1.2M`.
+fn short_count(count: u64) -> String {
+    match count {
+        0..1_000 => count.to_string(),
+        1_000..1_000_000 => format!("{}k", count / 1_000),
+        _ => format!("{:.1}M", count as f64 / 1_000_000.0),
+    }
+}
+
+/// A tablebase result, as in `Win, DTZ 13`.
+fn tablebase_result(wdl: Wdl, dtz: i32) -> String {
+    if wdl == Wdl::Draw {
+        String::from(wdl.label())
+    } else {
+        format!("{}, DTZ {}", wdl.label(), dtz.abs())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum AnalysisMessage {
+    SetLineCount(usize),
+    /// Plays the moves of the line, given by its index.
+    PlayLine(usize),
+}
+
+pub struct AnalysisPanel {
+    /// The analysed position, as the start of a game in which the lines are played.
+    position: Game,
+    line_count: usize,
+    /// The lines found by the engine, the best one first.
+    lines: Vec<AnalysisInfo>,
+    /// The moves of the opening book in the position.
+    book_moves: Vec<BookMove>,
+    /// The tablebase results of the position and of its moves, or why they are not known.
+    tablebase: Option<Result<PositionProbe, String>>,
+}
+
+impl AnalysisPanel {
+    pub fn new(position: Game, line_count: usize) -> Self {
+        Self {
+            position,
+            line_count,
+            lines: Vec::new(),
+            book_moves: Vec::new(),
+            tablebase: None,
+        }
+    }
+
+    /// Forgets the analysis, for another position.
+    pub fn reset(&mut self, position: Game) {
+        self.position = position;
+        self.lines.clear();
+        self.book_moves.clear();
+        self.tablebase = None;
+    }
+
+    pub fn set_book_moves(&mut self, book_moves: Vec<BookMove>) {
+        self.book_moves = book_moves;
+    }
+
+    pub fn set_tablebase(&mut self, tablebase: Result<PositionProbe, String>) {
+        self.tablebase = Some(tablebase);
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_count
+    }
+
+    pub fn set_line_count(&mut self, line_count: usize) {
+        self.line_count = line_count;
+        self.lines.truncate(line_count);
+    }
+
+    /// The evaluation of the best line, from the point of view of White.
+    pub fn best_score(&self) -> Option<Score> {
+        let score = self.lines.first()?.score?;
+        Some(score.for_white(self.position.position().side()))
+    }
+
+    /// The moves of the line, in UCI notation.
+    pub fn line_moves(&self, index: usize) -> Option<&[String]> {
+        self.lines.get(index).map(|line| line.pv.as_slice())
+    }
+
+    /// Whether the engine has something to analyse in the position.
+    pub fn has_moves(&self) -> bool {
+        !legal::gen_all(self.position.position()).is_empty()
+            || !self.position.castlings(0).is_empty()
+    }
+
+    pub fn update(&mut self, info: AnalysisInfo) {
+        let index = info.multipv.unwrap_or(1).saturating_sub(1);
+        if index >= self.line_count {
+            return;
+        }
+        if self.lines.len() <= index {
+            self.lines.resize_with(index + 1, AnalysisInfo::default);
+        }
+        self.lines[index].merge(info);
+    }
+
+    /// The first move of each line, as arrows fading out from the best line.
+    pub fn arrows(&self) -> Vec<Arrow> {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let first_move = self.position.legal_move(0, line.pv.first()?)?;
+                Some(Arrow {
+                    from: first_move.src(),
+                    to: first_move.dst(),
+                    color: ARROW_COLOR.scale_alpha(0.7_f32.powi(index as i32)),
+                })
+            })
+            .collect()
+    }
+
+    fn line_view(&self, index: usize, line: &AnalysisInfo) -> Element<'_, AnalysisMessage> {
+        let score = line
+            .score
+            .map(|score| score.for_white(self.position.position().side()).to_string())
+            .unwrap_or_default();
+        button(
+            row![
+                text(score).width(60),
+                text(san_line(&self.position, &line.pv)).width(Length::Fill),
+            ]
+            .spacing(5),
+        )
+        .style(button::text)
+        .width(Length::Fill)
+        .on_press_maybe((!line.pv.is_empty()).then_some(AnalysisMessage::PlayLine(index)))
+        .into()
+    }
+
+    /// The engine can analyse up to `max_lines` lines at once.
+    pub fn view(&self, engine_name: &str, max_lines: usize) -> Element<'_, AnalysisMessage> {
+        let mut panel = column![text(format!("Analysis by {engine_name}")).size(20)].spacing(8);
+        if max_lines > 1 {
+            panel = panel.push(
+                row![
+                    text("Lines"),
+                    pick_list(
+                        (1..=max_lines).collect::<Vec<_>>(),
+                        Some(self.line_count.min(max_lines)),
+                        AnalysisMessage::SetLineCount
+                    ),
+                ]
+                .spacing(5),
+            );
+        }
+        if !self.book_moves.is_empty() {
+            let book_moves = self
+                .book_moves
+                .iter()
+                .filter_map(|book_move| {
+                    let san = book_move.book_move.san(self.position.position()).ok()?;
+                    Some(format!("{san} {:.0}%", book_move.percent))
+                })
+                .collect::<Vec<_>>();
+            panel = panel.push(text(format!("Book: {}", book_moves.join(" · "))));
+        }
+        match &self.tablebase {
+            Some(Ok(probe)) => {
+                panel = panel.push(text(format!(
+                    "Tablebase: {}",
+                    tablebase_result(probe.wdl, probe.dtz)
+                )));
+                for move_probe in &probe.moves {
+                    if let Ok(san) = move_probe.chess_move.san(self.position.position()) {
+                        panel = panel.push(text(format!(
+                            "  {san} : {}",
+                            tablebase_result(move_probe.wdl, move_probe.dtz)
+                        )));
+                    }
+                }
+            }
+            Some(Err(err)) => panel = panel.push(text(format!("Tablebase: {err}"))),
+            None => {}
+        }
+        if !self.has_moves() {
+            return panel.push(text("No move to analyse.")).into();
+        }
+        let Some(best_line) = self.lines.first().filter(|line| !line.is_empty()) else {
+            return panel.push(text("The engine is thinking...")).into();
+        };
+
+        let mut statistics = Vec::new();
+        if let Some(depth) = best_line.depth {
+            statistics.push(format!("Depth {depth}"));
+        }
+        if let Some(nodes) = best_line.nodes {
+            statistics.push(format!("{} nodes", short_count(nodes)));
+        }
+        if let Some(nps) = best_line.nps {
+            statistics.push(format!("{} nodes/s", short_count(nps)));
+        }
+        self.lines
+            .iter()
+            .enumerate()
+            .fold(
+                panel.push(text(statistics.join(" · "))),
+                |panel, (index, line)| panel.push(self.line_view(index, line)),
+            )
+            .into()
+    }
+}
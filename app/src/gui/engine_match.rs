@@ -0,0 +1,306 @@
+//! The dialog for setting up a match between two engines, and the results of the match.
+use std::{fs, path::PathBuf, time::Duration};
+
+use iced::{
+    Element, Length,
+    widget::{button, checkbox, column, pick_list, row, scrollable, text, text_input},
+};
+use owlchess::Color;
+
+use crate::{
+    engine::tournament::{Adjudication, MatchSettings, TimeControl, Tournament},
+    game::openings,
+    gui::new_game::EngineChoice,
+    settings::{EngineSettings, Settings},
+};
+
+#[derive(Debug, Clone)]
+pub enum MatchDialogMessage {
+    /// Chooses the first or the second engine.
+    SelectEngine(usize, EngineChoice),
+    SetGames(String),
+    SetBaseMinutes(String),
+    SetIncrementSeconds(String),
+    SetOpeningsPath(String),
+    SetResign(bool),
+    SetResignScore(String),
+    SetDraw(bool),
+    SetDrawScore(String),
+}
+
+pub struct MatchDialog {
+    engine_paths: [Option<PathBuf>; 2],
+    games: String,
+    base_minutes: String,
+    increment_seconds: String,
+    /// An EPD or PGN file. Empty to start every game from the initial position.
+    openings_path: String,
+    resign: bool,
+    resign_score: String,
+    draw: bool,
+    draw_score: String,
+}
+
+impl MatchDialog {
+    /// Proposes the first two registered engines.
+    pub fn new(engines: &[EngineSettings]) -> Self {
+        let path = |index: usize| engines.get(index).map(|engine| engine.path.clone());
+        Self {
+            engine_paths: [path(0), path(1).or_else(|| path(0))],
+            games: String::from("10"),
+            base_minutes: String::from("1"),
+            increment_seconds: String::from("1"),
+            openings_path: String::new(),
+            resign: true,
+            resign_score: String::from("600"),
+            draw: true,
+            draw_score: String::from("10"),
+        }
+    }
+
+    pub fn update(&mut self, message: MatchDialogMessage) {
+        match message {
+            MatchDialogMessage::SelectEngine(index, choice) => {
+                self.engine_paths[index] = choice.path
+            }
+            MatchDialogMessage::SetGames(games) => self.games = games,
+            MatchDialogMessage::SetBaseMinutes(minutes) => self.base_minutes = minutes,
+            MatchDialogMessage::SetIncrementSeconds(seconds) => self.increment_seconds = seconds,
+            MatchDialogMessage::SetOpeningsPath(path) => self.openings_path = path,
+            MatchDialogMessage::SetResign(resign) => self.resign = resign,
+            MatchDialogMessage::SetResignScore(score) => self.resign_score = score,
+            MatchDialogMessage::SetDraw(draw) => self.draw = draw,
+            MatchDialogMessage::SetDrawScore(score) => self.draw_score = score,
+        }
+    }
+
+    /// The settings of the match, or why they are invalid.
+    pub fn settings(&self, settings: &Settings) -> Result<MatchSettings, String> {
+        let engine = |index: usize| {
+            self.engine_paths[index]
+                .as_ref()
+                .and_then(|path| settings.engine(path))
+                .cloned()
+                .ok_or_else(|| String::from("Choose both engines."))
+        };
+        let engines = [engine(0)?, engine(1)?];
+        let games = match self.games.trim().parse::<usize>() {
+            Ok(games) if games > 0 => games,
+            _ => {
+                return Err(String::from(
+                    "The number of games must be a positive number.",
+                ));
+            }
+        };
+        let base = match self.base_minutes.trim().parse::<f64>() {
+            Ok(minutes) if minutes > 0.0 => Duration::from_secs_f64(minutes * 60.0),
+            _ => return Err(String::from("The time per game must be a positive number.")),
+        };
+        let increment = match self.increment_seconds.trim().parse::<f64>() {
+            Ok(seconds) if seconds >= 0.0 => Duration::from_secs_f64(seconds),
+            _ => return Err(String::from("The increment must be a number of seconds.")),
+        };
+        let score = |enabled: bool, score: &str, name: &str| {
+            if !enabled {
+                return Ok(None);
+            }
+            match score.trim().parse::<i32>() {
+                Ok(score) if score >= 0 => Ok(Some(score)),
+                _ => Err(format!("The {name} score must be a number of centipawns.")),
+            }
+        };
+        let adjudication = Adjudication {
+            resign_score: score(self.resign, &self.resign_score, "resign")?,
+            draw_score: score(self.draw, &self.draw_score, "draw")?,
+        };
+        let openings_path = self.openings_path.trim();
+        let openings = if openings_path.is_empty() {
+            Vec::new()
+        } else {
+            let text = fs::read_to_string(openings_path)
+                .map_err(|err| format!("Cannot read the openings: {err}."))?;
+            openings::read(&text).map_err(|err| format!("Cannot read the openings: {err}."))?
+        };
+        Ok(MatchSettings {
+            engines,
+            games,
+            openings,
+            time_control: TimeControl { base, increment },
+            adjudication,
+            syzygy_path: settings.syzygy_path.clone(),
+        })
+    }
+
+    pub fn view(&self, engines: &[EngineSettings]) -> Element<'_, MatchDialogMessage> {
+        if engines.is_empty() {
+            return text("Register engines from the engines manager to let them play a match.")
+                .into();
+        }
+        let choices: Vec<EngineChoice> = engines
+            .iter()
+            .map(|engine| EngineChoice {
+                name: engine.name.clone(),
+                path: Some(engine.path.clone()),
+            })
+            .collect();
+        let engine_picker = |index: usize| {
+            let selected = choices
+                .iter()
+                .find(|choice| choice.path == self.engine_paths[index])
+                .cloned();
+            pick_list(choices.clone(), selected, move |choice| {
+                MatchDialogMessage::SelectEngine(index, choice)
+            })
+        };
+
+        let mut content = column![
+            text("First engine"),
+            engine_picker(0),
+            text("Second engine"),
+            engine_picker(1),
+            text("Number of games (the engines swap sides after each game)"),
+            text_input("Games", &self.games).on_input(MatchDialogMessage::SetGames),
+            text("Time control"),
+            row![
+                text_input("Minutes per game", &self.base_minutes)
+                    .on_input(MatchDialogMessage::SetBaseMinutes),
+                text("minutes +"),
+                text_input("Increment", &self.increment_seconds)
+                    .on_input(MatchDialogMessage::SetIncrementSeconds),
+                text("seconds per move"),
+            ]
+            .spacing(5),
+            text("Openings (EPD or PGN file, each opening is played with both sides)"),
+            text_input("Initial position", &self.openings_path)
+                .on_input(MatchDialogMessage::SetOpeningsPath),
+            text("Adjudication"),
+            checkbox(
+                "Resign when both engines see the game decided beyond the score",
+                self.resign
+            )
+            .on_toggle(MatchDialogMessage::SetResign),
+        ]
+        .spacing(8)
+        .max_width(500);
+        if self.resign {
+            content = content.push(
+                text_input("Centipawns", &self.resign_score)
+                    .on_input(MatchDialogMessage::SetResignScore),
+            );
+        }
+        content = content.push(
+            checkbox("Draw within the score, from the move 30", self.draw)
+                .on_toggle(MatchDialogMessage::SetDraw),
+        );
+        if self.draw {
+            content = content.push(
+                text_input("Centipawns", &self.draw_score)
+                    .on_input(MatchDialogMessage::SetDrawScore),
+            );
+        }
+        content.into()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum MatchMessage {
+    CopyPgn,
+    Close,
+}
+
+/// The time left on a clock, as minutes and seconds.
+pub fn clock_text(time: Duration) -> String {
+    let tenths = time.as_millis() / 100;
+    let seconds = tenths / 10;
+    if seconds < 10 {
+        format!("0:0{}.{}", seconds, tenths % 10)
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// The progress of the match, and its results so far.
+pub fn match_view(tournament: &Tournament) -> Element<'_, MatchMessage> {
+    let score = tournament.score();
+    let title = if tournament.is_over() {
+        String::from("Match over")
+    } else {
+        format!("Game {} of {}", tournament.round(), tournament.games())
+    };
+    let results_row = |cells: [String; 5]| {
+        let [name, wins, draws, losses, points] = cells;
+        row![
+            text(name).width(Length::Fill),
+            text(wins).width(40),
+            text(draws).width(40),
+            text(losses).width(40),
+            text(points).width(50),
+        ]
+    };
+    let games = score.games() as f64;
+    let elo = match (score.elo_difference(), score.error_margin()) {
+        (Some(difference), Some(margin)) => {
+            format!("Elo difference: {difference:+.0} ± {margin:.0}")
+        }
+        (Some(difference), None) => format!("Elo difference: {difference:+.0}"),
+        (None, _) => String::from("Elo difference: not available"),
+    };
+
+    let finished_games = tournament.finished_games().iter().enumerate().fold(
+        column![],
+        |finished_games, (index, game)| {
+            let result = owlchess::GameStatus::from(game.outcome);
+            finished_games.push(text(format!(
+                "{}. {} - {} : {}",
+                index + 1,
+                game.white,
+                game.black,
+                match game.outcome {
+                    Some(outcome) => format!("{result} ({outcome})"),
+                    None => String::from("*"),
+                }
+            )))
+        },
+    );
+
+    column![
+        row![
+            text(title).size(20).width(Length::Fill),
+            button(text("Copy PGN")).on_press_maybe(
+                (!tournament.finished_games().is_empty()).then_some(MatchMessage::CopyPgn)
+            ),
+            button(text("Close")).on_press(MatchMessage::Close),
+        ]
+        .spacing(5),
+        results_row(["Engine", "W", "D", "L", "Score"].map(String::from)),
+        results_row([
+            tournament.engine_name(0).to_string(),
+            score.wins.to_string(),
+            score.draws.to_string(),
+            score.losses.to_string(),
+            score.points().to_string(),
+        ]),
+        results_row([
+            tournament.engine_name(1).to_string(),
+            score.losses.to_string(),
+            score.draws.to_string(),
+            score.wins.to_string(),
+            (games - score.points()).to_string(),
+        ]),
+        text(elo),
+    ]
+    .push_maybe(tournament.error().map(text))
+    .push(text("Games").size(16))
+    .push(scrollable(finished_games))
+    .spacing(8)
+    .into()
+}
+
+/// The engine playing the side, and its clock.
+pub fn player_text(tournament: &Tournament, side: Color) -> String {
+    format!(
+        "{} {}",
+        tournament.engine_name(tournament.engine_of(side)),
+        clock_text(tournament.time_left(side))
+    )
+}
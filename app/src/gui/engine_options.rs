@@ -0,0 +1,288 @@
+//! A form for the options advertised by an engine.
+use std::collections::BTreeMap;
+
+use iced::{
+    Element, Length,
+    widget::{button, checkbox, column, pick_list, row, scrollable, slider, text, text_input},
+};
+
+use crate::engine::{
+    EngineIdentity, EngineOption, OptionKind, analysis::MULTI_PV_OPTION, strength::STRENGTH_OPTIONS,
+};
+
+/// Spin options with wider ranges (such as hash sizes) are edited as text.
+const MAX_SLIDER_RANGE: i64 = 1000;
+
+#[derive(Debug, Clone)]
+pub enum EngineOptionsMessage {
+    SetValue(String, String),
+    /// Buttons trigger an action of the engine, rather than changing a value.
+    PressButton(String),
+    ResetToDefaults,
+}
+
+pub struct EngineOptionsEditor {
+    engine_name: String,
+    /// The options shown in the form : the options depending on the strength chosen for each
+    /// game, and the number of lines chosen in the analysis, are left out.
+    options: Vec<EngineOption>,
+    values: BTreeMap<String, String>,
+}
+
+impl EngineOptionsEditor {
+    /// Fills the form with the saved values, or with the engine defaults.
+    pub fn new(identity: &EngineIdentity, saved_values: &BTreeMap<String, String>) -> Self {
+        let options: Vec<EngineOption> = identity
+            .options
+            .iter()
+            .filter(|option| {
+                !STRENGTH_OPTIONS
+                    .iter()
+                    .chain([&MULTI_PV_OPTION])
+                    .any(|name| option.name.eq_ignore_ascii_case(name))
+            })
+            .cloned()
+            .collect();
+        let values = options
+            .iter()
+            .filter_map(|option| {
+                let value = saved_values
+                    .get(&option.name)
+                    .filter(|value| option.kind.accepts(value))
+                    .cloned()
+                    .or_else(|| option.kind.default_value())?;
+                Some((option.name.clone(), value))
+            })
+            .collect();
+        Self {
+            engine_name: identity.name.clone(),
+            options,
+            values,
+        }
+    }
+
+    /// The valid values which differ from the engine defaults, to be saved.
+    pub fn changed_values(&self) -> BTreeMap<String, String> {
+        self.options
+            .iter()
+            .filter_map(|option| {
+                let value = self.values.get(&option.name)?;
+                let changed = option.kind.accepts(value)
+                    && option.kind.default_value().as_ref() != Some(value);
+                changed.then(|| (option.name.clone(), value.clone()))
+            })
+            .collect()
+    }
+
+    pub fn update(&mut self, message: EngineOptionsMessage) {
+        match message {
+            EngineOptionsMessage::SetValue(name, value) => {
+                self.values.insert(name, value);
+            }
+            EngineOptionsMessage::PressButton(_) => {}
+            EngineOptionsMessage::ResetToDefaults => {
+                for option in &self.options {
+                    if let Some(default) = option.kind.default_value() {
+                        self.values.insert(option.name.clone(), default);
+                    }
+                }
+            }
+        }
+    }
+
+    fn option_view<'a>(&'a self, option: &'a EngineOption) -> Element<'a, EngineOptionsMessage> {
+        let name = option.name.clone();
+        let value = self
+            .values
+            .get(&option.name)
+            .map(String::as_str)
+            .unwrap_or_default();
+        let set_value = move |value: String| EngineOptionsMessage::SetValue(name.clone(), value);
+
+        match &option.kind {
+            OptionKind::Spin { min, max, .. } if max - min <= MAX_SLIDER_RANGE => {
+                let current = value.parse::<i64>().unwrap_or(*min).clamp(*min, *max);
+                row![
+                    text(&option.name).width(200),
+                    slider(*min as i32..=*max as i32, current as i32, move |value| {
+                        set_value(value.to_string())
+                    }),
+                    text(current).width(60),
+                ]
+                .spacing(10)
+                .into()
+            }
+            OptionKind::Spin { min, max, .. } => {
+                let hint = if option.kind.accepts(value) {
+                    String::new()
+                } else {
+                    format!("Between {min} and {max}")
+                };
+                row![
+                    text(&option.name).width(200),
+                    text_input(&option.name, value).on_input(set_value),
+                    text(hint).width(160),
+                ]
+                .spacing(10)
+                .into()
+            }
+            OptionKind::Check { .. } => checkbox(&option.name, value == "true")
+                .on_toggle(move |checked| set_value(checked.to_string()))
+                .into(),
+            OptionKind::Combo { values, .. } => row![
+                text(&option.name).width(200),
+                pick_list(
+                    values.as_slice(),
+                    values.iter().find(|allowed| *allowed == value),
+                    move |choice: String| set_value(choice)
+                ),
+            ]
+            .spacing(10)
+            .into(),
+            OptionKind::String { .. } => row![
+                text(&option.name).width(200),
+                text_input(&option.name, value).on_input(set_value),
+            ]
+            .spacing(10)
+            .into(),
+            OptionKind::Button => button(text(&option.name))
+                .on_press(EngineOptionsMessage::PressButton(option.name.clone()))
+                .into(),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, EngineOptionsMessage> {
+        let options = self
+            .options
+            .iter()
+            .fold(column![].spacing(8), |options, option| {
+                options.push(self.option_view(option))
+            });
+
+        column![
+            text(format!("Options of {}", self.engine_name)).size(20),
+            text("The values are sent to the engine before each game."),
+            scrollable(options.padding(5)).height(Length::Fill),
+            button(text("Reset to defaults")).on_press(EngineOptionsMessage::ResetToDefaults),
+        ]
+        .spacing(10)
+        .max_width(700)
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity() -> EngineIdentity {
+        EngineIdentity {
+            name: String::from("Test engine"),
+            author: None,
+            options: vec![
+                EngineOption {
+                    name: String::from("Hash"),
+                    kind: OptionKind::Spin {
+                        default: 16,
+                        min: 1,
+                        max: 1024,
+                    },
+                },
+                EngineOption {
+                    name: String::from("Ponder"),
+                    kind: OptionKind::Check { default: false },
+                },
+                EngineOption {
+                    name: String::from("Style"),
+                    kind: OptionKind::Combo {
+                        default: String::from("Normal"),
+                        values: vec![String::from("Normal"), String::from("Risky")],
+                    },
+                },
+                EngineOption {
+                    name: String::from("Clear Hash"),
+                    kind: OptionKind::Button,
+                },
+                EngineOption {
+                    name: String::from("Skill Level"),
+                    kind: OptionKind::Spin {
+                        default: 20,
+                        min: 0,
+                        max: 20,
+                    },
+                },
+                EngineOption {
+                    name: String::from("multipv"),
+                    kind: OptionKind::Spin {
+                        default: 1,
+                        min: 1,
+                        max: 500,
+                    },
+                },
+            ],
+        }
+    }
+
+    fn values(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn leaves_out_strength_and_lines_options() {
+        let editor = EngineOptionsEditor::new(&identity(), &BTreeMap::new());
+        let names: Vec<&str> = editor
+            .options
+            .iter()
+            .map(|option| option.name.as_str())
+            .collect();
+        assert_eq!(names, ["Hash", "Ponder", "Style", "Clear Hash"]);
+    }
+
+    #[test]
+    fn keeps_only_valid_saved_values() {
+        let saved = values(&[("Hash", "4096"), ("Ponder", "true"), ("Style", "Risky")]);
+        let editor = EngineOptionsEditor::new(&identity(), &saved);
+        assert_eq!(editor.values["Hash"], "16");
+        assert_eq!(
+            editor.changed_values(),
+            values(&[("Ponder", "true"), ("Style", "Risky")])
+        );
+    }
+
+    #[test]
+    fn saves_only_valid_changes() {
+        let mut editor = EngineOptionsEditor::new(&identity(), &BTreeMap::new());
+        assert!(editor.changed_values().is_empty());
+
+        editor.update(EngineOptionsMessage::SetValue(
+            String::from("Hash"),
+            String::from("256"),
+        ));
+        editor.update(EngineOptionsMessage::SetValue(
+            String::from("Style"),
+            String::from("Unknown"),
+        ));
+        editor.update(EngineOptionsMessage::PressButton(String::from(
+            "Clear Hash",
+        )));
+        assert_eq!(editor.changed_values(), values(&[("Hash", "256")]));
+
+        editor.update(EngineOptionsMessage::ResetToDefaults);
+        assert!(editor.changed_values().is_empty());
+    }
+
+    #[test]
+    fn sends_only_known_and_accepted_values() {
+        let saved = values(&[("Hash", "0"), ("Ponder", "true"), ("Threads", "4")]);
+        assert_eq!(
+            identity().option_commands(&saved),
+            vec![crate::engine::EngineCommand::SetOption {
+                name: String::from("Ponder"),
+                value: Some(String::from("true")),
+            }]
+        );
+    }
+}
@@ -0,0 +1,297 @@
+//! The registry of the installed engines.
+use std::path::{Path, PathBuf};
+
+use iced::{
+    Element, Length,
+    widget::{button, column, pick_list, row, scrollable, text, text_input},
+};
+
+use crate::{
+    engine::{EngineError, EngineIdentity, Protocol, default_name},
+    settings::EngineSettings,
+};
+
+#[derive(Debug, Clone)]
+pub enum EnginesMessage {
+    NewPathChanged(String),
+    SetNewProtocol(Protocol),
+    /// Probes the engine at the new path, then registers it.
+    AddEngine,
+    Rename(usize, String),
+    Remove(usize),
+    SyzygyPathChanged(String),
+}
+
+pub struct EnginesManager {
+    engines: Vec<EngineSettings>,
+    new_path: String,
+    new_protocol: Protocol,
+    /// The engine being probed, before it can be added.
+    probing: Option<PathBuf>,
+    error: Option<String>,
+    /// The directory of the Syzygy tablebases, given to the engines which can use them.
+    syzygy_path: String,
+}
+
+impl EnginesManager {
+    pub fn new(engines: Vec<EngineSettings>, syzygy_path: Option<&Path>) -> Self {
+        Self {
+            engines,
+            syzygy_path: syzygy_path
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+            new_path: String::new(),
+            new_protocol: Protocol::default(),
+            probing: None,
+            error: None,
+        }
+    }
+
+    /// The directory of the tablebases to save, None when left empty.
+    pub fn syzygy_path(&self) -> Option<PathBuf> {
+        let path = self.syzygy_path.trim();
+        (!path.is_empty()).then(|| PathBuf::from(path))
+    }
+
+    /// The engines to save, named after their executable when the user left the name empty.
+    pub fn into_engines(self) -> Vec<EngineSettings> {
+        self.engines
+            .into_iter()
+            .map(|mut engine| {
+                if engine.name.trim().is_empty() {
+                    engine.name = default_name(&engine.path);
+                }
+                engine
+            })
+            .collect()
+    }
+
+    /// The path and the protocol of the engine to add, if it can be probed.
+    pub fn engine_to_probe(&mut self) -> Option<(PathBuf, Protocol)> {
+        let new_path = self.new_path.trim();
+        if new_path.is_empty() || self.probing.is_some() {
+            return None;
+        }
+        let path = PathBuf::from(new_path);
+        if self.engines.iter().any(|engine| engine.path == path) {
+            self.error = Some(String::from("This engine is already registered."));
+            return None;
+        }
+        self.probing = Some(path.clone());
+        self.error = None;
+        Some((path, self.new_protocol))
+    }
+
+    /// Registers the probed engine, or shows why it cannot be used.
+    pub fn probed(
+        &mut self,
+        path: PathBuf,
+        protocol: Protocol,
+        result: Result<EngineIdentity, EngineError>,
+    ) {
+        if self.probing.as_ref() != Some(&path) {
+            return;
+        }
+        self.probing = None;
+        match result {
+            Ok(identity) => {
+                self.engines.push(EngineSettings {
+                    name: identity.name,
+                    path,
+                    protocol,
+                    author: identity.author,
+                    ..Default::default()
+                });
+                self.new_path.clear();
+            }
+            Err(err) => self.error = Some(err.to_string()),
+        }
+    }
+
+    pub fn update(&mut self, message: EnginesMessage) {
+        match message {
+            EnginesMessage::NewPathChanged(new_path) => {
+                self.new_path = new_path;
+                self.error = None;
+            }
+            EnginesMessage::SetNewProtocol(protocol) => self.new_protocol = protocol,
+            EnginesMessage::AddEngine => {}
+            EnginesMessage::Rename(index, name) => {
+                if let Some(engine) = self.engines.get_mut(index) {
+                    engine.name = name;
+                }
+            }
+            EnginesMessage::Remove(index) => {
+                if index < self.engines.len() {
+                    self.engines.remove(index);
+                }
+            }
+            EnginesMessage::SyzygyPathChanged(path) => self.syzygy_path = path,
+        }
+    }
+
+    fn engine_view(&self, index: usize, engine: &EngineSettings) -> Element<'_, EnginesMessage> {
+        let mut details = column![text(format!(
+            "{} ({})",
+            engine.path.display(),
+            engine.protocol
+        ))]
+        .spacing(2);
+        if let Some(author) = &engine.author {
+            details = details.push(text(format!("By {author}")));
+        }
+        if !engine.path.is_file() {
+            details = details.push(text("The executable cannot be found."));
+        }
+
+        row![
+            text_input("Name", &engine.name)
+                .on_input(move |name| EnginesMessage::Rename(index, name))
+                .width(200),
+            details.width(Length::Fill),
+            button(text("Remove")).on_press(EnginesMessage::Remove(index)),
+        ]
+        .spacing(10)
+        .into()
+    }
+
+    pub fn view(&self) -> Element<'_, EnginesMessage> {
+        let engines = self
+            .engines
+            .iter()
+            .enumerate()
+            .fold(column![].spacing(10), |engines, (index, engine)| {
+                engines.push(self.engine_view(index, engine))
+            });
+        let status = match (&self.probing, &self.error) {
+            (Some(_), _) => Some(String::from("Checking the engine...")),
+            (None, Some(error)) => Some(error.clone()),
+            (None, None) => None,
+        };
+
+        column![
+            text("Engines").size(20),
+            if self.engines.is_empty() {
+                Element::from(text("No engine registered yet."))
+            } else {
+                scrollable(engines.padding(5)).height(Length::Fill).into()
+            },
+            row![
+                text_input("Path to an engine executable", &self.new_path)
+                    .on_input(EnginesMessage::NewPathChanged)
+                    .on_submit(EnginesMessage::AddEngine),
+                pick_list(
+                    Protocol::ALL,
+                    Some(self.new_protocol),
+                    EnginesMessage::SetNewProtocol
+                ),
+                button(text("Add engine")).on_press_maybe(
+                    (self.probing.is_none() && !self.new_path.trim().is_empty())
+                        .then_some(EnginesMessage::AddEngine)
+                ),
+            ]
+            .spacing(5),
+        ]
+        .push_maybe(status.map(text))
+        .push(text("Syzygy tablebases"))
+        .push(
+            text_input("Directory of the Syzygy files", &self.syzygy_path)
+                .on_input(EnginesMessage::SyzygyPathChanged),
+        )
+        .spacing(10)
+        .max_width(800)
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(name: &str) -> EngineIdentity {
+        EngineIdentity {
+            name: name.to_string(),
+            author: Some(String::from("Someone")),
+            options: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn registers_a_probed_engine() {
+        let mut manager = EnginesManager::new(Vec::new(), None);
+        manager.update(EnginesMessage::NewPathChanged(String::from(
+            " /usr/bin/stockfish ",
+        )));
+        let (path, protocol) = manager.engine_to_probe().expect("the path is not empty");
+        assert_eq!(path, PathBuf::from("/usr/bin/stockfish"));
+        // A single probe at a time.
+        assert!(manager.engine_to_probe().is_none());
+
+        manager.probed(path.clone(), protocol, Ok(identity("Stockfish 17")));
+        let engines = manager.into_engines();
+        assert_eq!(engines.len(), 1);
+        assert_eq!(engines[0].name, "Stockfish 17");
+        assert_eq!(engines[0].path, path);
+        assert_eq!(engines[0].author.as_deref(), Some("Someone"));
+    }
+
+    #[test]
+    fn refuses_an_engine_registered_twice() {
+        let engines = vec![EngineSettings {
+            path: PathBuf::from("/usr/bin/stockfish"),
+            ..Default::default()
+        }];
+        let mut manager = EnginesManager::new(engines, None);
+        manager.update(EnginesMessage::NewPathChanged(String::from(
+            "/usr/bin/stockfish",
+        )));
+        assert!(manager.engine_to_probe().is_none());
+        assert!(manager.error.is_some());
+    }
+
+    #[test]
+    fn shows_probe_errors_and_ignores_stale_results() {
+        let mut manager = EnginesManager::new(Vec::new(), None);
+        manager.update(EnginesMessage::NewPathChanged(String::from("/bin/engine")));
+        let (path, protocol) = manager.engine_to_probe().expect("the path is not empty");
+
+        manager.probed(PathBuf::from("/bin/other"), protocol, Ok(identity("Other")));
+        assert!(manager.probing.is_some());
+
+        manager.probed(path, protocol, Err(EngineError::NoAnswer(protocol)));
+        assert!(manager.probing.is_none());
+        assert_eq!(
+            manager.error,
+            Some(EngineError::NoAnswer(protocol).to_string())
+        );
+        assert!(manager.into_engines().is_empty());
+    }
+
+    #[test]
+    fn names_unnamed_engines_after_their_executable() {
+        let engines = vec![
+            EngineSettings {
+                name: String::from("Mine"),
+                path: PathBuf::from("/opt/a/first"),
+                ..Default::default()
+            },
+            EngineSettings {
+                path: PathBuf::from("/opt/b/second.exe"),
+                ..Default::default()
+            },
+        ];
+        let mut manager = EnginesManager::new(engines, Some(Path::new("/tb")));
+        manager.update(EnginesMessage::Rename(0, String::from("  ")));
+        manager.update(EnginesMessage::Remove(5));
+        assert_eq!(manager.syzygy_path(), Some(PathBuf::from("/tb")));
+        manager.update(EnginesMessage::SyzygyPathChanged(String::from(" ")));
+        assert_eq!(manager.syzygy_path(), None);
+
+        let names: Vec<String> = manager
+            .into_engines()
+            .into_iter()
+            .map(|engine| engine.name)
+            .collect();
+        assert_eq!(names, ["first", "second"]);
+    }
+}
@@ -0,0 +1,212 @@
+//! A vertical bar showing the evaluation of the engine, beside the board.
+use std::time::{Duration, Instant};
+
+use iced::{
+    Color, Element, Length, Point, Rectangle, Renderer, Size, Theme,
+    alignment::{Horizontal, Vertical},
+    mouse,
+    widget::canvas::{self, Canvas, Geometry},
+};
+
+use crate::engine::analysis::Score;
+
+const EVAL_BAR_WIDTH: f32 = 40.0;
+
+/// How fast the bar follows the evaluation : the remaining gap shrinks by about two thirds
+/// during this time.
+const TRANSITION_TIME: Duration = Duration::from_millis(150);
+
+/// The share of the bar given to White, from 0.0 to 1.0.
+/// Centipawns are mapped as winning chances, so that the bar does not saturate too soon.
+fn white_share(score: Score) -> f32 {
+    match score {
+        Score::Centipawns(centipawns) => 1.0 / (1.0 + 10_f32.powf(-(centipawns as f32) / 400.0)),
+        Score::Mate(moves) if moves > 0 => 1.0,
+        Score::Mate(0) => 0.5,
+        Score::Mate(_) => 0.0,
+    }
+}
+
+#[derive(Default)]
+pub struct EvalBar {
+    /// The evaluation to show, from the point of view of White.
+    score: Option<Score>,
+    /// The share of White currently drawn, moving toward the share of the score.
+    shown_share: Option<f32>,
+    last_frame: Option<Instant>,
+}
+
+impl EvalBar {
+    pub fn set_score(&mut self, score: Score) {
+        self.score = Some(score);
+        if self.shown_share.is_none() {
+            self.shown_share = Some(0.5);
+        }
+    }
+
+    /// Forgets the evaluation, which is shown again from the middle of the bar.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    fn target_share(&self) -> Option<f32> {
+        self.score.map(white_share)
+    }
+
+    pub fn is_moving(&self) -> bool {
+        match (self.shown_share, self.target_share()) {
+            (Some(shown), Some(target)) => (shown - target).abs() > 0.001,
+            _ => false,
+        }
+    }
+
+    /// Moves the bar toward the evaluation, at each frame of the transition.
+    pub fn animate(&mut self, now: Instant) {
+        let (Some(shown), Some(target)) = (self.shown_share, self.target_share()) else {
+            return;
+        };
+        let elapsed = self
+            .last_frame
+            .map_or(Duration::ZERO, |last_frame| now - last_frame)
+            .min(TRANSITION_TIME);
+        let progress = 1.0 - (-elapsed.as_secs_f32() / TRANSITION_TIME.as_secs_f32()).exp();
+        let shown = shown + (target - shown) * progress;
+        self.shown_share = Some(shown);
+        self.last_frame = if self.is_moving() {
+            Some(now)
+        } else {
+            self.shown_share = Some(target);
+            None
+        };
+    }
+
+    /// White is at the bottom, unless the board is reversed.
+    pub fn view<'a, Message: 'a>(&self, reversed: bool) -> Element<'a, Message> {
+        Canvas::new(EvalBarDrawing {
+            white_share: self.shown_share.unwrap_or(0.5),
+            score: self.score,
+            reversed,
+        })
+        .width(EVAL_BAR_WIDTH)
+        .height(Length::Fill)
+        .into()
+    }
+}
+
+struct EvalBarDrawing {
+    white_share: f32,
+    score: Option<Score>,
+    reversed: bool,
+}
+
+impl<Message> canvas::Program<Message> for EvalBarDrawing {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let white_height = bounds.height * self.white_share;
+        let black_height = bounds.height - white_height;
+        let (white_top, black_top) = if self.reversed {
+            (0.0, white_height)
+        } else {
+            (black_height, 0.0)
+        };
+        let dark = Color::from_rgb8(40, 40, 40);
+        let light = Color::from_rgb8(235, 235, 235);
+        frame.fill_rectangle(
+            Point::new(0.0, black_top),
+            Size::new(bounds.width, black_height),
+            dark,
+        );
+        frame.fill_rectangle(
+            Point::new(0.0, white_top),
+            Size::new(bounds.width, white_height),
+            light,
+        );
+
+        // The evaluation is written at the end of the side which is better.
+        if let Some(score) = self.score {
+            let white_leads = match score {
+                Score::Centipawns(centipawns) => centipawns >= 0,
+                Score::Mate(moves) => moves > 0,
+            };
+            let label = match score {
+                Score::Centipawns(centipawns) => format!("{:.1}", centipawns.abs() as f32 / 100.0),
+                Score::Mate(moves) => format!("M{}", moves.abs()),
+            };
+            let at_bottom = white_leads != self.reversed;
+            let (y, vertical_alignment) = if at_bottom {
+                (bounds.height - 4.0, Vertical::Bottom)
+            } else {
+                (4.0, Vertical::Top)
+            };
+            frame.fill_text(canvas::Text {
+                content: label,
+                position: Point::new(bounds.width / 2.0, y),
+                color: if white_leads { dark } else { light },
+                size: 12.0.into(),
+                horizontal_alignment: Horizontal::Center,
+                vertical_alignment,
+                ..canvas::Text::default()
+            });
+        }
+        vec![frame.into_geometry()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_scores_to_winning_chances() {
+        assert_eq!(white_share(Score::Centipawns(0)), 0.5);
+        assert!((white_share(Score::Centipawns(400)) - 10.0 / 11.0).abs() < 1e-6);
+        assert!((white_share(Score::Centipawns(-400)) - 1.0 / 11.0).abs() < 1e-6);
+        assert_eq!(white_share(Score::Mate(3)), 1.0);
+        assert_eq!(white_share(Score::Mate(-3)), 0.0);
+        assert_eq!(white_share(Score::Mate(0)), 0.5);
+    }
+
+    #[test]
+    fn moves_toward_the_score_then_stops() {
+        let mut eval_bar = EvalBar::default();
+        assert!(!eval_bar.is_moving());
+
+        eval_bar.set_score(Score::Mate(2));
+        assert_eq!(eval_bar.shown_share, Some(0.5));
+        assert!(eval_bar.is_moving());
+
+        let start = Instant::now();
+        eval_bar.animate(start);
+        // The first frame only starts the transition.
+        assert_eq!(eval_bar.shown_share, Some(0.5));
+
+        let mut now = start;
+        let mut previous = 0.5;
+        for _ in 0..100 {
+            if !eval_bar.is_moving() {
+                break;
+            }
+            now += Duration::from_millis(16);
+            eval_bar.animate(now);
+            let shown = eval_bar.shown_share.unwrap();
+            assert!(shown > previous);
+            previous = shown;
+        }
+        assert!(!eval_bar.is_moving());
+        assert_eq!(eval_bar.shown_share, Some(1.0));
+        assert_eq!(eval_bar.last_frame, None);
+
+        eval_bar.clear();
+        assert_eq!(eval_bar.shown_share, None);
+        assert_eq!(eval_bar.score, None);
+    }
+}
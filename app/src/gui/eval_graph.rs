@@ -0,0 +1,204 @@
+//! The evaluation of each position of the game, as a line chart.
+use iced::{
+    Color, Element, Length, Point, Rectangle, Renderer, Size, Theme, mouse,
+    widget::canvas::{self, Canvas, Geometry, Path, Stroke, event},
+};
+
+use crate::game::review::MAX_CENTIPAWNS;
+
+const GRAPH_HEIGHT: f32 = 120.0;
+
+/// The evaluations of the positions, from the point of view of White, with markers on the
+/// positions after the blunders. Clicking the graph gives the number of moves leading to the
+/// nearest position.
+pub struct EvalGraph<Message> {
+    /// The evaluations in centipawns, within the bounds of the review.
+    evals: Vec<i32>,
+    /// The number of positions of the game, some of them not evaluated yet.
+    positions: usize,
+    /// The positions reached by a blunder.
+    blunders: Vec<usize>,
+    /// The position shown on the board.
+    shown_plies: usize,
+    on_click: fn(usize) -> Message,
+}
+
+impl<Message> EvalGraph<Message> {
+    pub fn new(
+        evals: Vec<i32>,
+        positions: usize,
+        blunders: Vec<usize>,
+        shown_plies: usize,
+        on_click: fn(usize) -> Message,
+    ) -> Self {
+        Self {
+            evals,
+            positions,
+            blunders,
+            shown_plies,
+            on_click,
+        }
+    }
+
+    pub fn view<'a>(self) -> Element<'a, Message>
+    where
+        Message: 'a,
+    {
+        Canvas::new(self)
+            .width(Length::Fill)
+            .height(GRAPH_HEIGHT)
+            .into()
+    }
+
+    /// The horizontal position of the position, given by its number of moves.
+    fn x(&self, plies: usize, width: f32) -> f32 {
+        match self.positions {
+            0 | 1 => width / 2.0,
+            positions => width * plies as f32 / (positions - 1) as f32,
+        }
+    }
+
+    fn y(eval: i32, height: f32) -> f32 {
+        let white_share = (eval + MAX_CENTIPAWNS) as f32 / (2 * MAX_CENTIPAWNS) as f32;
+        height * (1.0 - white_share.clamp(0.0, 1.0))
+    }
+
+    /// The number of moves leading to the position nearest to the horizontal position.
+    fn plies_at(&self, x: f32, width: f32) -> usize {
+        let last = self.positions.saturating_sub(1);
+        if last == 0 || width <= 0.0 {
+            return 0;
+        }
+        ((x / width * last as f32).round().max(0.0) as usize).min(last)
+    }
+}
+
+impl<Message> canvas::Program<Message> for EvalGraph<Message> {
+    type State = ();
+
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        match event {
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                match cursor.position_in(bounds) {
+                    Some(position) => (
+                        event::Status::Captured,
+                        Some((self.on_click)(self.plies_at(position.x, bounds.width))),
+                    ),
+                    None => (event::Status::Ignored, None),
+                }
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let (width, height) = (bounds.width, bounds.height);
+        frame.fill_rectangle(
+            Point::ORIGIN,
+            Size::new(width, height),
+            Color::from_rgb8(40, 40, 40),
+        );
+
+        // The advantage of White is the light area under the curve.
+        if let Some(last) = self.evals.len().checked_sub(1) {
+            let area = Path::new(|builder| {
+                builder.move_to(Point::new(self.x(0, width), height));
+                for (plies, eval) in self.evals.iter().enumerate() {
+                    builder.line_to(Point::new(self.x(plies, width), Self::y(*eval, height)));
+                }
+                builder.line_to(Point::new(self.x(last, width), height));
+                builder.close();
+            });
+            frame.fill(&area, Color::from_rgb8(235, 235, 235));
+        }
+        frame.stroke(
+            &Path::line(
+                Point::new(0.0, height / 2.0),
+                Point::new(width, height / 2.0),
+            ),
+            Stroke::default()
+                .with_color(Color::from_rgb8(128, 128, 128))
+                .with_width(1.0),
+        );
+
+        let shown_x = self.x(self.shown_plies, width);
+        frame.stroke(
+            &Path::line(Point::new(shown_x, 0.0), Point::new(shown_x, height)),
+            Stroke::default()
+                .with_color(Color::from_rgb8(30, 144, 255))
+                .with_width(2.0),
+        );
+
+        for plies in &self.blunders {
+            if let Some(eval) = self.evals.get(*plies) {
+                let center = Point::new(self.x(*plies, width), Self::y(*eval, height));
+                frame.fill(&Path::circle(center, 4.0), Color::from_rgb8(220, 20, 60));
+            }
+        }
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        _state: &Self::State,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        if cursor.is_over(bounds) {
+            mouse::Interaction::Pointer
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(positions: usize) -> EvalGraph<usize> {
+        EvalGraph::new(Vec::new(), positions, Vec::new(), 0, |plies| plies)
+    }
+
+    #[test]
+    fn places_the_positions_across_the_width() {
+        assert_eq!(graph(1).x(0, 200.0), 100.0);
+        assert_eq!(graph(5).x(0, 200.0), 0.0);
+        assert_eq!(graph(5).x(2, 200.0), 100.0);
+        assert_eq!(graph(5).x(4, 200.0), 200.0);
+    }
+
+    #[test]
+    fn bounds_the_evaluations_to_the_height() {
+        assert_eq!(EvalGraph::<usize>::y(0, 100.0), 50.0);
+        assert_eq!(EvalGraph::<usize>::y(MAX_CENTIPAWNS, 100.0), 0.0);
+        assert_eq!(EvalGraph::<usize>::y(-MAX_CENTIPAWNS, 100.0), 100.0);
+        assert_eq!(EvalGraph::<usize>::y(3 * MAX_CENTIPAWNS, 100.0), 0.0);
+    }
+
+    #[test]
+    fn clicks_show_the_nearest_position() {
+        let five = graph(5);
+        assert_eq!(five.plies_at(0.0, 200.0), 0);
+        assert_eq!(five.plies_at(60.0, 200.0), 1);
+        assert_eq!(five.plies_at(80.0, 200.0), 2);
+        assert_eq!(five.plies_at(-30.0, 200.0), 0);
+        assert_eq!(five.plies_at(500.0, 200.0), 4);
+        assert_eq!(graph(1).plies_at(150.0, 200.0), 0);
+        assert_eq!(five.plies_at(10.0, 0.0), 0);
+    }
+}
@@ -0,0 +1,134 @@
+//! The panel of the opening explorer : the moves played from the position on the board in a PGN
+//! collection, with their results.
+use std::path::{Path, PathBuf};
+
+use iced::{
+    Element, Length,
+    widget::{button, column, row, scrollable, text, text_input},
+};
+use owlchess::Board;
+
+use crate::game::explorer::{Continuation, OpeningExplorer};
+
+#[derive(Debug, Clone)]
+pub enum ExplorerMessage {
+    PathChanged(String),
+    /// Indexes the collection at the path.
+    Load,
+    /// Plays the move, in UCI notation.
+    PlayMove(String),
+}
+
+pub struct ExplorerPanel {
+    /// The PGN collection.
+    path: String,
+    error: Option<String>,
+}
+
+impl ExplorerPanel {
+    pub fn new(path: Option<&Path>) -> Self {
+        Self {
+            path: path
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+            error: None,
+        }
+    }
+
+    /// The collection to index, None when left empty.
+    pub fn path(&self) -> Option<PathBuf> {
+        let path = self.path.trim();
+        (!path.is_empty()).then(|| PathBuf::from(path))
+    }
+
+    pub fn set_error(&mut self, error: Option<String>) {
+        self.error = error;
+    }
+
+    pub fn update(&mut self, message: ExplorerMessage) {
+        match message {
+            ExplorerMessage::PathChanged(path) => self.path = path,
+            ExplorerMessage::Load | ExplorerMessage::PlayMove(_) => {}
+        }
+    }
+
+    fn continuation_view<'a>(
+        position: &Board,
+        continuation: &Continuation,
+    ) -> Option<Element<'a, ExplorerMessage>> {
+        // A move of another position with the same hash is not shown.
+        let san = continuation.chess_move.san(position).ok()?;
+        let stats = continuation.stats;
+        Some(
+            button(
+                row![
+                    text(san.to_string()).width(60),
+                    text(stats.games.to_string()).width(60),
+                    text(format!(
+                        "{:.0}% / {:.0}% / {:.0}%",
+                        stats.percent(stats.white_wins),
+                        stats.percent(stats.draws),
+                        stats.percent(stats.black_wins)
+                    ))
+                    .width(Length::Fill),
+                ]
+                .spacing(5),
+            )
+            .style(button::text)
+            .width(Length::Fill)
+            .on_press(ExplorerMessage::PlayMove(
+                continuation.chess_move.uci().to_string(),
+            ))
+            .into(),
+        )
+    }
+
+    pub fn view<'a>(
+        &'a self,
+        explorer: Option<&OpeningExplorer>,
+        position: &Board,
+    ) -> Element<'a, ExplorerMessage> {
+        let mut panel = column![
+            text("Opening explorer").size(20),
+            row![
+                text_input("Path to a PGN collection", &self.path)
+                    .on_input(ExplorerMessage::PathChanged)
+                    .on_submit(ExplorerMessage::Load),
+                button(text("Load")).on_press_maybe(
+                    (!self.path.trim().is_empty()).then_some(ExplorerMessage::Load)
+                ),
+            ]
+            .spacing(5),
+        ]
+        .push_maybe(self.error.as_deref().map(text))
+        .spacing(8);
+        let Some(explorer) = explorer else {
+            return panel.into();
+        };
+
+        let mut games = format!("{} games", explorer.games());
+        if explorer.skipped() > 0 {
+            games.push_str(&format!(", {} unreadable", explorer.skipped()));
+        }
+        panel = panel.push(text(games));
+        let continuations = explorer.continuations(position);
+        if continuations.is_empty() {
+            return panel.push(text("No game reached this position.")).into();
+        }
+        let rows = continuations
+            .iter()
+            .filter_map(|continuation| Self::continuation_view(position, continuation))
+            .fold(
+                column![
+                    row![
+                        text("Move").width(60),
+                        text("Games").width(60),
+                        text("White / Draw / Black"),
+                    ]
+                    .spacing(5)
+                ],
+                |rows, row| rows.push(row),
+            );
+        panel.push(scrollable(rows).height(Length::Fill)).into()
+    }
+}
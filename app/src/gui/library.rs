@@ -0,0 +1,244 @@
+//! The browser of the game library : the stored games, filtered by their fields.
+use std::fmt;
+
+use iced::{
+    Element, Length,
+    widget::{button, column, pick_list, row, scrollable, text, text_input},
+};
+
+use crate::library::LibraryEntry;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResultFilter {
+    #[default]
+    Any,
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl ResultFilter {
+    pub const ALL: [ResultFilter; 4] = [
+        ResultFilter::Any,
+        ResultFilter::WhiteWins,
+        ResultFilter::BlackWins,
+        ResultFilter::Draw,
+    ];
+
+    fn matches(self, result: &str) -> bool {
+        match self {
+            ResultFilter::Any => true,
+            ResultFilter::WhiteWins => result == "1-0",
+            ResultFilter::BlackWins => result == "0-1",
+            ResultFilter::Draw => result == "1/2-1/2",
+        }
+    }
+}
+
+impl fmt::Display for ResultFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            ResultFilter::Any => "Any result",
+            ResultFilter::WhiteWins => "White wins",
+            ResultFilter::BlackWins => "Black wins",
+            ResultFilter::Draw => "Draw",
+        };
+        write!(f, "{label}")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum LibraryMessage {
+    SetDate(String),
+    SetOpponent(String),
+    SetResult(ResultFilter),
+    SetOpening(String),
+    SetMinMoves(String),
+    SetMaxMoves(String),
+    /// Opens the game of the given index in the library, to replay it.
+    Open(usize),
+}
+
+/// The filters of the browser. A text filter left empty matches every game.
+#[derive(Default)]
+pub struct LibraryBrowser {
+    /// The start of the date, as `2024` or `2024.05`.
+    date: String,
+    /// A part of the name of a player or of the engine.
+    opponent: String,
+    result: ResultFilter,
+    /// A part of the opening.
+    opening: String,
+    min_moves: String,
+    max_moves: String,
+}
+
+/// Whether the text contains the part, ignoring the case.
+fn contains(text: &str, part: &str) -> bool {
+    text.to_lowercase().contains(&part.trim().to_lowercase())
+}
+
+impl LibraryBrowser {
+    pub fn update(&mut self, message: LibraryMessage) {
+        match message {
+            LibraryMessage::SetDate(date) => self.date = date,
+            LibraryMessage::SetOpponent(opponent) => self.opponent = opponent,
+            LibraryMessage::SetResult(result) => self.result = result,
+            LibraryMessage::SetOpening(opening) => self.opening = opening,
+            LibraryMessage::SetMinMoves(moves) => self.min_moves = moves,
+            LibraryMessage::SetMaxMoves(moves) => self.max_moves = moves,
+            LibraryMessage::Open(_) => {}
+        }
+    }
+
+    fn matches(&self, entry: &LibraryEntry) -> bool {
+        // A length which is not a number does not filter.
+        let min_moves = self.min_moves.trim().parse::<usize>().unwrap_or(0);
+        let max_moves = self.max_moves.trim().parse::<usize>().unwrap_or(usize::MAX);
+        entry.date.starts_with(self.date.trim())
+            && (contains(&entry.white, &self.opponent)
+                || contains(&entry.black, &self.opponent)
+                || entry
+                    .engine
+                    .as_ref()
+                    .is_some_and(|engine| contains(engine, &self.opponent)))
+            && self.result.matches(&entry.result)
+            && contains(&entry.opening, &self.opening)
+            && (min_moves..=max_moves).contains(&entry.moves())
+    }
+
+    fn entry_view(index: usize, entry: &LibraryEntry) -> Element<'_, LibraryMessage> {
+        button(
+            row![
+                text(&entry.date).width(100),
+                text(format!("{} - {}", entry.white, entry.black)).width(Length::FillPortion(2)),
+                text(entry.engine.as_deref().unwrap_or("-")).width(Length::FillPortion(1)),
+                text(&entry.result).width(70),
+                text(&entry.opening).width(Length::FillPortion(2)),
+                text(format!("{} moves", entry.moves())).width(80),
+            ]
+            .spacing(10),
+        )
+        .style(button::text)
+        .width(Length::Fill)
+        .on_press(LibraryMessage::Open(index))
+        .into()
+    }
+
+    /// The games of the library matching the filters, the last played first.
+    pub fn view<'a>(&'a self, entries: &'a [LibraryEntry]) -> Element<'a, LibraryMessage> {
+        let games = entries
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, entry)| self.matches(entry))
+            .fold(column![].spacing(2), |games, (index, entry)| {
+                games.push(Self::entry_view(index, entry))
+            });
+
+        column![
+            text("Library").size(20),
+            row![
+                text_input("Date (YYYY.MM.DD)", &self.date)
+                    .on_input(LibraryMessage::SetDate)
+                    .width(150),
+                text_input("Player or engine", &self.opponent)
+                    .on_input(LibraryMessage::SetOpponent),
+                pick_list(
+                    ResultFilter::ALL,
+                    Some(self.result),
+                    LibraryMessage::SetResult
+                ),
+                text_input("Opening", &self.opening).on_input(LibraryMessage::SetOpening),
+                text_input("Min moves", &self.min_moves)
+                    .on_input(LibraryMessage::SetMinMoves)
+                    .width(100),
+                text_input("Max moves", &self.max_moves)
+                    .on_input(LibraryMessage::SetMaxMoves)
+                    .width(100),
+            ]
+            .spacing(5),
+            if entries.is_empty() {
+                Element::from(text("No game stored yet : finished games are added here."))
+            } else {
+                scrollable(games.padding(5)).height(Length::Fill).into()
+            },
+        ]
+        .spacing(10)
+        .max_width(1000)
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        date: &str,
+        white: &str,
+        engine: Option<&str>,
+        result: &str,
+        plies: usize,
+    ) -> LibraryEntry {
+        let engine = engine.map_or(String::new(), |engine| format!("engine = \"{engine}\"\n"));
+        toml::from_str(&format!(
+            "date = \"{date}\"\nwhite = \"{white}\"\nblack = \"Player\"\n{engine}\
+             result = \"{result}\"\nopening = \"B27 Sicilian Defense\"\nplies = {plies}\n\
+             offset = 0\nlength = 0\n"
+        ))
+        .unwrap()
+    }
+
+    fn search(browser: &LibraryBrowser, entries: &[LibraryEntry]) -> Vec<String> {
+        entries
+            .iter()
+            .filter(|entry| browser.matches(entry))
+            .map(|entry| entry.white.clone())
+            .collect()
+    }
+
+    #[test]
+    fn searches_the_games_by_their_fields() {
+        let entries = [
+            entry("2024.05.01", "Stockfish", Some("Stockfish"), "1-0", 40),
+            entry("2024.06.12", "Alice", None, "1/2-1/2", 81),
+            entry("2025.01.03", "GNU Chess", Some("GNU Chess"), "0-1", 12),
+        ];
+        let mut browser = LibraryBrowser::default();
+        assert_eq!(
+            search(&browser, &entries),
+            ["Stockfish", "Alice", "GNU Chess"]
+        );
+
+        browser.update(LibraryMessage::SetDate(String::from("2024.06 ")));
+        assert_eq!(search(&browser, &entries), ["Alice"]);
+        browser.update(LibraryMessage::SetDate(String::new()));
+
+        // The opponent is looked for in the names of the players and of the engine.
+        browser.update(LibraryMessage::SetOpponent(String::from("stock")));
+        assert_eq!(search(&browser, &entries), ["Stockfish"]);
+        browser.update(LibraryMessage::SetOpponent(String::from("player")));
+        assert_eq!(search(&browser, &entries).len(), 3);
+        browser.update(LibraryMessage::SetOpponent(String::new()));
+
+        browser.update(LibraryMessage::SetResult(ResultFilter::BlackWins));
+        assert_eq!(search(&browser, &entries), ["GNU Chess"]);
+        browser.update(LibraryMessage::SetResult(ResultFilter::Any));
+
+        browser.update(LibraryMessage::SetOpening(String::from("sicilian")));
+        assert_eq!(search(&browser, &entries).len(), 3);
+        browser.update(LibraryMessage::SetOpening(String::from("French")));
+        assert!(search(&browser, &entries).is_empty());
+        browser.update(LibraryMessage::SetOpening(String::new()));
+
+        // The lengths are counted in full moves, and an invalid length does not filter.
+        browser.update(LibraryMessage::SetMinMoves(String::from("20")));
+        browser.update(LibraryMessage::SetMaxMoves(String::from("41")));
+        assert_eq!(search(&browser, &entries), ["Stockfish", "Alice"]);
+        browser.update(LibraryMessage::SetMaxMoves(String::from("forty")));
+        assert_eq!(search(&browser, &entries), ["Stockfish", "Alice"]);
+        browser.update(LibraryMessage::SetMinMoves(String::from("21")));
+        assert_eq!(search(&browser, &entries), ["Alice"]);
+    }
+}
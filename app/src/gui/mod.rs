@@ -0,0 +1,12 @@
+pub mod analysis;
+pub mod engine_match;
+pub mod engine_options;
+pub mod engines_manager;
+pub mod eval_bar;
+pub mod eval_graph;
+pub mod explorer;
+pub mod library;
+pub mod move_list;
+pub mod new_game;
+pub mod position_editor;
+pub mod review;
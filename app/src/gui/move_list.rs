@@ -0,0 +1,143 @@
+//! The moves of the game in SAN, numbered by pairs, under the opening of the position shown on
+//! the board. The move leading to this position is highlighted, and clicking a move shows the
+//! position it leads to.
+use std::sync::LazyLock;
+
+use iced::{
+    Element, Length,
+    widget::{button, column, row, scrollable, text},
+};
+use owlchess::Color;
+
+use crate::game::{Game, eco::EcoOpening};
+
+/// Lets the list be scrolled to the position shown on the board.
+static SCROLLABLE_ID: LazyLock<scrollable::Id> = LazyLock::new(scrollable::Id::unique);
+
+const NUMBER_WIDTH: f32 = 40.0;
+const MOVE_WIDTH: f32 = 80.0;
+
+pub struct MoveList {
+    /// The moves in SAN, written once as they are played rather than at each redraw.
+    moves: Vec<String>,
+    /// The opening after each number of moves.
+    openings: Vec<Option<&'static EcoOpening>>,
+    /// The number of the first move.
+    first_number: u16,
+    /// Whether the game starts with a move of Black.
+    black_first: bool,
+}
+
+impl MoveList {
+    pub fn new(game: &Game) -> Self {
+        let start = game.start_position();
+        Self {
+            moves: game
+                .walk()
+                .map(|(board, played)| played.san(board))
+                .collect(),
+            openings: game.openings().to_vec(),
+            first_number: start.raw().move_number,
+            black_first: start.side() == Color::Black,
+        }
+    }
+
+    /// Scrolls the list to the move leading to the position shown on the board.
+    pub fn scroll_to<Message>(&self, shown_plies: usize) -> iced::Task<Message> {
+        let y = if self.moves.len() > 1 {
+            shown_plies.saturating_sub(1) as f32 / (self.moves.len() - 1) as f32
+        } else {
+            0.0
+        };
+        scrollable::snap_to(
+            SCROLLABLE_ID.clone(),
+            scrollable::RelativeOffset { x: 0.0, y },
+        )
+    }
+
+    fn move_button<'a, Message: Clone + 'a>(
+        &'a self,
+        index: usize,
+        shown_plies: usize,
+        on_select: fn(usize) -> Message,
+    ) -> Element<'a, Message> {
+        let style = if index + 1 == shown_plies {
+            button::primary
+        } else {
+            button::text
+        };
+        button(text(&self.moves[index]))
+            .style(style)
+            .width(MOVE_WIDTH)
+            .on_press(on_select(index + 1))
+            .into()
+    }
+
+    pub fn view<'a, Message: Clone + 'a>(
+        &'a self,
+        shown_plies: usize,
+        on_select: fn(usize) -> Message,
+    ) -> Element<'a, Message> {
+        let mut rows = column![].spacing(2);
+        // The moves by pairs, the first pair starting with an empty cell when Black begins.
+        let offset = usize::from(self.black_first);
+        for pair in 0..(self.moves.len() + offset).div_ceil(2) {
+            let number = self.first_number + pair as u16;
+            let mut line = row![text(format!("{number}.")).width(NUMBER_WIDTH)];
+            for index in [2 * pair, 2 * pair + 1] {
+                match index.checked_sub(offset) {
+                    Some(index) if index < self.moves.len() => {
+                        line = line.push(self.move_button(index, shown_plies, on_select));
+                    }
+                    Some(_) => {}
+                    None => line = line.push(text("...").width(MOVE_WIDTH)),
+                }
+            }
+            rows = rows.push(line.spacing(5));
+        }
+        if self.moves.is_empty() {
+            rows = rows.push(text("No move played."));
+        }
+        let opening = self.openings.get(shown_plies).copied().flatten();
+        column![]
+            .push_maybe(opening.map(|opening| text(opening.to_string()).size(16)))
+            .push(
+                scrollable(rows)
+                    .id(SCROLLABLE_ID.clone())
+                    .height(Length::Fill),
+            )
+            .spacing(8)
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use owlchess::Board;
+
+    use super::*;
+
+    #[test]
+    fn writes_the_moves_once_in_san() {
+        let mut game = Game::new(Board::initial());
+        let moves = ["e2e4", "e7e5", "g1f3"].map(String::from);
+        assert!(game.play_line(0, &moves));
+        let move_list = MoveList::new(&game);
+        assert_eq!(move_list.moves, ["e4", "e5", "Nf3"]);
+        assert_eq!(move_list.first_number, 1);
+        assert!(!move_list.black_first);
+        assert_eq!(move_list.openings.len(), 4);
+    }
+
+    #[test]
+    fn numbers_games_starting_with_black() {
+        let start =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 7").unwrap();
+        let mut game = Game::new(start);
+        assert!(game.play_line(0, &[String::from("c7c5")]));
+        let move_list = MoveList::new(&game);
+        assert_eq!(move_list.moves, ["c5"]);
+        assert_eq!(move_list.first_number, 7);
+        assert!(move_list.black_first);
+    }
+}
@@ -0,0 +1,396 @@
+//! The dialog for starting a new game against the engine.
+use std::{fmt, path::PathBuf};
+
+use iced::{
+    Element,
+    widget::{button, checkbox, column, pick_list, radio, row, slider, text, text_input},
+};
+use owlchess::{Board, Color};
+
+use crate::{
+    engine::{
+        EngineOption,
+        conduct::DEFAULT_RESIGN_SCORE,
+        strength::{Strength, StrengthControl},
+        uci,
+    },
+    game::{
+        Game, chess960,
+        handicap::{self, Handicap},
+    },
+    settings::{BookSettings, EngineSettings},
+};
+
+/// The number of moves which may be played from the opening book, at most.
+const MAX_BOOK_DEPTH: usize = 40;
+
+/// An engine of the registry, or no engine at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineChoice {
+    pub name: String,
+    pub path: Option<PathBuf>,
+}
+
+impl EngineChoice {
+    fn none() -> Self {
+        Self {
+            name: String::from("None (play both sides)"),
+            path: None,
+        }
+    }
+}
+
+impl fmt::Display for EngineChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum NewGameMessage {
+    SelectEngine(EngineChoice),
+    SetPlayerSide(Color),
+    SetLimitStrength(bool),
+    SetLevel(i64),
+    SetBookPath(String),
+    SetBookDepth(usize),
+    SetBookBestMoveOnly(bool),
+    SetHandicap(Handicap),
+    /// The side giving the odds.
+    SetHandicapGiver(Color),
+    SetEngineResigns(bool),
+    SetResignScore(String),
+    SetChess960(bool),
+    SetChess960Number(String),
+    RandomChess960,
+}
+
+/// The engine, as known by the dialog.
+pub enum EngineStatus<'a> {
+    NotLoaded,
+    Loading,
+    Ready {
+        name: &'a str,
+        options: &'a [EngineOption],
+    },
+    Failed(&'a str),
+}
+
+pub struct NewGameDialog {
+    start_position: Board,
+    engine_path: Option<PathBuf>,
+    player_side: Color,
+    limit_strength: bool,
+    /// The last chosen level, kept within the range of the engine when it is used.
+    level: Option<i64>,
+    book_path: String,
+    book_depth: usize,
+    book_best_move_only: bool,
+    handicap: Handicap,
+    handicap_giver: Color,
+    engine_resigns: bool,
+    /// In centipawns.
+    resign_score: String,
+    /// Starts from a Chess960 position rather than from the given one, without handicap.
+    chess960: bool,
+    chess960_number: String,
+}
+
+impl NewGameDialog {
+    pub fn new(start_position: Board, engine_path: Option<PathBuf>, book: &BookSettings) -> Self {
+        Self {
+            start_position,
+            engine_path,
+            player_side: Color::White,
+            limit_strength: false,
+            level: None,
+            book_path: book
+                .path
+                .as_ref()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+            book_depth: book.depth,
+            book_best_move_only: book.best_move_only,
+            handicap: Handicap::None,
+            handicap_giver: Color::Black,
+            engine_resigns: true,
+            resign_score: DEFAULT_RESIGN_SCORE.to_string(),
+            chess960: false,
+            chess960_number: chess960::random_number().to_string(),
+        }
+    }
+
+    /// The start position, or None when the handicap cannot be given from the position.
+    fn start_position(&self) -> Option<Board> {
+        self.handicap
+            .apply(&self.start_position, self.handicap_giver)
+    }
+
+    fn chess960_number(&self) -> Option<u16> {
+        self.chess960_number
+            .trim()
+            .parse()
+            .ok()
+            .filter(|number| *number < chess960::POSITION_COUNT)
+    }
+
+    /// The new game, with its handicap header, or None when its start position cannot be set up.
+    pub fn game(&self) -> Option<Game> {
+        if self.chess960 {
+            let (start, castling) = chess960::start_position(self.chess960_number()?);
+            return Some(Game::new_chess960(start, castling));
+        }
+        let mut game = Game::new(self.start_position()?);
+        if self.handicap != Handicap::None {
+            game.set_header(
+                handicap::HEADER,
+                self.handicap.header_value(self.handicap_giver),
+            );
+        }
+        Some(game)
+    }
+
+    /// Whether the game can start : a Chess960 game needs an engine playing it, if any.
+    pub fn can_start(&self, engine: &EngineStatus<'_>) -> bool {
+        match engine {
+            EngineStatus::Loading => false,
+            EngineStatus::Ready { options, .. } if self.chess960 => {
+                uci::plays_chess960(options) && self.game().is_some()
+            }
+            _ => self.game().is_some(),
+        }
+    }
+
+    pub fn player_side(&self) -> Color {
+        self.player_side
+    }
+
+    /// The opening book chosen for the engine.
+    pub fn book(&self) -> BookSettings {
+        let book_path = self.book_path.trim();
+        BookSettings {
+            path: (!book_path.is_empty()).then(|| PathBuf::from(book_path)),
+            depth: self.book_depth,
+            best_move_only: self.book_best_move_only,
+        }
+    }
+
+    /// The score below which the engine resigns, None when it plays on to the end.
+    pub fn resign_score(&self) -> Option<i32> {
+        self.engine_resigns.then(|| {
+            self.resign_score
+                .trim()
+                .parse()
+                .ok()
+                .filter(|score: &i32| *score > 0)
+                .unwrap_or(DEFAULT_RESIGN_SCORE)
+        })
+    }
+
+    fn level(&self, control: StrengthControl) -> i64 {
+        let (min, max) = control.range();
+        self.level.unwrap_or((min + max) / 2).clamp(min, max)
+    }
+
+    /// The strength chosen for an engine with the given options.
+    pub fn strength(&self, options: &[EngineOption]) -> Strength {
+        let control = StrengthControl::from_options(options);
+        Strength {
+            control,
+            level: self.limit_strength.then(|| self.level(control)),
+        }
+    }
+
+    pub fn update(&mut self, message: NewGameMessage) {
+        match message {
+            NewGameMessage::SelectEngine(choice) => self.engine_path = choice.path,
+            NewGameMessage::SetPlayerSide(side) => self.player_side = side,
+            NewGameMessage::SetLimitStrength(limit_strength) => {
+                self.limit_strength = limit_strength
+            }
+            NewGameMessage::SetLevel(level) => self.level = Some(level),
+            NewGameMessage::SetBookPath(path) => self.book_path = path,
+            NewGameMessage::SetBookDepth(depth) => self.book_depth = depth,
+            NewGameMessage::SetBookBestMoveOnly(best_move_only) => {
+                self.book_best_move_only = best_move_only
+            }
+            NewGameMessage::SetHandicap(handicap) => self.handicap = handicap,
+            NewGameMessage::SetHandicapGiver(giver) => self.handicap_giver = giver,
+            NewGameMessage::SetEngineResigns(engine_resigns) => {
+                self.engine_resigns = engine_resigns
+            }
+            NewGameMessage::SetResignScore(score) => self.resign_score = score,
+            NewGameMessage::SetChess960(chess960) => self.chess960 = chess960,
+            NewGameMessage::SetChess960Number(number) => self.chess960_number = number,
+            NewGameMessage::RandomChess960 => {
+                self.chess960_number = chess960::random_number().to_string()
+            }
+        }
+    }
+
+    pub fn view(
+        &self,
+        engines: &[EngineSettings],
+        engine: EngineStatus<'_>,
+    ) -> Element<'_, NewGameMessage> {
+        let choices: Vec<EngineChoice> = std::iter::once(EngineChoice::none())
+            .chain(engines.iter().map(|engine| EngineChoice {
+                name: engine.name.clone(),
+                path: Some(engine.path.clone()),
+            }))
+            .collect();
+        let selected = choices
+            .iter()
+            .find(|choice| choice.path == self.engine_path)
+            .cloned();
+
+        let engine_status = match &engine {
+            EngineStatus::NotLoaded if engines.is_empty() => {
+                String::from("Register engines from the engines manager to play against them.")
+            }
+            EngineStatus::NotLoaded => String::from("Both sides are played on the board."),
+            EngineStatus::Loading => String::from("Loading the engine..."),
+            EngineStatus::Ready { name, .. } => format!("Playing against {name}."),
+            EngineStatus::Failed(error) => error.to_string(),
+        };
+
+        let mut content = column![
+            text("Opponent"),
+            pick_list(choices, selected, NewGameMessage::SelectEngine),
+            text(engine_status),
+            text("Your side"),
+            row![
+                radio(
+                    "White",
+                    Color::White,
+                    Some(self.player_side),
+                    NewGameMessage::SetPlayerSide
+                ),
+                radio(
+                    "Black",
+                    Color::Black,
+                    Some(self.player_side),
+                    NewGameMessage::SetPlayerSide
+                ),
+            ]
+            .spacing(10),
+        ]
+        .spacing(8)
+        .max_width(500);
+
+        content = content.push(
+            checkbox("Chess960 (Fischer Random)", self.chess960)
+                .on_toggle(NewGameMessage::SetChess960),
+        );
+        if self.chess960 {
+            let position = match self.chess960_number() {
+                Some(number) => format!(
+                    "{}. Castle by dropping the king onto the rook.",
+                    chess960::back_rank(number)
+                ),
+                None => String::from("Choose a position from 0 to 959."),
+            };
+            content = content
+                .push(
+                    row![
+                        text_input("Position from 0 to 959", &self.chess960_number)
+                            .on_input(NewGameMessage::SetChess960Number),
+                        button(text("Random")).on_press(NewGameMessage::RandomChess960),
+                    ]
+                    .spacing(5),
+                )
+                .push(text(position));
+            if let EngineStatus::Ready { name, options } = &engine
+                && !uci::plays_chess960(options)
+            {
+                content = content.push(text(format!("{name} does not play Chess960.")));
+            }
+        } else {
+            content = content.push(text("Handicap")).push(pick_list(
+                Handicap::ALL,
+                Some(self.handicap),
+                NewGameMessage::SetHandicap,
+            ));
+        }
+        if !self.chess960 && self.handicap != Handicap::None {
+            content = content.push(
+                row![
+                    text("Given by"),
+                    radio(
+                        "White",
+                        Color::White,
+                        Some(self.handicap_giver),
+                        NewGameMessage::SetHandicapGiver
+                    ),
+                    radio(
+                        "Black",
+                        Color::Black,
+                        Some(self.handicap_giver),
+                        NewGameMessage::SetHandicapGiver
+                    ),
+                ]
+                .spacing(10),
+            );
+            if self.start_position().is_none() {
+                content = content.push(text(
+                    "The piece given is not on its square in the start position.",
+                ));
+            }
+        }
+
+        if let EngineStatus::Ready { options, .. } = engine {
+            let control = StrengthControl::from_options(options);
+            let (min, max) = control.range();
+            let level = self.level(control);
+            content = content.push(text("Engine strength")).push(
+                checkbox("Limit the engine strength", self.limit_strength)
+                    .on_toggle(NewGameMessage::SetLimitStrength),
+            );
+            if self.limit_strength {
+                content = content
+                    .push(slider(min as i32..=max as i32, level as i32, |level| {
+                        NewGameMessage::SetLevel(i64::from(level))
+                    }))
+                    .push(text(control.level_label(level)));
+            }
+            content = content.push(
+                checkbox("The engine resigns lost positions", self.engine_resigns)
+                    .on_toggle(NewGameMessage::SetEngineResigns),
+            );
+            if self.engine_resigns {
+                content = content.push(
+                    row![
+                        text("Below"),
+                        text_input("Centipawns", &self.resign_score)
+                            .on_input(NewGameMessage::SetResignScore)
+                            .width(100),
+                        text("centipawns for 3 moves in a row"),
+                    ]
+                    .spacing(5),
+                );
+            }
+            content = content.push(text("Opening book")).push(
+                text_input("Polyglot book (.bin), none by default", &self.book_path)
+                    .on_input(NewGameMessage::SetBookPath),
+            );
+            if !self.book_path.trim().is_empty() {
+                content = content
+                    .push(slider(
+                        1..=MAX_BOOK_DEPTH as u32,
+                        self.book_depth.min(MAX_BOOK_DEPTH) as u32,
+                        |depth| NewGameMessage::SetBookDepth(depth as usize),
+                    ))
+                    .push(text(format!(
+                        "Book moves during the first {} moves of the game",
+                        self.book_depth
+                    )))
+                    .push(
+                        checkbox("Best book move only", self.book_best_move_only)
+                            .on_toggle(NewGameMessage::SetBookBestMoveOnly),
+                    );
+            }
+        }
+
+        content.into()
+    }
+}
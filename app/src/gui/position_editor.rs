@@ -0,0 +1,336 @@
+//! A panel for setting up a custom starting position.
+use std::fmt;
+
+use iced::{
+    Element, Length,
+    widget::{button, checkbox, column, container, pick_list, radio, row, text, text_input},
+};
+use owlchess::{CastlingSide, Cell, Color, Coord, File, Piece, Rank, RawBoard};
+
+use chessboard::{Chessboard, ChessboardOptionsBuilder};
+
+/// Reasons why an edited position cannot be used to start a game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PositionError {
+    NoKing(Color),
+    TooManyKings(Color),
+    TooManyPieces(Color),
+    PawnOnBackRank(Coord),
+    CastlingWithoutKing(Color),
+    CastlingWithoutRook(Color, CastlingSide),
+    InvalidEnPassant(Coord),
+    OpponentKingAttacked(Color),
+}
+
+fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::White => "White",
+        Color::Black => "Black",
+    }
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PositionError::NoKing(color) => write!(f, "{} has no king.", color_name(*color)),
+            PositionError::TooManyKings(color) => {
+                write!(f, "{} has more than one king.", color_name(*color))
+            }
+            PositionError::TooManyPieces(color) => {
+                write!(f, "{} has more than 16 pieces.", color_name(*color))
+            }
+            PositionError::PawnOnBackRank(coord) => {
+                write!(
+                    f,
+                    "There is a pawn on {coord}, pawns cannot stand on the first or last rank."
+                )
+            }
+            PositionError::CastlingWithoutKing(color) => write!(
+                f,
+                "{} can only castle if its king stands on its initial square.",
+                color_name(*color)
+            ),
+            PositionError::CastlingWithoutRook(color, side) => write!(
+                f,
+                "{} can only castle {} if the matching rook stands on its initial square.",
+                color_name(*color),
+                match side {
+                    CastlingSide::King => "kingside",
+                    CastlingSide::Queen => "queenside",
+                }
+            ),
+            PositionError::InvalidEnPassant(coord) => write!(
+                f,
+                "En passant on {coord} needs a pawn that has just moved two squares, with both squares behind it empty."
+            ),
+            PositionError::OpponentKingAttacked(color) => write!(
+                f,
+                "{} king is in check, but it is {} to move.",
+                color_name(*color),
+                color_name(color.inv())
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+fn castling_rank(color: Color) -> Rank {
+    match color {
+        Color::White => Rank::R1,
+        Color::Black => Rank::R8,
+    }
+}
+
+/// Checks that the position can be used to start a game, explaining why it
+/// is illegal otherwise.
+///
+/// Some of these checks are made before converting to [`owlchess::Board`], as
+/// the conversion silently drops inconsistent castling rights and en passant
+/// squares, which would be confusing for the user.
+pub fn validate_position(position: &RawBoard) -> Result<owlchess::Board, PositionError> {
+    for color in [Color::White, Color::Black] {
+        let king = Cell::from_parts(color, Piece::King);
+        let kings_count = position.cells.iter().filter(|cell| **cell == king).count();
+        if kings_count == 0 {
+            return Err(PositionError::NoKing(color));
+        }
+        if kings_count > 1 {
+            return Err(PositionError::TooManyKings(color));
+        }
+
+        let rank = castling_rank(color);
+        let has_castling = position.castling.has(color, CastlingSide::King)
+            || position.castling.has(color, CastlingSide::Queen);
+        if has_castling && position.get2(File::E, rank) != king {
+            return Err(PositionError::CastlingWithoutKing(color));
+        }
+        let rook = Cell::from_parts(color, Piece::Rook);
+        for (side, file) in [
+            (CastlingSide::King, File::H),
+            (CastlingSide::Queen, File::A),
+        ] {
+            if position.castling.has(color, side) && position.get2(file, rank) != rook {
+                return Err(PositionError::CastlingWithoutRook(color, side));
+            }
+        }
+    }
+
+    if let Some(ep_source) = position.ep_source {
+        // Rank indices grow from the 8th rank down to the 1st one.
+        let direction = match position.side {
+            Color::White => -1,
+            Color::Black => 1,
+        };
+        let passed_square = ep_source.shift(0, direction);
+        let start_square = ep_source.shift(0, 2 * direction);
+        let is_valid = position.get(ep_source)
+            == Cell::from_parts(position.side.inv(), Piece::Pawn)
+            && passed_square.is_some_and(|coord| position.get(coord).is_free())
+            && start_square.is_some_and(|coord| position.get(coord).is_free());
+        if !is_valid {
+            return Err(PositionError::InvalidEnPassant(
+                position.ep_dest().unwrap_or(ep_source),
+            ));
+        }
+    }
+
+    owlchess::Board::try_from(position).map_err(|err| match err {
+        owlchess::board::ValidateError::InvalidEnpassant(coord) => {
+            PositionError::InvalidEnPassant(coord)
+        }
+        owlchess::board::ValidateError::TooManyPieces(color) => PositionError::TooManyPieces(color),
+        owlchess::board::ValidateError::NoKing(color) => PositionError::NoKing(color),
+        owlchess::board::ValidateError::TooManyKings(color) => PositionError::TooManyKings(color),
+        owlchess::board::ValidateError::InvalidPawn(coord) => PositionError::PawnOnBackRank(coord),
+        owlchess::board::ValidateError::OpponentKingAttacked => {
+            PositionError::OpponentKingAttacked(position.side.inv())
+        }
+    })
+}
+
+/// The file of the pawn which can be taken en passant, if any.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnPassantFile(Option<File>);
+
+impl EnPassantFile {
+    const ALL: [EnPassantFile; 9] = [
+        EnPassantFile(None),
+        EnPassantFile(Some(File::A)),
+        EnPassantFile(Some(File::B)),
+        EnPassantFile(Some(File::C)),
+        EnPassantFile(Some(File::D)),
+        EnPassantFile(Some(File::E)),
+        EnPassantFile(Some(File::F)),
+        EnPassantFile(Some(File::G)),
+        EnPassantFile(Some(File::H)),
+    ];
+}
+
+impl fmt::Display for EnPassantFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(file) => write!(f, "{file}"),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum EditorMessage {
+    UpdatePosition(String),
+    SetSide(Color),
+    SetCastling(Color, CastlingSide, bool),
+    SetEnPassant(EnPassantFile),
+    FenInputChanged(String),
+    LoadFen,
+    SetInitialPosition,
+    ClearBoard,
+}
+
+pub struct PositionEditor {
+    position: RawBoard,
+    fen_input: String,
+    fen_error: Option<String>,
+}
+
+impl PositionEditor {
+    pub fn new(fen: &str) -> Self {
+        let position = RawBoard::from_fen(fen).unwrap_or_else(|_| RawBoard::initial());
+        Self {
+            position,
+            fen_input: position.as_fen(),
+            fen_error: None,
+        }
+    }
+
+    pub fn validated_board(&self) -> Result<owlchess::Board, PositionError> {
+        validate_position(&self.position)
+    }
+
+    fn set_position(&mut self, position: RawBoard) {
+        self.position = position;
+        self.fen_input = position.as_fen();
+        self.fen_error = None;
+    }
+
+    pub fn update(&mut self, message: EditorMessage) {
+        match message {
+            EditorMessage::UpdatePosition(new_position) => {
+                if let Ok(position) = RawBoard::from_fen(&new_position) {
+                    self.set_position(position);
+                }
+            }
+            EditorMessage::SetSide(side) => {
+                let mut position = self.position;
+                if position.side != side {
+                    // The en passant pawn depends on the side to move.
+                    position.ep_source = None;
+                }
+                position.side = side;
+                self.set_position(position);
+            }
+            EditorMessage::SetCastling(color, side, enabled) => {
+                let mut position = self.position;
+                if enabled {
+                    position.castling.set(color, side);
+                } else {
+                    position.castling.unset(color, side);
+                }
+                self.set_position(position);
+            }
+            EditorMessage::SetEnPassant(EnPassantFile(file)) => {
+                let mut position = self.position;
+                let rank = match position.side {
+                    Color::White => Rank::R5,
+                    Color::Black => Rank::R4,
+                };
+                position.ep_source = file.map(|file| Coord::from_parts(file, rank));
+                self.set_position(position);
+            }
+            EditorMessage::FenInputChanged(fen) => self.fen_input = fen,
+            EditorMessage::LoadFen => match RawBoard::from_fen(self.fen_input.trim()) {
+                Ok(position) => self.set_position(position),
+                Err(err) => self.fen_error = Some(format!("Cannot read this FEN: {err}.")),
+            },
+            EditorMessage::SetInitialPosition => self.set_position(RawBoard::initial()),
+            EditorMessage::ClearBoard => {
+                let mut position = RawBoard::empty();
+                position.side = self.position.side;
+                self.set_position(position);
+            }
+        }
+    }
+
+    pub fn view(&self, reversed: bool) -> Element<'_, EditorMessage> {
+        let side = self.position.side;
+        let castling = self.position.castling;
+        let castling_checkbox = |label: &'static str, color: Color, side: CastlingSide| {
+            checkbox(label, castling.has(color, side))
+                .on_toggle(move |enabled| EditorMessage::SetCastling(color, side, enabled))
+        };
+        let en_passant = EnPassantFile(self.position.ep_source.map(|coord| coord.file()));
+
+        let status = match (&self.fen_error, self.validated_board()) {
+            (Some(fen_error), _) => fen_error.clone(),
+            (None, Ok(_)) => String::from("The position is legal."),
+            (None, Err(err)) => err.to_string(),
+        };
+
+        let controls = column![
+            text("Side to move"),
+            row![
+                radio("White", Color::White, Some(side), EditorMessage::SetSide),
+                radio("Black", Color::Black, Some(side), EditorMessage::SetSide),
+            ]
+            .spacing(10),
+            text("Castling rights"),
+            castling_checkbox("White O-O", Color::White, CastlingSide::King),
+            castling_checkbox("White O-O-O", Color::White, CastlingSide::Queen),
+            castling_checkbox("Black O-O", Color::Black, CastlingSide::King),
+            castling_checkbox("Black O-O-O", Color::Black, CastlingSide::Queen),
+            text("En passant file"),
+            pick_list(
+                EnPassantFile::ALL,
+                Some(en_passant),
+                EditorMessage::SetEnPassant
+            ),
+            row![
+                button("Initial position").on_press(EditorMessage::SetInitialPosition),
+                button("Clear board").on_press(EditorMessage::ClearBoard),
+            ]
+            .spacing(5),
+        ]
+        .spacing(8)
+        .width(220);
+
+        column![
+            row![
+                container(Chessboard::new(
+                    ChessboardOptionsBuilder::new()
+                        .set_reversed(reversed)
+                        .set_position(self.position.as_fen())
+                        .set_edit_mode(true)
+                        .build(),
+                    chessboard::MessageProducer {
+                        build_update_position: EditorMessage::UpdatePosition,
+                    }
+                ))
+                .center(Length::Fill),
+                controls,
+            ]
+            .spacing(10)
+            .height(Length::Fill),
+            row![
+                text_input("FEN", &self.fen_input)
+                    .on_input(EditorMessage::FenInputChanged)
+                    .on_submit(EditorMessage::LoadFen),
+                button("Load FEN").on_press(EditorMessage::LoadFen),
+            ]
+            .spacing(5),
+            text(status),
+        ]
+        .spacing(10)
+        .into()
+    }
+}
@@ -0,0 +1,276 @@
+//! The review of the game by the engine : its progress, then its summary.
+use std::collections::BTreeMap;
+
+use chessboard::Arrow;
+use iced::{
+    Color, Element, Length,
+    widget::{button, column, progress_bar, row, scrollable, text},
+};
+use owlchess::Color as Side;
+
+use crate::{
+    engine::analysis::{AnalysisInfo, san_line},
+    game::{
+        Annotation, Game,
+        review::{MoveClass, MoveReview, PositionEval, SideSummary, annotations, review_moves},
+    },
+    gui::eval_graph::EvalGraph,
+};
+
+/// The colors of the arrows showing the move played and the best move, at a reviewed position.
+const PLAYED_ARROW_COLOR: Color = Color::from_rgba(0.8, 0.1, 0.1, 0.7);
+const BEST_ARROW_COLOR: Color = Color::from_rgba(0.0, 0.5, 0.25, 0.8);
+
+#[derive(Debug, Clone)]
+pub enum ReviewMessage {
+    /// Shows the position after the given number of moves.
+    ShowPosition(usize),
+    Close,
+}
+
+/// A move worth a look, with its label.
+struct CriticalMoment {
+    index: usize,
+    label: String,
+}
+
+pub struct ReviewPanel {
+    /// The side to move in the start position.
+    start_side: Side,
+    /// The number of positions to evaluate, from the start position.
+    positions: usize,
+    /// The evaluations of the positions searched so far.
+    evals: Vec<PositionEval>,
+    /// What the engine reports on the position being searched.
+    current: AnalysisInfo,
+    /// The reviews of the moves, once every position is evaluated.
+    reviews: Vec<MoveReview>,
+    summaries: [SideSummary; 2],
+    critical_moments: Vec<CriticalMoment>,
+}
+
+impl ReviewPanel {
+    pub fn new(game: &Game) -> Self {
+        Self {
+            start_side: game.start_position().side(),
+            positions: game.moves().len() + 1,
+            evals: Vec::new(),
+            current: AnalysisInfo::default(),
+            reviews: Vec::new(),
+            summaries: Default::default(),
+            critical_moments: Vec::new(),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.evals.len() < self.positions
+    }
+
+    /// The number of moves leading to the next position to evaluate.
+    pub fn next_position(&self) -> Option<usize> {
+        self.is_running().then_some(self.evals.len())
+    }
+
+    pub fn add_eval(&mut self, eval: PositionEval) {
+        self.evals.push(eval);
+        self.current = AnalysisInfo::default();
+    }
+
+    pub fn update(&mut self, info: AnalysisInfo) {
+        self.current.merge(info);
+    }
+
+    /// Evaluates the searched position with the last report of the engine.
+    /// Returns false when the engine has not given its evaluation.
+    pub fn finish_search(&mut self, best_move: String) -> bool {
+        let Some(score) = self.current.score else {
+            return false;
+        };
+        self.add_eval(PositionEval {
+            score,
+            best_move: Some(best_move),
+        });
+        true
+    }
+
+    /// Reviews the moves once every position is evaluated, and gives back their annotations.
+    pub fn complete(&mut self, game: &Game) -> BTreeMap<usize, Annotation> {
+        self.reviews = review_moves(game, &self.evals);
+        self.summaries = [
+            SideSummary::new(&self.reviews, Side::White),
+            SideSummary::new(&self.reviews, Side::Black),
+        ];
+        self.critical_moments = self
+            .reviews
+            .iter()
+            .enumerate()
+            .filter(|(_, review)| review.class >= MoveClass::Inaccuracy)
+            .map(|(index, review)| {
+                let position = game.position_at(index);
+                let mut label = format!(
+                    "{}{} ({:+.2} to {:+.2})",
+                    san_line(&game.continuation(index), &[review.played.to_string()]),
+                    review.class.symbol(),
+                    f64::from(review.eval_before) / 100.0,
+                    f64::from(review.eval_after) / 100.0,
+                );
+                if let Some(best_move) = review.best_move {
+                    label.push_str(&format!(", best {}", best_move.san(&position)));
+                }
+                CriticalMoment { index, label }
+            })
+            .collect();
+        annotations(game, &self.reviews)
+    }
+
+    /// The move played and the best move, in the position before the move of the given index.
+    pub fn arrows(&self, index: usize) -> Vec<Arrow> {
+        let Some(review) = self.reviews.get(index) else {
+            return Vec::new();
+        };
+        let Some(best_move) = review.best_move else {
+            return Vec::new();
+        };
+        vec![
+            Arrow {
+                from: review.played.src(),
+                to: review.played.dst(),
+                color: PLAYED_ARROW_COLOR,
+            },
+            Arrow {
+                from: best_move.src(),
+                to: best_move.dst(),
+                color: BEST_ARROW_COLOR,
+            },
+        ]
+    }
+
+    /// The evaluations of the positions evaluated so far, from the point of view of White.
+    fn eval_curve(&self) -> Vec<i32> {
+        self.evals
+            .iter()
+            .enumerate()
+            .map(|(plies, eval)| {
+                let side_to_move = if plies % 2 == 0 {
+                    self.start_side
+                } else {
+                    self.start_side.inv()
+                };
+                eval.white_centipawns(side_to_move)
+            })
+            .collect()
+    }
+
+    fn eval_graph(&self, shown_plies: usize) -> Element<'_, ReviewMessage> {
+        let blunders = self
+            .reviews
+            .iter()
+            .enumerate()
+            .filter(|(_, review)| review.class == MoveClass::Blunder)
+            .map(|(index, _)| index + 1)
+            .collect();
+        EvalGraph::new(
+            self.eval_curve(),
+            self.positions,
+            blunders,
+            shown_plies,
+            ReviewMessage::ShowPosition,
+        )
+        .view()
+    }
+
+    fn summary_view(side: &str, summary: &SideSummary) -> Element<'static, ReviewMessage> {
+        column![
+            text(format!("{side} : {:.0}% accuracy", summary.accuracy)).size(16),
+            text(format!(
+                "Average loss {:.0} centipawns",
+                summary.average_loss
+            )),
+            text(format!(
+                "{} inaccuracies, {} mistakes, {} blunders",
+                summary.inaccuracies, summary.mistakes, summary.blunders
+            )),
+        ]
+        .into()
+    }
+
+    /// The position after `shown_plies` moves is shown on the board.
+    pub fn view(&self, engine_name: &str, shown_plies: usize) -> Element<'_, ReviewMessage> {
+        let panel = column![row![
+            text(format!("Review by {engine_name}"))
+                .size(20)
+                .width(Length::Fill),
+            button(text("Close")).on_press(ReviewMessage::Close),
+        ],]
+        .spacing(8);
+        if self.is_running() {
+            return panel
+                .push(self.eval_graph(shown_plies))
+                .push(text(format!(
+                    "Evaluating the position {} of {}...",
+                    self.evals.len() + 1,
+                    self.positions
+                )))
+                .push(progress_bar(
+                    0.0..=self.positions as f32,
+                    self.evals.len() as f32,
+                ))
+                .into();
+        }
+
+        let moments = self
+            .critical_moments
+            .iter()
+            .fold(column![], |moments, moment| {
+                moments.push(
+                    button(text(&moment.label))
+                        .style(button::text)
+                        .width(Length::Fill)
+                        .on_press(ReviewMessage::ShowPosition(moment.index)),
+                )
+            });
+        panel
+            .push(self.eval_graph(shown_plies))
+            .push(Self::summary_view("White", &self.summaries[0]))
+            .push(Self::summary_view("Black", &self.summaries[1]))
+            .push(text("Critical moments").size(16))
+            .push_maybe(
+                self.critical_moments
+                    .is_empty()
+                    .then(|| text("No inaccuracy found.")),
+            )
+            .push(scrollable(moments))
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use owlchess::Board;
+
+    use super::*;
+    use crate::{engine::analysis::Score, game::review::MAX_CENTIPAWNS};
+
+    #[test]
+    fn draws_the_evaluations_for_white() {
+        let start =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        let mut game = Game::new(start);
+        assert!(game.play_line(0, &[String::from("e7e5")]));
+        let mut panel = ReviewPanel::new(&game);
+        assert_eq!(panel.next_position(), Some(0));
+
+        panel.update(AnalysisInfo {
+            score: Some(Score::Centipawns(-40)),
+            ..Default::default()
+        });
+        assert!(panel.finish_search(String::from("c7c5")));
+        assert!(!panel.finish_search(String::from("g1f3")));
+        panel.add_eval(PositionEval {
+            score: Score::Mate(2),
+            best_move: Some(String::from("g1f3")),
+        });
+        assert_eq!(panel.next_position(), None);
+        assert_eq!(panel.eval_curve(), [40, MAX_CENTIPAWNS]);
+    }
+}
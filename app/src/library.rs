@@ -0,0 +1,291 @@
+//! The finished games, kept in a PGN archive along with an index of their headers, so that they
+//! can be listed and searched without reading the whole archive.
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use owlchess::{Color, GameStatus};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{
+        Game, eco,
+        pgn::{self, PgnError},
+    },
+    settings::{Settings, SettingsError},
+};
+
+const ARCHIVE_FILE: &str = "library.pgn";
+const INDEX_FILE: &str = "library.toml";
+
+/// The moves naming the opening of a game out of the opening table.
+const OPENING_PLIES: usize = 6;
+
+#[derive(Debug)]
+pub enum LibraryError {
+    Storage(SettingsError),
+    /// The game stored at the entry cannot be read back.
+    InvalidGame(PgnError),
+}
+
+impl fmt::Display for LibraryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LibraryError::Storage(err) => write!(f, "{err}"),
+            LibraryError::InvalidGame(err) => write!(f, "invalid game in the library: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for LibraryError {}
+
+impl From<SettingsError> for LibraryError {
+    fn from(err: SettingsError) -> Self {
+        LibraryError::Storage(err)
+    }
+}
+
+impl From<io::Error> for LibraryError {
+    fn from(err: io::Error) -> Self {
+        LibraryError::Storage(err.into())
+    }
+}
+
+/// A game of the library, as listed in the browser.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    /// In the PGN format (YYYY.MM.DD).
+    pub date: String,
+    pub white: String,
+    pub black: String,
+    /// The name of the engine the game was played against, None for a game played on the board.
+    #[serde(default)]
+    pub engine: Option<String>,
+    pub result: String,
+    pub opening: String,
+    pub plies: usize,
+    /// Where the PGN of the game starts in the archive, in bytes.
+    offset: u64,
+    length: u64,
+}
+
+impl LibraryEntry {
+    /// The number of full moves.
+    pub fn moves(&self) -> usize {
+        self.plies.div_ceil(2)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Library {
+    /// The games in the order they were played.
+    #[serde(default)]
+    games: Vec<LibraryEntry>,
+    /// The directory of the archive and of the index, the settings directory when None.
+    #[serde(skip)]
+    directory: Option<PathBuf>,
+}
+
+/// The opening of the game : its `Opening` header, the opening recognized from its moves, or
+/// else its first moves.
+fn opening_of(game: &Game) -> String {
+    if let Some(opening) = game.header(eco::OPENING_HEADER) {
+        return opening.to_string();
+    }
+    if let Some(opening) = game.opening() {
+        return opening.to_string();
+    }
+    let mut words = Vec::new();
+    for (ply, (board, played)) in game.walk().take(OPENING_PLIES).enumerate() {
+        match board.side() {
+            Color::White => words.push(format!("{}.", board.raw().move_number)),
+            Color::Black if ply == 0 => words.push(format!("{}...", board.raw().move_number)),
+            Color::Black => {}
+        }
+        words.push(played.san(board));
+    }
+    words.join(" ")
+}
+
+impl Library {
+    fn file_path(&self, file: &str) -> Result<PathBuf, SettingsError> {
+        match &self.directory {
+            Some(directory) => Ok(directory.join(file)),
+            None => Ok(Settings::directory()?.join(file)),
+        }
+    }
+
+    /// Loads the index of the library, empty if no game was stored yet.
+    pub fn load() -> Result<Self, SettingsError> {
+        Self::load_from(&Settings::directory()?)
+    }
+
+    /// Loads the index of the library kept in the directory.
+    fn load_from(directory: &Path) -> Result<Self, SettingsError> {
+        let mut library = match fs::read_to_string(directory.join(INDEX_FILE)) {
+            Ok(content) => toml::from_str(&content).map_err(SettingsError::Read)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(err) => return Err(err.into()),
+        };
+        library.directory = Some(directory.to_path_buf());
+        Ok(library)
+    }
+
+    fn save_index(&self) -> Result<(), SettingsError> {
+        let file_path = self.file_path(INDEX_FILE)?;
+        let content = toml::to_string_pretty(self).map_err(SettingsError::Write)?;
+        let temporary_path = file_path.with_extension("tmp");
+        fs::write(&temporary_path, content)?;
+        fs::rename(temporary_path, file_path)?;
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[LibraryEntry] {
+        &self.games
+    }
+
+    /// Appends the game to the archive, and records it in the index. Returns false when the
+    /// game was already stored, as when a game of the library is opened and ended again.
+    pub fn add(&mut self, game: &Game, engine: Option<String>) -> Result<bool, SettingsError> {
+        let game_pgn = pgn::write(game);
+        let header = |name: &str| game.header(name).unwrap_or("?").to_string();
+        let entry = LibraryEntry {
+            date: header("Date"),
+            white: header("White"),
+            black: header("Black"),
+            engine,
+            result: GameStatus::from(game.outcome()).to_string(),
+            opening: opening_of(game),
+            plies: game.moves().len(),
+            offset: 0,
+            length: game_pgn.len() as u64,
+        };
+        if self.contains(&entry, game) {
+            return Ok(false);
+        }
+
+        let archive_path = self.file_path(ARCHIVE_FILE)?;
+        if let Some(directory) = archive_path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let mut archive = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(archive_path)?;
+        let offset = archive.seek(SeekFrom::End(0))?;
+        // The games are separated by an empty line.
+        archive.write_all(format!("{game_pgn}\n").as_bytes())?;
+
+        self.games.push(LibraryEntry { offset, ..entry });
+        self.save_index()?;
+        Ok(true)
+    }
+
+    /// Whether the game is stored already, with the same start position and moves. Only the
+    /// entries with the same players, date, result and length are read back.
+    fn contains(&self, entry: &LibraryEntry, game: &Game) -> bool {
+        self.games.iter().any(|stored| {
+            (
+                &stored.date,
+                &stored.white,
+                &stored.black,
+                &stored.result,
+                stored.plies,
+            ) == (
+                &entry.date,
+                &entry.white,
+                &entry.black,
+                &entry.result,
+                entry.plies,
+            ) && self.read_game(stored).is_ok_and(|stored_game| {
+                stored_game.start_fen() == game.start_fen() && stored_game.moves() == game.moves()
+            })
+        })
+    }
+
+    /// Reads the game of the entry back from the archive.
+    pub fn read_game(&self, entry: &LibraryEntry) -> Result<Game, LibraryError> {
+        let mut archive = fs::File::open(self.file_path(ARCHIVE_FILE)?)?;
+        archive.seek(SeekFrom::Start(entry.offset))?;
+        let mut game_pgn = String::new();
+        archive.take(entry.length).read_to_string(&mut game_pgn)?;
+        pgn::read(&game_pgn).map_err(LibraryError::InvalidGame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use owlchess::{Board, Outcome, WinReason};
+
+    use super::*;
+
+    fn finished_game(moves: &[&str], white: &str) -> Game {
+        let mut game = Game::new(Board::initial());
+        let moves: Vec<String> = moves.iter().map(|uci_move| uci_move.to_string()).collect();
+        assert!(game.play_line(0, &moves));
+        game.set_header("White", white.to_string());
+        game.set_header("Black", String::from("Stockfish"));
+        game.set_header("Date", String::from("2024.05.01"));
+        game.set_outcome(Outcome::Win {
+            side: Color::White,
+            reason: WinReason::Resign,
+        });
+        game
+    }
+
+    #[test]
+    fn appends_the_games_and_keeps_the_index_in_sync() {
+        let directory = std::env::temp_dir().join(format!("library_{}", std::process::id()));
+        let mut library = Library::load_from(&directory).unwrap();
+        assert!(library.entries().is_empty());
+
+        let first = finished_game(&["e2e4", "c7c5", "g1f3"], "Alice");
+        let second = finished_game(&["d2d4", "d7d5"], "Bob");
+        assert!(
+            library
+                .add(&first, Some(String::from("Stockfish")))
+                .unwrap()
+        );
+        assert!(library.add(&second, None).unwrap());
+        let archive = fs::read_to_string(directory.join(ARCHIVE_FILE)).unwrap();
+        assert_eq!(
+            archive,
+            format!("{}\n{}\n", pgn::write(&first), pgn::write(&second))
+        );
+
+        // The index read back points at both games of the archive.
+        let library = Library::load_from(&directory).unwrap();
+        let entries = library.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].white, "Alice");
+        assert_eq!(entries[0].engine.as_deref(), Some("Stockfish"));
+        assert_eq!(entries[0].result, "1-0");
+        assert_eq!(entries[0].opening, "B27 Sicilian Defense");
+        assert_eq!(entries[0].moves(), 2);
+        assert_eq!(entries[1].opening, "D00 Queen's Pawn Game");
+        let read_back = library.read_game(&entries[1]).unwrap();
+        assert_eq!(read_back.header("White"), Some("Bob"));
+        assert_eq!(read_back.moves(), second.moves());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn stores_a_game_once() {
+        let directory = std::env::temp_dir().join(format!("library_once_{}", std::process::id()));
+        let mut library = Library::load_from(&directory).unwrap();
+        let game = finished_game(&["e2e4", "e7e5"], "Alice");
+        assert!(library.add(&game, None).unwrap());
+        let read_back = library.read_game(&library.entries()[0]).unwrap();
+        assert!(!library.add(&read_back, None).unwrap());
+
+        // The same players with other moves, or the same moves by other players, are stored.
+        for (moves, white) in [(["e2e4", "c7c5"], "Alice"), (["e2e4", "e7e5"], "Bob")] {
+            assert!(library.add(&finished_game(&moves, white), None).unwrap());
+        }
+        assert_eq!(Library::load_from(&directory).unwrap().entries().len(), 3);
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
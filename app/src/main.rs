@@ -0,0 +1,1886 @@
+#![windows_subsystem = "windows"]
+
+mod engine;
+mod game;
+mod gui;
+mod library;
+mod recovery;
+mod settings;
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use chessboard::{Arrow, Chessboard, ChessboardOptionsBuilder};
+use iced::{
+    Background, Color, Length, Subscription, Task,
+    alignment::{Horizontal, Vertical},
+    widget::{Svg, button, column, container, row, svg::Handle, text},
+};
+
+use crate::{
+    engine::{
+        Engine, EngineCommand, EngineError, EngineEvent, EngineIdentity, HINT_MOVE_TIME, Protocol,
+        REVIEW_MOVE_TIME, SearchLimit, SearchUpdate,
+        analysis::{lines_command, max_lines},
+        book::OpeningBook,
+        conduct::Conduct,
+        default_name,
+        strength::{Strength, StrengthControl},
+        syzygy::{PositionProbe, Tablebases},
+        tournament::Tournament,
+    },
+    game::{Game, GameMove, clocks::Clocks, explorer::OpeningExplorer, pgn, review::PositionEval},
+    gui::{
+        analysis::{AnalysisMessage, AnalysisPanel},
+        engine_match::{
+            MatchDialog, MatchDialogMessage, MatchMessage, clock_text, match_view, player_text,
+        },
+        engine_options::{EngineOptionsEditor, EngineOptionsMessage},
+        engines_manager::{EnginesManager, EnginesMessage},
+        eval_bar::EvalBar,
+        explorer::{ExplorerMessage, ExplorerPanel},
+        library::{LibraryBrowser, LibraryMessage},
+        move_list::MoveList,
+        new_game::{EngineStatus, NewGameDialog, NewGameMessage},
+        position_editor::{EditorMessage, PositionEditor},
+        review::{ReviewMessage, ReviewPanel},
+    },
+    library::Library,
+    recovery::{AUTOSAVE_INTERVAL, Recovery, SavedOpponent},
+    settings::{BookSettings, Settings},
+};
+
+static SWAP_VERT_BYTES: &[u8] = include_bytes!("swap-vert.svg");
+static SWAP_VERT_HANDLE: LazyLock<Handle> = LazyLock::new(|| Handle::from_memory(SWAP_VERT_BYTES));
+
+/// The color of the arrow showing the destination of the hinted piece.
+const HINT_ARROW_COLOR: Color = Color::from_rgba(0.1, 0.3, 0.8, 0.8);
+
+fn main() -> iced::Result {
+    iced::application("Chess against engine", App::update, App::view)
+        .subscription(App::subscription)
+        .run()
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    ToggleBoardOrientation,
+    UpdatePosition(String),
+    /// The king dropped onto a rook of its side, which is castling in Chess960.
+    Castle(owlchess::Coord, owlchess::Coord),
+    /// Shows the position after the given number of moves.
+    ShowPly(usize),
+    ToggleAnalysis,
+    Hint,
+    OfferDraw,
+    Resign,
+    /// Resumes the game saved by the last session.
+    ResumeGame,
+    DiscardRecovery,
+    /// The tablebases probed for the engine move, with the hash of the position.
+    TablebaseMove(u64, Result<PositionProbe, String>),
+    /// The tablebases probed for the analysis, with the hash of the position.
+    TablebaseAnalysis(u64, Result<PositionProbe, String>),
+    StartReview,
+    Review(ReviewMessage),
+    Analysis(AnalysisMessage),
+    EvalBarFrame(Instant),
+    /// Each second while the clocks run, so that they are shown and saved.
+    ClockTick(Instant),
+    OpenPositionEditor,
+    Editor(EditorMessage),
+    StartGameFromEditor,
+    CancelPositionEditor,
+    CopyFen,
+    CopyPgn,
+    PasteRequested,
+    ClipboardRead(Option<String>),
+    Paste(String),
+    OpenNewGameDialog,
+    NewGame(NewGameMessage),
+    StartNewGame,
+    CancelNewGame,
+    Engine(EngineEvent),
+    OpenEngineOptions,
+    EngineOptions(EngineOptionsMessage),
+    SaveEngineOptions,
+    CancelEngineOptions,
+    OpenEnginesManager,
+    EnginesManager(EnginesMessage),
+    EngineProbed(PathBuf, Protocol, Result<EngineIdentity, EngineError>),
+    SaveEngines,
+    CancelEnginesManager,
+    OpenMatchDialog,
+    MatchDialog(MatchDialogMessage),
+    StartMatch,
+    CancelMatchDialog,
+    Match(MatchMessage),
+    ToggleExplorer,
+    Explorer(ExplorerMessage),
+    OpenLibrary,
+    Library(LibraryMessage),
+    CloseLibrary,
+    /// An event of the first or the second engine of the match.
+    MatchEngine(usize, EngineEvent),
+    MatchFrame(Instant),
+}
+
+/// The engine side in the current game.
+struct Opponent {
+    side: owlchess::Color,
+    strength: Strength,
+    conduct: Conduct,
+    /// The number of moves played when the player last offered a draw : one offer per move.
+    draw_offered_at: Option<usize>,
+    /// The values of the options changed by the user, set before the game.
+    options: BTreeMap<String, String>,
+}
+
+/// A move suggested to the player, shown piece first, then with its destination.
+struct Hint {
+    /// None while the engine searches.
+    suggested_move: Option<GameMove>,
+    show_destination: bool,
+}
+
+impl Hint {
+    fn describe(&self, position: &owlchess::Board) -> Option<String> {
+        let suggested_move = self.suggested_move?;
+        if self.show_destination {
+            return Some(format!("Hint: play {}.", suggested_move.san(position)));
+        }
+        let piece = match position.get(suggested_move.src()).piece()? {
+            owlchess::Piece::Pawn => "pawn",
+            owlchess::Piece::King => "king",
+            owlchess::Piece::Knight => "knight",
+            owlchess::Piece::Bishop => "bishop",
+            owlchess::Piece::Rook => "rook",
+            owlchess::Piece::Queen => "queen",
+        };
+        Some(format!(
+            "Hint: move the {piece} on {}.",
+            suggested_move.src()
+        ))
+    }
+}
+
+struct App {
+    board_reversed: bool,
+    game: Game,
+    position_editor: Option<PositionEditor>,
+    new_game_dialog: Option<NewGameDialog>,
+    engine_options_editor: Option<EngineOptionsEditor>,
+    engines_manager: Option<EnginesManager>,
+    engine: Option<Engine>,
+    settings: Settings,
+    /// None when both sides are played on the board.
+    opponent: Option<Opponent>,
+    /// The number of moves leading to the position shown on the board, while browsing the
+    /// history. None shows the current position.
+    history_ply: Option<usize>,
+    /// The moves of the game, written again whenever it changes.
+    move_list: MoveList,
+    /// Some while the engine analyses the position shown on the board.
+    analysis: Option<AnalysisPanel>,
+    eval_bar: EvalBar,
+    /// The hint asked by the player for the current move.
+    hint: Option<Hint>,
+    /// Some while the engine reviews the game, and once it is reviewed.
+    review: Option<ReviewPanel>,
+    /// The opening book of the engine opponent, as set in the settings.
+    book: Option<OpeningBook>,
+    /// The Syzygy tablebases, as set in the engines manager.
+    tablebases: Option<Tablebases>,
+    match_dialog: Option<MatchDialog>,
+    /// Some while two engines play a match, and once it is over.
+    engine_match: Option<Tournament>,
+    /// The PGN collection indexed by the opening explorer, once loaded.
+    explorer: Option<OpeningExplorer>,
+    /// Some while the opening explorer is shown beside the board.
+    explorer_panel: Option<ExplorerPanel>,
+    /// The finished games, stored as they end.
+    library: Library,
+    library_browser: Option<LibraryBrowser>,
+    /// The unfinished game of the last session, until the player resumes or discards it.
+    recovery: Option<Recovery>,
+    /// The engine of the resumed game, which takes its side once it is ready.
+    pending_opponent: Option<SavedOpponent>,
+    /// The time spent by each side, running while the engine plays one of them.
+    clocks: Clocks,
+    last_autosave: Instant,
+    /// Feedback about the last operation.
+    status_message: Option<String>,
+    /// The tasks started while handling the last message, such as the probes of the tablebases.
+    tasks: Vec<Task<Message>>,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        let (settings, status_message) = match Settings::load() {
+            Ok(settings) => (settings, None),
+            Err(err) => (
+                Settings::default(),
+                Some(format!("Cannot load the settings: {err}.")),
+            ),
+        };
+        let (book, book_error) = match App::open_book(&settings) {
+            Ok(book) => (book, None),
+            Err(err) => (None, Some(err)),
+        };
+        let (tablebases, tablebases_error) = match App::open_tablebases(&settings) {
+            Ok(tablebases) => (tablebases, None),
+            Err(err) => (None, Some(err)),
+        };
+        let (library, library_error) = match Library::load() {
+            Ok(library) => (library, None),
+            Err(err) => (
+                Library::default(),
+                Some(format!("Cannot read the game library: {err}.")),
+            ),
+        };
+        let (recovery, recovery_error) = match Recovery::load() {
+            Ok(recovery) => (recovery, None),
+            Err(err) => (None, Some(format!("Cannot read the saved game: {err}."))),
+        };
+        Self {
+            board_reversed: false,
+            game: Game::new(owlchess::Board::initial()),
+            position_editor: None,
+            new_game_dialog: None,
+            engine_options_editor: None,
+            engines_manager: None,
+            engine: None,
+            settings,
+            opponent: None,
+            history_ply: None,
+            move_list: MoveList::new(&Game::new(owlchess::Board::initial())),
+            analysis: None,
+            eval_bar: EvalBar::default(),
+            hint: None,
+            review: None,
+            book,
+            tablebases,
+            match_dialog: None,
+            engine_match: None,
+            explorer: None,
+            explorer_panel: None,
+            library,
+            library_browser: None,
+            recovery,
+            pending_opponent: None,
+            clocks: Clocks::default(),
+            last_autosave: Instant::now(),
+            status_message: status_message
+                .or(book_error)
+                .or(tablebases_error)
+                .or(library_error)
+                .or(recovery_error),
+            tasks: Vec::new(),
+        }
+    }
+}
+
+impl App {
+    fn update(&mut self, message: Message) -> Task<Message> {
+        let task = self.handle(message);
+        Task::batch(std::iter::once(task).chain(self.tasks.drain(..)))
+    }
+
+    fn handle(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::ToggleBoardOrientation => self.board_reversed = !self.board_reversed,
+            Message::UpdatePosition(new_position) => {
+                self.play_from_board(|game, plies| game.play_to_position_from(plies, &new_position))
+            }
+            Message::Castle(king, rook) => {
+                self.play_from_board(|game, plies| game.castle(plies, king, rook))
+            }
+            Message::ShowPly(plies) => {
+                self.history_ply = (plies < self.game.moves().len()).then_some(plies);
+                self.restart_analysis();
+                return self.move_list.scroll_to(plies);
+            }
+            Message::ToggleAnalysis => {
+                if self.analysis.is_some() {
+                    self.stop_analysis();
+                } else {
+                    self.start_analysis();
+                }
+            }
+            Message::Hint => self.request_hint(),
+            Message::OfferDraw => self.offer_draw(),
+            Message::Resign => self.resign(),
+            Message::ResumeGame => self.resume_game(),
+            Message::DiscardRecovery => {
+                self.recovery = None;
+                if let Err(err) = Recovery::clear() {
+                    self.status_message = Some(format!("Cannot discard the saved game: {err}."));
+                }
+            }
+            Message::TablebaseMove(hash, probe) => self.play_tablebase_move(hash, probe),
+            Message::TablebaseAnalysis(hash, probe) => {
+                let shown_hash = self.shown_position().zobrist_hash();
+                if let Some(analysis) = self.analysis.as_mut()
+                    && shown_hash == hash
+                {
+                    analysis.set_tablebase(probe);
+                }
+            }
+            Message::StartReview => self.start_review(),
+            Message::Review(ReviewMessage::ShowPosition(plies)) => {
+                self.history_ply = (plies < self.game.moves().len()).then_some(plies);
+                return self.move_list.scroll_to(plies);
+            }
+            Message::Review(ReviewMessage::Close) => self.stop_review(),
+            Message::Analysis(AnalysisMessage::SetLineCount(line_count)) => {
+                if let Some(analysis) = self.analysis.as_mut() {
+                    analysis.set_line_count(line_count);
+                    self.restart_analysis();
+                }
+            }
+            Message::Analysis(AnalysisMessage::PlayLine(index)) => {
+                let line_moves = self
+                    .analysis
+                    .as_ref()
+                    .and_then(|analysis| analysis.line_moves(index))
+                    .map(<[String]>::to_vec)
+                    .unwrap_or_default();
+                // The engine opponent would have to answer moves it did not choose.
+                if self.opponent.is_none() && self.game.play_line(self.shown_plies(), &line_moves) {
+                    self.history_ply = None;
+                    self.after_move();
+                }
+            }
+            Message::EvalBarFrame(now) => self.eval_bar.animate(now),
+            Message::ClockTick(now) => {
+                if now.saturating_duration_since(self.last_autosave) >= AUTOSAVE_INTERVAL {
+                    self.autosave();
+                }
+            }
+            Message::OpenPositionEditor => {
+                self.stop_analysis();
+                self.position_editor = Some(PositionEditor::new(&self.game.position().as_fen()))
+            }
+            Message::Editor(editor_message) => {
+                if let Some(position_editor) = self.position_editor.as_mut() {
+                    position_editor.update(editor_message);
+                }
+            }
+            Message::StartGameFromEditor => {
+                if let Some(position_editor) = self.position_editor.as_ref()
+                    && let Ok(board) = position_editor.validated_board()
+                {
+                    self.new_game_dialog = Some(NewGameDialog::new(
+                        board,
+                        self.engine_path(),
+                        &self.settings.book,
+                    ));
+                    self.position_editor = None;
+                }
+            }
+            Message::CancelPositionEditor => self.position_editor = None,
+            Message::CopyFen => {
+                self.status_message = Some(String::from("FEN copied to the clipboard."));
+                return iced::clipboard::write(self.game.position().as_fen());
+            }
+            Message::CopyPgn => {
+                self.status_message = Some(String::from("PGN copied to the clipboard."));
+                return iced::clipboard::write(pgn::write(&self.game));
+            }
+            Message::PasteRequested => return iced::clipboard::read().map(Message::ClipboardRead),
+            Message::ClipboardRead(Some(text)) | Message::Paste(text) => {
+                self.load_pasted_text(&text)
+            }
+            Message::ClipboardRead(None) => {
+                self.status_message = Some(String::from("The clipboard does not contain text."))
+            }
+            Message::OpenNewGameDialog => {
+                self.stop_analysis();
+                self.new_game_dialog = Some(NewGameDialog::new(
+                    owlchess::Board::initial(),
+                    self.engine_path(),
+                    &self.settings.book,
+                ))
+            }
+            Message::NewGame(NewGameMessage::SelectEngine(choice)) => {
+                self.load_engine(choice.path.clone());
+                if let Some(new_game_dialog) = self.new_game_dialog.as_mut() {
+                    new_game_dialog.update(NewGameMessage::SelectEngine(choice));
+                }
+            }
+            Message::NewGame(new_game_message) => {
+                if let Some(new_game_dialog) = self.new_game_dialog.as_mut() {
+                    new_game_dialog.update(new_game_message);
+                }
+            }
+            Message::StartNewGame => {
+                if let Some(new_game_dialog) = self.new_game_dialog.take() {
+                    self.start_new_game(new_game_dialog);
+                }
+            }
+            Message::CancelNewGame => self.new_game_dialog = None,
+            Message::Engine(event) => {
+                let ready = matches!(event, EngineEvent::Ready(_));
+                if let Some(engine) = self.engine.as_mut() {
+                    match engine.handle_event(event) {
+                        Some(SearchUpdate::BestMove(best_move))
+                            if self
+                                .hint
+                                .as_ref()
+                                .is_some_and(|hint| hint.suggested_move.is_none()) =>
+                        {
+                            self.show_hint(&best_move)
+                        }
+                        Some(SearchUpdate::BestMove(best_move))
+                            if self.review.as_ref().is_some_and(ReviewPanel::is_running) =>
+                        {
+                            self.review_search_done(best_move)
+                        }
+                        Some(SearchUpdate::BestMove(best_move)) => {
+                            self.play_engine_move(&best_move)
+                        }
+                        Some(SearchUpdate::Info(info)) => {
+                            let engine_turn = self.is_engine_turn();
+                            if engine_turn
+                                && let Some(opponent) = self.opponent.as_mut()
+                                && let Some(score) = info.score
+                            {
+                                opponent.conduct.update(score);
+                            }
+                            if let Some(analysis) = self.analysis.as_mut() {
+                                analysis.update(info);
+                                if let Some(score) = analysis.best_score() {
+                                    self.eval_bar.set_score(score);
+                                }
+                            } else if let Some(review) = self.review.as_mut() {
+                                review.update(info);
+                            }
+                        }
+                        None => {}
+                    }
+                }
+                if ready {
+                    if let Some(engine) = &self.engine
+                        && let Some(path) = &self.settings.syzygy_path
+                    {
+                        engine.set_tablebase_path(path);
+                    }
+                    self.start_pending_opponent();
+                    self.restart_analysis();
+                    self.continue_review();
+                }
+            }
+            Message::OpenEngineOptions => {
+                if let Some(engine) = &self.engine
+                    && let Some(identity) = engine.identity()
+                {
+                    let saved_values = self
+                        .settings
+                        .engine(engine.path())
+                        .map(|engine_settings| engine_settings.options.clone())
+                        .unwrap_or_default();
+                    self.engine_options_editor =
+                        Some(EngineOptionsEditor::new(identity, &saved_values));
+                }
+            }
+            Message::EngineOptions(EngineOptionsMessage::PressButton(name)) => {
+                if let Some(engine) = &self.engine {
+                    engine.send(EngineCommand::SetOption { name, value: None });
+                }
+            }
+            Message::EngineOptions(options_message) => {
+                if let Some(engine_options_editor) = self.engine_options_editor.as_mut() {
+                    engine_options_editor.update(options_message);
+                }
+            }
+            Message::SaveEngineOptions => {
+                if let Some(engine_options_editor) = self.engine_options_editor.take()
+                    && let Some(engine) = &self.engine
+                    && let Some(engine_settings) = self.settings.engine_mut(engine.path())
+                {
+                    engine_settings.options = engine_options_editor.changed_values();
+                    self.save_settings();
+                }
+            }
+            Message::CancelEngineOptions => self.engine_options_editor = None,
+            Message::OpenEnginesManager => {
+                self.engines_manager = Some(EnginesManager::new(
+                    self.settings.engines.clone(),
+                    self.settings.syzygy_path.as_deref(),
+                ))
+            }
+            Message::EnginesManager(EnginesMessage::AddEngine) => {
+                if let Some(engines_manager) = self.engines_manager.as_mut()
+                    && let Some((path, protocol)) = engines_manager.engine_to_probe()
+                {
+                    return Task::perform(engine::probe(path.clone(), protocol), move |result| {
+                        Message::EngineProbed(path.clone(), protocol, result)
+                    });
+                }
+            }
+            Message::EnginesManager(engines_message) => {
+                if let Some(engines_manager) = self.engines_manager.as_mut() {
+                    engines_manager.update(engines_message);
+                }
+            }
+            Message::EngineProbed(path, protocol, result) => {
+                if let Some(engines_manager) = self.engines_manager.as_mut() {
+                    engines_manager.probed(path, protocol, result);
+                }
+            }
+            Message::SaveEngines => {
+                if let Some(engines_manager) = self.engines_manager.take() {
+                    let syzygy_path = engines_manager.syzygy_path();
+                    self.settings.engines = engines_manager.into_engines();
+                    self.save_settings();
+                    if syzygy_path != self.settings.syzygy_path {
+                        self.set_syzygy_path(syzygy_path);
+                    }
+                }
+            }
+            Message::CancelEnginesManager => self.engines_manager = None,
+            Message::ToggleExplorer => {
+                if self.explorer_panel.take().is_none() {
+                    self.explorer_panel =
+                        Some(ExplorerPanel::new(self.settings.explorer_path.as_deref()));
+                    if self.explorer.is_none() {
+                        self.load_explorer();
+                    }
+                }
+            }
+            Message::Explorer(ExplorerMessage::Load) => self.load_explorer(),
+            Message::Explorer(ExplorerMessage::PlayMove(uci_move)) => {
+                // As on the board, the engine opponent only lets the player move in the current
+                // position, on their turn.
+                let can_play = match self.history_ply {
+                    Some(_) => self.opponent.is_none(),
+                    None => !self.is_engine_turn(),
+                };
+                if can_play && self.game.play_line(self.shown_plies(), &[uci_move]) {
+                    self.history_ply = None;
+                    self.after_move();
+                }
+            }
+            Message::Explorer(explorer_message) => {
+                if let Some(explorer_panel) = self.explorer_panel.as_mut() {
+                    explorer_panel.update(explorer_message);
+                }
+            }
+            Message::OpenLibrary => self.library_browser = Some(LibraryBrowser::default()),
+            Message::Library(LibraryMessage::Open(index)) => self.open_library_game(index),
+            Message::Library(library_message) => {
+                if let Some(library_browser) = self.library_browser.as_mut() {
+                    library_browser.update(library_message);
+                }
+            }
+            Message::CloseLibrary => self.library_browser = None,
+            Message::OpenMatchDialog => {
+                self.match_dialog = Some(MatchDialog::new(&self.settings.engines))
+            }
+            Message::MatchDialog(dialog_message) => {
+                if let Some(match_dialog) = self.match_dialog.as_mut() {
+                    match_dialog.update(dialog_message);
+                }
+            }
+            Message::StartMatch => {
+                if let Some(match_dialog) = &self.match_dialog {
+                    match match_dialog.settings(&self.settings) {
+                        Ok(match_settings) => {
+                            self.match_dialog = None;
+                            self.stop_analysis();
+                            self.stop_review();
+                            self.clear_hint();
+                            self.engine_match = Some(Tournament::new(match_settings));
+                            self.status_message = None;
+                        }
+                        Err(err) => self.status_message = Some(err),
+                    }
+                }
+            }
+            Message::CancelMatchDialog => self.match_dialog = None,
+            Message::Match(MatchMessage::CopyPgn) => {
+                if let Some(engine_match) = &self.engine_match {
+                    self.status_message = Some(String::from("PGN copied to the clipboard."));
+                    return iced::clipboard::write(engine_match.pgn());
+                }
+            }
+            Message::Match(MatchMessage::Close) => {
+                if let Some(engine_match) = self.engine_match.take() {
+                    engine_match.quit();
+                }
+            }
+            Message::MatchEngine(index, event) => {
+                if let Some(engine_match) = self.engine_match.as_mut() {
+                    engine_match.handle_event(index, event);
+                }
+            }
+            Message::MatchFrame(now) => {
+                if let Some(engine_match) = self.engine_match.as_mut() {
+                    engine_match.check_time(now);
+                }
+            }
+        }
+        Task::none()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let engine = match &self.engine {
+            Some(engine) => engine.subscription().map(Message::Engine),
+            None => Subscription::none(),
+        };
+        let eval_bar_frames = if self.analysis.is_some() && self.eval_bar.is_moving() {
+            iced::window::frames().map(Message::EvalBarFrame)
+        } else {
+            Subscription::none()
+        };
+        let engine_match = match &self.engine_match {
+            Some(engine_match) if engine_match.is_searching() => Subscription::batch([
+                engine_match
+                    .subscription()
+                    .map(|(index, event)| Message::MatchEngine(index, event)),
+                iced::window::frames().map(Message::MatchFrame),
+            ]),
+            Some(engine_match) => engine_match
+                .subscription()
+                .map(|(index, event)| Message::MatchEngine(index, event)),
+            None => Subscription::none(),
+        };
+        let clock_ticks = if self.clocks.is_running() {
+            Subscription::run(every_second)
+        } else {
+            Subscription::none()
+        };
+        Subscription::batch([engine, eval_bar_frames, engine_match, clock_ticks])
+    }
+
+    fn save_settings(&mut self) {
+        self.status_message = match self.settings.save() {
+            Ok(()) => Some(String::from("Settings saved.")),
+            Err(err) => Some(format!("Cannot save the settings: {err}.")),
+        };
+    }
+
+    fn engine_path(&self) -> Option<PathBuf> {
+        self.engine.as_ref().map(|engine| engine.path().clone())
+    }
+
+    /// Starts the engine at the given path, unless it is already running.
+    /// Without path, the engine is stopped.
+    fn load_engine(&mut self, path: Option<PathBuf>) {
+        self.opponent = None;
+        self.pending_opponent = None;
+        if let Some(engine) = self.engine.as_mut()
+            && Some(engine.path()) == path.as_ref()
+        {
+            if engine.error().is_some() {
+                engine.restart();
+            }
+            return;
+        }
+
+        if let Some(engine) = self.engine.take() {
+            engine.send(EngineCommand::Quit);
+        }
+        self.engine = path.map(|path| {
+            let protocol = self
+                .settings
+                .engine(&path)
+                .map(|engine_settings| engine_settings.protocol)
+                .unwrap_or_default();
+            Engine::new(path, protocol)
+        });
+    }
+
+    /// Opens the book of the settings, if any.
+    fn open_book(settings: &Settings) -> Result<Option<OpeningBook>, String> {
+        let Some(path) = &settings.book.path else {
+            return Ok(None);
+        };
+        OpeningBook::open(path)
+            .map(Some)
+            .map_err(|err| format!("Cannot open the book {}: {err}.", path.display()))
+    }
+
+    /// Opens the tablebases of the settings, if any.
+    fn open_tablebases(settings: &Settings) -> Result<Option<Tablebases>, String> {
+        let Some(path) = &settings.syzygy_path else {
+            return Ok(None);
+        };
+        Tablebases::open(path)
+            .map(Some)
+            .map_err(|err| format!("Cannot open the tablebases: {err}."))
+    }
+
+    /// Keeps the directory of the tablebases, opens them and tells the engine where they are.
+    fn set_syzygy_path(&mut self, syzygy_path: Option<PathBuf>) {
+        self.settings.syzygy_path = syzygy_path;
+        self.save_settings();
+        self.tablebases = match App::open_tablebases(&self.settings) {
+            Ok(tablebases) => tablebases,
+            Err(err) => {
+                self.status_message = Some(err);
+                None
+            }
+        };
+        if let Some(engine) = &self.engine
+            && let Some(path) = &self.settings.syzygy_path
+        {
+            engine.set_tablebase_path(path);
+        }
+        self.restart_analysis();
+    }
+
+    /// Keeps the book chosen in the new game dialog, and opens it if it has changed.
+    fn set_book(&mut self, book: BookSettings) {
+        if book == self.settings.book {
+            return;
+        }
+        let path_changed = book.path != self.settings.book.path;
+        self.settings.book = book;
+        if let Err(err) = self.settings.save() {
+            self.status_message = Some(format!("Cannot save the settings: {err}."));
+        }
+        if path_changed {
+            self.book = match App::open_book(&self.settings) {
+                Ok(book) => book,
+                Err(err) => {
+                    self.status_message = Some(err);
+                    None
+                }
+            };
+        }
+    }
+
+    fn start_new_game(&mut self, new_game_dialog: NewGameDialog) {
+        let Some(game) = new_game_dialog.game() else {
+            return;
+        };
+        self.clear_hint();
+        self.stop_review();
+        let player_side = new_game_dialog.player_side();
+        self.game = game;
+        self.history_ply = None;
+        self.board_reversed = player_side == owlchess::Color::Black;
+        self.status_message = None;
+        self.opponent = None;
+        self.pending_opponent = None;
+        self.clocks = Clocks::default();
+        self.set_book(new_game_dialog.book());
+
+        let level = self
+            .engine
+            .as_ref()
+            .and_then(Engine::identity)
+            .and_then(|identity| new_game_dialog.strength(&identity.options).level);
+        let options = self
+            .engine_path()
+            .and_then(|path| self.settings.engine(&path))
+            .map(|engine_settings| engine_settings.options.clone())
+            .unwrap_or_default();
+        self.start_opponent(
+            player_side.inv(),
+            level,
+            new_game_dialog.resign_score(),
+            options,
+        );
+        self.move_list = MoveList::new(&self.game);
+        self.autosave();
+    }
+
+    /// Lets the engine play the side of the new game, if it is ready.
+    fn start_opponent(
+        &mut self,
+        engine_side: owlchess::Color,
+        level: Option<i64>,
+        resign_score: Option<i32>,
+        options: BTreeMap<String, String>,
+    ) {
+        if let Some(engine) = self.engine.as_mut()
+            && let Some(identity) = engine.identity()
+        {
+            let control = StrengthControl::from_options(&identity.options);
+            let (min, max) = control.range();
+            let strength = Strength {
+                control,
+                level: level.map(|level| level.clamp(min, max)),
+            };
+            let engine_tag = match engine_side {
+                owlchess::Color::White => "White",
+                owlchess::Color::Black => "Black",
+            };
+            let engine_name = self
+                .settings
+                .engine(engine.path())
+                .map_or(&identity.name, |engine_settings| &engine_settings.name);
+            self.game.set_header(engine_tag, engine_name.clone());
+
+            // The saved options come first, so that the strength options override them.
+            // A single line is searched, whatever the number of lines of the last analysis.
+            let option_commands = lines_command(&identity.options, 1)
+                .into_iter()
+                .chain(identity.option_commands(&options))
+                .chain(strength.setup_commands(&identity.options))
+                .collect::<Vec<_>>();
+
+            engine.stop();
+            engine.send(EngineCommand::NewGame);
+            for command in option_commands {
+                engine.send(command);
+            }
+            self.opponent = Some(Opponent {
+                side: engine_side,
+                strength,
+                conduct: Conduct::new(resign_score),
+                draw_offered_at: None,
+                options,
+            });
+            self.run_clocks();
+            self.start_engine_turn();
+        }
+    }
+
+    /// Runs the clock of the side to move while the engine plays, and stops both otherwise.
+    fn run_clocks(&mut self) {
+        let side = (self.opponent.is_some() && !self.game.is_finished())
+            .then(|| self.game.position().side());
+        self.clocks.run(side, Instant::now());
+    }
+
+    /// Saves the game in progress with its clocks, so that it can be resumed by the next session.
+    /// A finished game, or an empty one on the board, is not worth resuming.
+    fn autosave(&mut self) {
+        self.run_clocks();
+        let now = Instant::now();
+        self.last_autosave = now;
+        let worth_saving = !self.game.is_finished()
+            && (self.opponent.is_some()
+                || self.pending_opponent.is_some()
+                || !self.game.moves().is_empty());
+        let result = if worth_saving {
+            Recovery {
+                pgn: pgn::write(&self.game),
+                // The engine of a resumed game may not be ready yet.
+                opponent: self
+                    .opponent
+                    .as_ref()
+                    .zip(self.engine.as_ref())
+                    .map(|(opponent, engine)| SavedOpponent {
+                        engine_path: engine.path().clone(),
+                        engine_is_white: opponent.side == owlchess::Color::White,
+                        level: opponent.strength.level,
+                        resign_score: opponent.conduct.resign_score(),
+                        book: self.settings.book.clone(),
+                        options: opponent.options.clone(),
+                    })
+                    .or_else(|| self.pending_opponent.clone()),
+                time_spent: [owlchess::Color::White, owlchess::Color::Black]
+                    .map(|side| self.clocks.spent(side, now).as_millis() as u64),
+            }
+            .save()
+        } else {
+            Recovery::clear()
+        };
+        // The game of the last session is replaced.
+        self.recovery = None;
+        if let Err(err) = result {
+            self.status_message = Some(format!("Cannot save the game for recovery: {err}."));
+        }
+    }
+
+    /// Resumes the game of the last session, with its engine once it is ready.
+    fn resume_game(&mut self) {
+        let Some(recovery) = self.recovery.take() else {
+            return;
+        };
+        let game = match pgn::read(&recovery.pgn) {
+            Ok(game) => game,
+            Err(err) => {
+                self.status_message = Some(format!("Cannot resume the saved game: {err}."));
+                return;
+            }
+        };
+        self.replace_game(game);
+        self.clocks = Clocks::new(recovery.time_spent.map(Duration::from_millis));
+        if let Some(saved_opponent) = recovery.opponent {
+            self.board_reversed = saved_opponent.engine_is_white;
+            self.load_engine(Some(saved_opponent.engine_path.clone()));
+            self.pending_opponent = Some(saved_opponent);
+            // The engine may already be running, and will not tell again that it is ready.
+            if self.engine.as_ref().and_then(Engine::identity).is_some() {
+                self.start_pending_opponent();
+            }
+            self.autosave();
+        }
+        self.status_message = Some(String::from("The saved game is resumed."));
+    }
+
+    /// Lets the engine of the resumed game take its side.
+    fn start_pending_opponent(&mut self) {
+        if let Some(saved_opponent) = self.pending_opponent.take() {
+            let engine_side = if saved_opponent.engine_is_white {
+                owlchess::Color::White
+            } else {
+                owlchess::Color::Black
+            };
+            self.set_book(saved_opponent.book);
+            self.start_opponent(
+                engine_side,
+                saved_opponent.level,
+                saved_opponent.resign_score,
+                saved_opponent.options,
+            );
+        }
+    }
+
+    fn is_engine_turn(&self) -> bool {
+        self.opponent
+            .as_ref()
+            .is_some_and(|opponent| opponent.side == self.game.position().side())
+    }
+
+    /// Asks the engine for its move, if it has to play.
+    /// A move of the opening book is played at once, as long as the game is in the book, and so
+    /// is the best move of the tablebases once few pieces are left, once they are probed.
+    fn start_engine_turn(&mut self) {
+        if self.game.is_finished() || !self.is_engine_turn() {
+            return;
+        }
+        if let Some(opponent) = self.opponent.as_mut() {
+            opponent.conduct.start_search();
+        }
+        if let Some(book_move) = self.book_move() {
+            if self.engine_resigns() {
+                return;
+            }
+            self.game.play_move(book_move.into());
+            self.after_move();
+            return;
+        }
+        let position = self.game.position().clone();
+        if self
+            .tablebases
+            .as_ref()
+            .is_some_and(|tablebases| tablebases.covers(&position))
+        {
+            self.probe_tablebases(position, Message::TablebaseMove);
+            return;
+        }
+        self.start_engine_search();
+    }
+
+    fn start_engine_search(&mut self) {
+        if let Some(engine) = self.engine.as_mut()
+            && let Some(opponent) = &self.opponent
+        {
+            engine.go(&self.game, opponent.strength.search_limit());
+        }
+    }
+
+    /// Polyglot books hold standard chess only, so they are not used in Chess960 games.
+    fn book_move(&self) -> Option<owlchess::Move> {
+        let book = self.book.as_ref()?;
+        if self.game.is_chess960() || self.game.moves().len() >= 2 * self.settings.book.depth {
+            return None;
+        }
+        book.choose(self.game.position(), self.settings.book.best_move_only)
+    }
+
+    /// Probes the tablebases in the background, the result coming back with the message.
+    fn probe_tablebases(
+        &mut self,
+        position: owlchess::Board,
+        message: fn(u64, Result<PositionProbe, String>) -> Message,
+    ) {
+        let Some(tablebases) = self.tablebases.clone() else {
+            return;
+        };
+        let hash = position.zobrist_hash();
+        self.tasks.push(Task::perform(
+            async move { tablebases.probe(&position).map_err(|err| err.to_string()) },
+            move |probe| message(hash, probe),
+        ));
+    }
+
+    /// Plays the best move of the tablebases, whose result the engine keeps as its evaluation.
+    /// The engine searches when the tables cannot tell.
+    fn play_tablebase_move(&mut self, hash: u64, probe: Result<PositionProbe, String>) {
+        if self.game.is_finished()
+            || !self.is_engine_turn()
+            || self.game.position().zobrist_hash() != hash
+        {
+            return;
+        }
+        let best_move = probe
+            .ok()
+            .and_then(|probe| Some((probe.wdl, probe.moves.first()?.chess_move)));
+        let Some((wdl, best_move)) = best_move else {
+            self.start_engine_search();
+            return;
+        };
+        if let Some(opponent) = self.opponent.as_mut() {
+            opponent.conduct.update_from_tablebases(wdl);
+        }
+        if self.engine_resigns() {
+            return;
+        }
+        self.game.play_move(best_move.into());
+        self.after_move();
+    }
+
+    /// Lets the engine play, or tells it the result once the game is over.
+    fn after_move(&mut self) {
+        self.clear_hint();
+        self.stop_review();
+        match self.game.outcome() {
+            Some(outcome) => {
+                if let Some(engine) = &self.engine
+                    && self.opponent.is_some()
+                {
+                    engine.send(EngineCommand::GameOver(outcome));
+                }
+                self.store_game();
+            }
+            None => self.start_engine_turn(),
+        }
+        self.restart_analysis();
+        self.move_list = MoveList::new(&self.game);
+        self.autosave();
+    }
+
+    /// Indexes the PGN collection of the explorer panel, and keeps its path in the settings.
+    fn load_explorer(&mut self) {
+        let Some(explorer_panel) = self.explorer_panel.as_mut() else {
+            return;
+        };
+        let Some(path) = explorer_panel.path() else {
+            return;
+        };
+        match OpeningExplorer::open(&path) {
+            Ok(explorer) => {
+                explorer_panel.set_error(None);
+                self.explorer = Some(explorer);
+                if self.settings.explorer_path.as_ref() != Some(&path) {
+                    self.settings.explorer_path = Some(path);
+                    self.save_settings();
+                }
+            }
+            Err(err) => explorer_panel.set_error(Some(format!(
+                "Cannot read the collection {}: {err}.",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Adds the finished game to the library, with the engine it was played against.
+    fn store_game(&mut self) {
+        let engine = self.opponent.as_ref().and_then(|opponent| {
+            let engine_tag = match opponent.side {
+                owlchess::Color::White => "White",
+                owlchess::Color::Black => "Black",
+            };
+            self.game.header(engine_tag).map(str::to_string)
+        });
+        if let Err(err) = self.library.add(&self.game, engine) {
+            self.status_message = Some(format!("Cannot store the game in the library: {err}."));
+        }
+    }
+
+    /// Replaces the game by a game of the library, shown from its start position.
+    fn open_library_game(&mut self, index: usize) {
+        let Some(entry) = self.library.entries().get(index) else {
+            return;
+        };
+        match self.library.read_game(entry) {
+            Ok(game) => {
+                self.library_browser = None;
+                self.replace_game(game);
+                self.history_ply = (!self.game.moves().is_empty()).then_some(0);
+                self.restart_analysis();
+                self.status_message = Some(String::from("Game opened from the library."));
+            }
+            Err(err) => self.status_message = Some(format!("Cannot open the game: {err}.")),
+        }
+    }
+
+    /// Ends the game before its end on the board, by resignation or agreement.
+    fn end_game(&mut self, outcome: owlchess::Outcome) {
+        if self.game.is_finished() {
+            return;
+        }
+        if let Some(engine) = self.engine.as_mut()
+            && self.opponent.is_some()
+        {
+            engine.stop();
+        }
+        self.game.set_outcome(outcome);
+        self.after_move();
+    }
+
+    /// Whether the player may offer a draw to the engine, once per move.
+    fn can_offer_draw(&self) -> bool {
+        self.opponent
+            .as_ref()
+            .is_some_and(|opponent| opponent.draw_offered_at != Some(self.game.moves().len()))
+            && !self.game.is_finished()
+    }
+
+    /// The engine accepts the draw or declines it, from its last evaluation.
+    fn offer_draw(&mut self) {
+        if !self.can_offer_draw() {
+            return;
+        }
+        let move_number = self.game.position().raw().move_number;
+        let Some(opponent) = self.opponent.as_mut() else {
+            return;
+        };
+        opponent.draw_offered_at = Some(self.game.moves().len());
+        if opponent.conduct.accepts_draw(move_number) {
+            self.end_game(owlchess::Outcome::Draw(owlchess::DrawReason::Agreement));
+            self.status_message = Some(String::from("The engine accepts the draw."));
+        } else {
+            self.status_message = Some(String::from("The engine declines the draw."));
+        }
+    }
+
+    /// Whether the player may resign : only against the engine opponent.
+    fn can_resign(&self) -> bool {
+        self.opponent.is_some() && !self.game.is_finished()
+    }
+
+    /// The player resigns to the engine opponent.
+    fn resign(&mut self) {
+        if !self.can_resign() {
+            return;
+        }
+        let Some(opponent) = &self.opponent else {
+            return;
+        };
+        self.end_game(owlchess::Outcome::Win {
+            side: opponent.side,
+            reason: owlchess::WinReason::Resign,
+        });
+    }
+
+    /// Whether the player may ask for a hint, or for the destination of the hinted piece.
+    fn can_ask_hint(&self) -> bool {
+        let hint_pending = self
+            .hint
+            .as_ref()
+            .is_some_and(|hint| hint.suggested_move.is_none() || hint.show_destination);
+        self.opponent.is_some()
+            && !self.game.is_finished()
+            && !self.is_engine_turn()
+            && self.history_ply.is_none()
+            && !hint_pending
+            && self
+                .engine
+                .as_ref()
+                .is_some_and(|engine| engine.identity().is_some() && engine.error().is_none())
+    }
+
+    /// Asks the engine for a move the first time, then shows the destination of the hinted
+    /// piece.
+    fn request_hint(&mut self) {
+        if !self.can_ask_hint() {
+            return;
+        }
+        if let Some(hint) = self.hint.as_mut() {
+            hint.show_destination = true;
+        } else if let Some(engine) = self.engine.as_mut() {
+            engine.go(&self.game, SearchLimit::MoveTime(HINT_MOVE_TIME));
+            self.game.count_hint();
+            self.hint = Some(Hint {
+                suggested_move: None,
+                show_destination: false,
+            });
+        }
+    }
+
+    fn show_hint(&mut self, best_move: &str) {
+        match self.game.legal_move(self.game.moves().len(), best_move) {
+            Some(legal_move) => {
+                self.hint = Some(Hint {
+                    suggested_move: Some(legal_move),
+                    show_destination: false,
+                });
+            }
+            None => {
+                self.hint = None;
+                self.status_message = Some(String::from("The engine found no hint."));
+            }
+        }
+    }
+
+    /// Forgets the hint, stopping its search if needed.
+    fn clear_hint(&mut self) {
+        if let Some(hint) = self.hint.take()
+            && hint.suggested_move.is_none()
+            && let Some(engine) = self.engine.as_mut()
+        {
+            engine.stop();
+        }
+    }
+
+    /// Plays the move made on the board, from the position after the given number of moves.
+    /// Playing from a past position starts another line, unless the engine plays.
+    fn play_from_board(&mut self, play: impl FnOnce(&mut Game, usize) -> bool) {
+        let plies = self.game.moves().len();
+        let played = match self.history_ply {
+            Some(plies) if self.opponent.is_none() => play(&mut self.game, plies),
+            Some(_) => false,
+            None => !self.is_engine_turn() && play(&mut self.game, plies),
+        };
+        if played {
+            self.history_ply = None;
+            self.after_move();
+        }
+    }
+
+    /// The number of moves leading to the position shown on the board.
+    fn shown_plies(&self) -> usize {
+        self.history_ply.unwrap_or(self.game.moves().len())
+    }
+
+    fn shown_position(&self) -> owlchess::Board {
+        self.game.position_at(self.shown_plies())
+    }
+
+    /// Whether the engine may analyse : it must not be playing a game.
+    fn can_analyze(&self) -> bool {
+        (self.opponent.is_none() || self.game.is_finished())
+            && (self.engine.is_some() || !self.settings.engines.is_empty())
+    }
+
+    /// Analyses the position on the board with the loaded engine, or with the first registered
+    /// engine.
+    fn start_analysis(&mut self) {
+        let path = self.engine_path().or_else(|| {
+            self.settings
+                .engines
+                .first()
+                .map(|engine| engine.path.clone())
+        });
+        if path.is_none() {
+            self.status_message = Some(String::from("Register an engine to analyse positions."));
+            return;
+        }
+        self.stop_review();
+        self.load_engine(path);
+        self.analysis = Some(AnalysisPanel::new(
+            self.game.continuation(self.shown_plies()),
+            1,
+        ));
+        self.restart_analysis();
+    }
+
+    fn stop_analysis(&mut self) {
+        self.eval_bar.clear();
+        if self.analysis.take().is_some()
+            && let Some(engine) = self.engine.as_mut()
+        {
+            engine.stop();
+        }
+    }
+
+    /// Analyses the position shown on the board, after it has changed.
+    /// The analysis starts once the engine is ready.
+    fn restart_analysis(&mut self) {
+        let plies = self.shown_plies();
+        let Some(analysis) = self.analysis.as_mut() else {
+            return;
+        };
+        analysis.reset(self.game.continuation(plies));
+        if let Some(book) = &self.book
+            && !self.game.is_chess960()
+        {
+            analysis.set_book_moves(book.moves(&self.game.position_at(plies)));
+        }
+        if let Some(engine) = self.engine.as_mut()
+            && let Some(identity) = engine.identity()
+        {
+            let lines_command = lines_command(&identity.options, analysis.line_count());
+            engine.stop();
+            if analysis.has_moves() {
+                if let Some(lines_command) = lines_command {
+                    engine.send(lines_command);
+                }
+                engine.analyze(&self.game, plies);
+            }
+        }
+        // The positions the tablebases do not cover are left to the engine, silently.
+        let position = self.game.position_at(plies);
+        if self
+            .tablebases
+            .as_ref()
+            .is_some_and(|tablebases| tablebases.covers(&position))
+        {
+            self.probe_tablebases(position, Message::TablebaseAnalysis);
+        }
+    }
+
+    /// Reviews the game with the loaded engine, or with the first registered engine.
+    fn start_review(&mut self) {
+        let path = self.engine_path().or_else(|| {
+            self.settings
+                .engines
+                .first()
+                .map(|engine| engine.path.clone())
+        });
+        if path.is_none() {
+            self.status_message = Some(String::from("Register an engine to review games."));
+            return;
+        }
+        self.stop_analysis();
+        self.stop_review();
+        self.load_engine(path);
+        if let Some(engine) = self.engine.as_mut() {
+            engine.stop();
+        }
+        self.game.set_annotations(BTreeMap::new());
+        self.review = Some(ReviewPanel::new(&self.game));
+        self.continue_review();
+    }
+
+    fn stop_review(&mut self) {
+        if self.review.take().is_some_and(|review| review.is_running())
+            && let Some(engine) = self.engine.as_mut()
+        {
+            engine.stop();
+        }
+    }
+
+    /// Searches the next position of the reviewed game, once the engine is ready.
+    /// The game is annotated once every position is evaluated.
+    fn continue_review(&mut self) {
+        let Some(review) = self.review.as_mut() else {
+            return;
+        };
+        while let Some(plies) = review.next_position() {
+            if let Some(eval) = PositionEval::without_search(&self.game, plies) {
+                review.add_eval(eval);
+                continue;
+            }
+            if let Some(engine) = self.engine.as_mut()
+                && let Some(identity) = engine.identity()
+            {
+                // The engine reviews at full strength, on a single line.
+                if plies == 0 {
+                    let full_strength = Strength {
+                        control: StrengthControl::from_options(&identity.options),
+                        level: None,
+                    };
+                    let commands = lines_command(&identity.options, 1)
+                        .into_iter()
+                        .chain(full_strength.setup_commands(&identity.options))
+                        .collect::<Vec<_>>();
+                    for command in commands {
+                        engine.send(command);
+                    }
+                }
+                engine.search(&self.game, plies, SearchLimit::MoveTime(REVIEW_MOVE_TIME));
+            }
+            return;
+        }
+        let annotations = review.complete(&self.game);
+        self.game.set_annotations(annotations);
+    }
+
+    fn review_search_done(&mut self, best_move: String) {
+        let Some(review) = self.review.as_mut() else {
+            return;
+        };
+        if review.finish_search(best_move) {
+            self.continue_review();
+        } else {
+            self.review = None;
+            self.status_message = Some(String::from(
+                "Cannot review the game: the engine does not tell its evaluation.",
+            ));
+        }
+    }
+
+    fn analysis_max_lines(&self) -> usize {
+        self.engine
+            .as_ref()
+            .and_then(Engine::identity)
+            .map_or(1, |identity| max_lines(&identity.options))
+    }
+
+    /// The name of the loaded engine, as registered.
+    fn engine_name(&self) -> String {
+        match &self.engine {
+            Some(engine) => self
+                .settings
+                .engine(engine.path())
+                .map(|engine_settings| engine_settings.name.clone())
+                .or_else(|| engine.identity().map(|identity| identity.name.clone()))
+                .unwrap_or_else(|| default_name(engine.path())),
+            None => String::new(),
+        }
+    }
+
+    /// Whether the engine resigns rather than playing its move, ending the game if so.
+    fn engine_resigns(&mut self) -> bool {
+        let Some(opponent) = self.opponent.as_mut() else {
+            return false;
+        };
+        if !opponent.conduct.resigns() {
+            return false;
+        }
+        let winner = opponent.side.inv();
+        self.end_game(owlchess::Outcome::Win {
+            side: winner,
+            reason: owlchess::WinReason::Resign,
+        });
+        self.status_message = Some(String::from("The engine resigns."));
+        true
+    }
+
+    fn play_engine_move(&mut self, best_move: &str) {
+        if self.game.is_finished() || !self.is_engine_turn() {
+            return;
+        }
+        if self.engine_resigns() {
+            return;
+        }
+        match self.game.legal_move(self.game.moves().len(), best_move) {
+            Some(legal_move) => {
+                self.game.play_move(legal_move);
+                self.after_move();
+            }
+            None => {
+                self.status_message =
+                    Some(format!("The engine played an illegal move {best_move}."));
+                self.opponent = None;
+            }
+        }
+    }
+
+    /// Replaces the game by a game played on the board, without the engine.
+    fn replace_game(&mut self, game: Game) {
+        self.clear_hint();
+        self.stop_review();
+        if let Some(engine) = self.engine.as_mut() {
+            engine.stop();
+        }
+        self.opponent = None;
+        self.pending_opponent = None;
+        self.clocks = Clocks::default();
+        self.game = game;
+        self.history_ply = None;
+        self.move_list = MoveList::new(&self.game);
+        self.restart_analysis();
+        self.autosave();
+    }
+
+    /// Starts a new game from a FEN position or from a PGN game.
+    fn load_pasted_text(&mut self, text: &str) {
+        let text = text.trim();
+        let fen_error = match game::parse_fen(text) {
+            Ok(board) => {
+                self.replace_game(Game::new(board));
+                self.status_message = Some(String::from("Position loaded from the clipboard."));
+                return;
+            }
+            Err(err) => err,
+        };
+        let looks_like_fen = text.contains('/') && !text.starts_with('[') && !text.contains('\n');
+        self.status_message = Some(match pgn::read(text) {
+            Ok(game) => {
+                self.replace_game(game);
+                String::from("Game loaded from the clipboard.")
+            }
+            Err(_) if looks_like_fen => {
+                format!("Cannot load the FEN from the clipboard: {fen_error}")
+            }
+            Err(err) => format!("Cannot load the PGN from the clipboard: {err}"),
+        });
+    }
+
+    fn engine_status(&self) -> EngineStatus<'_> {
+        match &self.engine {
+            None => EngineStatus::NotLoaded,
+            Some(engine) => match (engine.identity(), engine.error()) {
+                (_, Some(error)) => EngineStatus::Failed(error),
+                (Some(identity), None) => EngineStatus::Ready {
+                    name: &identity.name,
+                    options: &identity.options,
+                },
+                (None, None) => EngineStatus::Loading,
+            },
+        }
+    }
+
+    fn view(&self) -> iced::Element<'_, Message> {
+        let mode_buttons = if self.engine_match.is_some() {
+            row![]
+        } else if self.match_dialog.is_some() {
+            row![
+                button(text("Start match")).on_press(Message::StartMatch),
+                button(text("Cancel")).on_press(Message::CancelMatchDialog),
+            ]
+        } else if self.library_browser.is_some() {
+            row![button(text("Close")).on_press(Message::CloseLibrary)]
+        } else if self.engines_manager.is_some() {
+            row![
+                button(text("Save")).on_press(Message::SaveEngines),
+                button(text("Cancel")).on_press(Message::CancelEnginesManager),
+            ]
+        } else if self.engine_options_editor.is_some() {
+            row![
+                button(text("Save")).on_press(Message::SaveEngineOptions),
+                button(text("Cancel")).on_press(Message::CancelEngineOptions),
+            ]
+        } else if self.new_game_dialog.is_some() {
+            row![
+                button(text("Engine options")).on_press_maybe(
+                    matches!(self.engine_status(), EngineStatus::Ready { .. })
+                        .then_some(Message::OpenEngineOptions)
+                ),
+                button(text("Start game")).on_press_maybe(
+                    self.new_game_dialog
+                        .as_ref()
+                        .is_some_and(|dialog| dialog.can_start(&self.engine_status()))
+                        .then_some(Message::StartNewGame)
+                ),
+                button(text("Cancel")).on_press(Message::CancelNewGame),
+            ]
+        } else if let Some(position_editor) = &self.position_editor {
+            row![
+                button(text("Start game")).on_press_maybe(
+                    position_editor
+                        .validated_board()
+                        .ok()
+                        .map(|_| Message::StartGameFromEditor)
+                ),
+                button(text("Cancel")).on_press(Message::CancelPositionEditor),
+            ]
+        } else {
+            row![
+                button(text("New game")).on_press(Message::OpenNewGameDialog),
+                button(text("Setup position")).on_press(Message::OpenPositionEditor),
+                button(text("Engines")).on_press(Message::OpenEnginesManager),
+                button(text("Engine match")).on_press(Message::OpenMatchDialog),
+                button(text("Library")).on_press(Message::OpenLibrary),
+                button(text(if self.analysis.is_some() {
+                    "Stop analysis"
+                } else {
+                    "Analyze"
+                }))
+                .on_press_maybe(
+                    (self.analysis.is_some() || self.can_analyze())
+                        .then_some(Message::ToggleAnalysis)
+                ),
+                button(text("Hint")).on_press_maybe(self.can_ask_hint().then_some(Message::Hint)),
+                button(text("Offer draw"))
+                    .on_press_maybe(self.can_offer_draw().then_some(Message::OfferDraw)),
+                button(text("Resign")).on_press_maybe(self.can_resign().then_some(Message::Resign)),
+                button(text(if self.explorer_panel.is_some() {
+                    "Close explorer"
+                } else {
+                    "Explorer"
+                }))
+                .on_press(Message::ToggleExplorer),
+                button(text("Review")).on_press_maybe(
+                    (self.can_analyze() && !self.game.moves().is_empty())
+                        .then_some(Message::StartReview)
+                ),
+                button(text("Copy FEN")).on_press(Message::CopyFen),
+                button(text("Copy PGN")).on_press(Message::CopyPgn),
+                button(text("Paste")).on_press(Message::PasteRequested),
+            ]
+        }
+        .spacing(5);
+
+        let content: iced::Element<'_, Message> = if let Some(engine_match) = &self.engine_match {
+            self.match_board_view(engine_match)
+        } else if let Some(match_dialog) = &self.match_dialog {
+            container(
+                match_dialog
+                    .view(&self.settings.engines)
+                    .map(Message::MatchDialog),
+            )
+            .center_x(Length::Fill)
+            .into()
+        } else if let Some(library_browser) = &self.library_browser {
+            container(
+                library_browser
+                    .view(self.library.entries())
+                    .map(Message::Library),
+            )
+            .center_x(Length::Fill)
+            .into()
+        } else if let Some(engines_manager) = &self.engines_manager {
+            container(engines_manager.view().map(Message::EnginesManager))
+                .center_x(Length::Fill)
+                .into()
+        } else if let Some(engine_options_editor) = &self.engine_options_editor {
+            container(engine_options_editor.view().map(Message::EngineOptions))
+                .center_x(Length::Fill)
+                .into()
+        } else if let Some(new_game_dialog) = &self.new_game_dialog {
+            container(
+                new_game_dialog
+                    .view(&self.settings.engines, self.engine_status())
+                    .map(Message::NewGame),
+            )
+            .center_x(Length::Fill)
+            .into()
+        } else if let Some(position_editor) = &self.position_editor {
+            position_editor
+                .view(self.board_reversed)
+                .map(Message::Editor)
+        } else {
+            let engine_status = match &self.engine {
+                Some(engine)
+                    if self.opponent.is_some()
+                        || self.analysis.is_some()
+                        || self.review.is_some() =>
+                {
+                    engine.error().map(str::to_string).or_else(|| {
+                        (self.opponent.is_some() && engine.is_thinking()).then(|| {
+                            String::from(if self.is_engine_turn() {
+                                "The engine is thinking..."
+                            } else {
+                                "The engine is looking for a hint..."
+                            })
+                        })
+                    })
+                }
+                _ => None,
+            };
+            let game_status = self.game.outcome().map(|outcome| {
+                format!(
+                    "{} ({})",
+                    owlchess::GameStatus::from(Some(outcome)),
+                    outcome
+                )
+            });
+            let now = Instant::now();
+            let clocks_text = self.opponent.as_ref().map(|_| {
+                format!(
+                    "White {} - Black {}",
+                    clock_text(self.clocks.spent(owlchess::Color::White, now)),
+                    clock_text(self.clocks.spent(owlchess::Color::Black, now))
+                )
+            });
+            // The hint is only shown with the current position.
+            let hint = self.hint.as_ref().filter(|_| self.history_ply.is_none());
+            let hint_text = hint.and_then(|hint| hint.describe(self.game.position()));
+            let hint_move = hint.and_then(|hint| hint.suggested_move);
+            let plies = self.shown_plies();
+            let mut arrows = match (&self.analysis, &self.review) {
+                (Some(analysis), _) => analysis.arrows(),
+                (None, Some(review)) => review.arrows(plies),
+                (None, None) => Vec::new(),
+            };
+            if let Some(hint_move) = hint_move
+                && hint.is_some_and(|hint| hint.show_destination)
+            {
+                arrows.push(Arrow {
+                    from: hint_move.src(),
+                    to: hint_move.dst(),
+                    color: HINT_ARROW_COLOR,
+                });
+            }
+            let last_ply = self.game.moves().len();
+            let history_buttons = row![
+                button(text("<<")).on_press_maybe((plies > 0).then_some(Message::ShowPly(0))),
+                button(text("<")).on_press_maybe((plies > 0).then(|| Message::ShowPly(plies - 1))),
+                button(text(">"))
+                    .on_press_maybe((plies < last_ply).then_some(Message::ShowPly(plies + 1))),
+                button(text(">>"))
+                    .on_press_maybe((plies < last_ply).then_some(Message::ShowPly(last_ply))),
+            ]
+            .spacing(5);
+            let chessboard = Chessboard::new(
+                ChessboardOptionsBuilder::new()
+                    .set_reversed(self.board_reversed)
+                    .set_position(self.shown_position().as_fen())
+                    .set_arrows(arrows)
+                    .set_highlighted_cells(
+                        hint_move
+                            .map(|hint_move| hint_move.src())
+                            .into_iter()
+                            .collect(),
+                    )
+                    .build(),
+                chessboard::MessageProducer {
+                    build_update_position: App::build_update_position_message,
+                },
+            )
+            .on_paste(Message::Paste)
+            .on_castling(Message::Castle);
+            // The bar comes first, so that the board is sized in the remaining width.
+            let board: iced::Element<'_, Message> = if self.analysis.is_some() {
+                row![self.eval_bar.view(self.board_reversed), chessboard]
+                    .height(Length::Fill)
+                    .align_y(Vertical::Center)
+                    .spacing(5)
+                    .into()
+            } else {
+                chessboard.into()
+            };
+            let recovery_prompt = self.recovery.as_ref().map(|_| {
+                row![
+                    text("An unfinished game was saved by the last session."),
+                    button(text("Resume")).on_press(Message::ResumeGame),
+                    button(text("Discard")).on_press(Message::DiscardRecovery),
+                ]
+                .spacing(5)
+                .align_y(Vertical::Center)
+            });
+            let board_column = column![container(board).center(Length::Fill)]
+                .push(history_buttons)
+                .push_maybe(recovery_prompt)
+                .push_maybe(clocks_text.map(text))
+                .push_maybe(game_status.map(text))
+                .push_maybe(engine_status.map(text))
+                .push_maybe(hint_text.map(text))
+                .align_x(Horizontal::Center)
+                .spacing(5);
+            let side_panel = match (&self.analysis, &self.review) {
+                (Some(analysis), _) => Some(
+                    analysis
+                        .view(&self.engine_name(), self.analysis_max_lines())
+                        .map(Message::Analysis),
+                ),
+                (None, Some(review)) => {
+                    Some(review.view(&self.engine_name(), plies).map(Message::Review))
+                }
+                (None, None) => None,
+            };
+            let explorer_panel = self.explorer_panel.as_ref().map(|explorer_panel| {
+                explorer_panel
+                    .view(self.explorer.as_ref(), &self.shown_position())
+                    .map(Message::Explorer)
+            });
+            // The explorer comes under the analysis or the review.
+            let side_panel = match (side_panel, explorer_panel) {
+                (Some(side_panel), Some(explorer_panel)) => {
+                    Some(column![side_panel, explorer_panel].spacing(20).into())
+                }
+                (side_panel, explorer_panel) => side_panel.or(explorer_panel),
+            };
+            let move_list = container(self.move_list.view(plies, Message::ShowPly)).width(220);
+            row![board_column, move_list]
+                .push_maybe(side_panel.map(|side_panel| container(side_panel).width(350)))
+                .spacing(10)
+                .into()
+        };
+
+        column![
+            container(
+                row![
+                    button(Svg::new(SWAP_VERT_HANDLE.clone()))
+                        .width(50)
+                        .height(50)
+                        .on_press(Message::ToggleBoardOrientation),
+                    mode_buttons,
+                ]
+                .spacing(15.0)
+                .align_y(Vertical::Center),
+            )
+            .padding(5.0)
+            .center_x(Length::Fill)
+            .style(|_| container::Style {
+                background: Some(Background::Color(Color::from_rgb8(120, 120, 120))),
+                ..Default::default()
+            }),
+            content,
+        ]
+        .push_maybe(self.status_message.as_deref().map(text))
+        .align_x(Horizontal::Center)
+        .padding(10)
+        .spacing(10)
+        .into()
+    }
+
+    /// The game being played by the engines, with their clocks, beside the results of the match.
+    fn match_board_view<'a>(&self, engine_match: &'a Tournament) -> iced::Element<'a, Message> {
+        let (top_side, bottom_side) = if self.board_reversed {
+            (owlchess::Color::White, owlchess::Color::Black)
+        } else {
+            (owlchess::Color::Black, owlchess::Color::White)
+        };
+        let game = engine_match.game();
+        let chessboard = Chessboard::new(
+            ChessboardOptionsBuilder::new()
+                .set_reversed(self.board_reversed)
+                .set_position(game.position().as_fen())
+                .set_read_only(true)
+                .build(),
+            chessboard::MessageProducer {
+                build_update_position: App::build_update_position_message,
+            },
+        );
+        let game_status = game.outcome().map(|outcome| {
+            format!(
+                "{} ({})",
+                owlchess::GameStatus::from(Some(outcome)),
+                outcome
+            )
+        });
+        let board_column = column![
+            text(player_text(engine_match, top_side)).size(18),
+            container(chessboard).center(Length::Fill),
+            text(player_text(engine_match, bottom_side)).size(18),
+        ]
+        .push_maybe(game_status.map(text))
+        .align_x(Horizontal::Center)
+        .spacing(5);
+        row![
+            board_column,
+            container(match_view(engine_match).map(Message::Match)).width(350)
+        ]
+        .spacing(10)
+        .into()
+    }
+
+    fn build_update_position_message(new_position: String) -> Message {
+        Message::UpdatePosition(new_position)
+    }
+}
+
+/// Ticks each second, from a thread of its own as the executor has no timer.
+fn every_second() -> impl iced::futures::Stream<Item = Message> {
+    let (sender, ticks) = iced::futures::channel::mpsc::unbounded();
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(Duration::from_secs(1));
+            if sender.unbounded_send(Instant::now()).is_err() {
+                break;
+            }
+        }
+    });
+    iced::futures::StreamExt::map(ticks, Message::ClockTick)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hints_show_the_piece_then_the_move() {
+        let position = owlchess::Board::initial();
+        let suggested_move = owlchess::Move::from_uci_legal("g1f3", &position)
+            .unwrap()
+            .into();
+        let mut hint = Hint {
+            suggested_move: None,
+            show_destination: false,
+        };
+        assert_eq!(hint.describe(&position), None);
+
+        hint.suggested_move = Some(suggested_move);
+        assert_eq!(
+            hint.describe(&position).as_deref(),
+            Some("Hint: move the knight on g1.")
+        );
+        hint.show_destination = true;
+        assert_eq!(hint.describe(&position).as_deref(), Some("Hint: play Nf3."));
+    }
+}
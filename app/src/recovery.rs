@@ -0,0 +1,136 @@
+//! The game in progress, saved after each move and periodically so that it can be resumed after
+//! the application was closed or has crashed.
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{BookSettings, Settings, SettingsError};
+
+const RECOVERY_FILE: &str = "recovery.toml";
+
+/// How often the game in progress is saved between the moves, so that the clocks are kept.
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The engine playing against the player, and how it was set up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedOpponent {
+    pub engine_path: PathBuf,
+    pub engine_is_white: bool,
+    /// The level of the engine, None for its full strength.
+    #[serde(default)]
+    pub level: Option<i64>,
+    #[serde(default)]
+    pub resign_score: Option<i32>,
+    /// The opening book of the engine.
+    #[serde(default)]
+    pub book: BookSettings,
+    /// The values of the engine options changed by the user.
+    #[serde(default)]
+    pub options: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recovery {
+    pub pgn: String,
+    /// None when both sides are played on the board.
+    #[serde(default)]
+    pub opponent: Option<SavedOpponent>,
+    /// The time spent by White and Black on their moves, in milliseconds.
+    #[serde(default)]
+    pub time_spent: [u64; 2],
+}
+
+impl Recovery {
+    fn file_path() -> Result<PathBuf, SettingsError> {
+        Ok(Settings::directory()?.join(RECOVERY_FILE))
+    }
+
+    /// The game saved by the last session, if it was not over.
+    pub fn load() -> Result<Option<Self>, SettingsError> {
+        Self::load_from(&Self::file_path()?)
+    }
+
+    fn load_from(file_path: &Path) -> Result<Option<Self>, SettingsError> {
+        let content = match fs::read_to_string(file_path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        toml::from_str(&content)
+            .map(Some)
+            .map_err(SettingsError::Read)
+    }
+
+    pub fn save(&self) -> Result<(), SettingsError> {
+        self.save_to(&Self::file_path()?)
+    }
+
+    fn save_to(&self, file_path: &Path) -> Result<(), SettingsError> {
+        if let Some(directory) = file_path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let content = toml::to_string_pretty(self).map_err(SettingsError::Write)?;
+        // The file is replaced at once, so that a crash while writing keeps the previous game.
+        let temporary_path = file_path.with_extension("tmp");
+        fs::write(&temporary_path, content)?;
+        fs::rename(temporary_path, file_path)?;
+        Ok(())
+    }
+
+    /// Forgets the saved game, once it is over or replaced by a game not worth saving.
+    pub fn clear() -> Result<(), SettingsError> {
+        match fs::remove_file(Self::file_path()?) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_and_loads_the_game_with_its_settings() {
+        let directory = std::env::temp_dir().join(format!("recovery_{}", std::process::id()));
+        let file_path = directory.join(RECOVERY_FILE);
+        assert_eq!(Recovery::load_from(&file_path).unwrap(), None);
+
+        let recovery = Recovery {
+            pgn: String::from("[Variant \"Chess960\"]\n\n1. e4 *\n"),
+            opponent: Some(SavedOpponent {
+                engine_path: PathBuf::from("/usr/games/stockfish"),
+                engine_is_white: false,
+                level: Some(1500),
+                resign_score: Some(600),
+                book: BookSettings {
+                    path: Some(PathBuf::from("/books/performance.bin")),
+                    depth: 6,
+                    best_move_only: true,
+                },
+                options: BTreeMap::from([(String::from("Hash"), String::from("64"))]),
+            }),
+            time_spent: [61_500, 2_250],
+        };
+        recovery.save_to(&file_path).unwrap();
+        assert_eq!(Recovery::load_from(&file_path).unwrap(), Some(recovery));
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn loads_the_files_of_older_versions() {
+        let recovery: Recovery = toml::from_str(
+            "pgn = \"1. e4 *\"\n\n[opponent]\nengine_path = \"stockfish\"\nengine_is_white = true\n",
+        )
+        .unwrap();
+        let opponent = recovery.opponent.unwrap();
+        assert_eq!(opponent.book, BookSettings::default());
+        assert!(opponent.options.is_empty());
+        assert_eq!(recovery.time_spent, [0, 0]);
+    }
+}
@@ -0,0 +1,139 @@
+//! The user settings, kept in a TOML file in the configuration directory.
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::Protocol;
+
+const SETTINGS_DIRECTORY: &str = "chess_against_engine_rust";
+const SETTINGS_FILE: &str = "settings.toml";
+
+#[derive(Debug)]
+pub enum SettingsError {
+    NoConfigDirectory,
+    Io(io::Error),
+    Read(toml::de::Error),
+    Write(toml::ser::Error),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::NoConfigDirectory => write!(f, "no configuration directory was found"),
+            SettingsError::Io(err) => write!(f, "{err}"),
+            SettingsError::Read(err) => write!(f, "invalid settings file: {err}"),
+            SettingsError::Write(err) => write!(f, "cannot write the settings: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+impl From<io::Error> for SettingsError {
+    fn from(err: io::Error) -> Self {
+        SettingsError::Io(err)
+    }
+}
+
+/// A registered engine, identified by the path of its executable.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineSettings {
+    /// The name shown to the user, initially the name given by the engine.
+    #[serde(default)]
+    pub name: String,
+    pub path: PathBuf,
+    #[serde(default)]
+    pub protocol: Protocol,
+    #[serde(default)]
+    pub author: Option<String>,
+    /// The values of the options changed by the user, sent to the engine before each game.
+    #[serde(default)]
+    pub options: BTreeMap<String, String>,
+}
+
+/// The opening book of the engine opponent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookSettings {
+    /// A Polyglot book. None lets the engine search from the first move.
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// The book is followed during this number of moves of the game, at most.
+    #[serde(default = "default_book_depth")]
+    pub depth: usize,
+    /// Plays the most played move of the book rather than a random one.
+    #[serde(default)]
+    pub best_move_only: bool,
+}
+
+fn default_book_depth() -> usize {
+    10
+}
+
+impl Default for BookSettings {
+    fn default() -> Self {
+        Self {
+            path: None,
+            depth: default_book_depth(),
+            best_move_only: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub engines: Vec<EngineSettings>,
+    #[serde(default)]
+    pub book: BookSettings,
+    /// The directory of the Syzygy endgame tablebases.
+    #[serde(default)]
+    pub syzygy_path: Option<PathBuf>,
+    /// The PGN collection of the opening explorer.
+    #[serde(default)]
+    pub explorer_path: Option<PathBuf>,
+}
+
+impl Settings {
+    /// The directory of the application files, in the configuration directory.
+    pub fn directory() -> Result<PathBuf, SettingsError> {
+        dirs::config_dir()
+            .map(|directory| directory.join(SETTINGS_DIRECTORY))
+            .ok_or(SettingsError::NoConfigDirectory)
+    }
+
+    fn file_path() -> Result<PathBuf, SettingsError> {
+        Ok(Self::directory()?.join(SETTINGS_FILE))
+    }
+
+    /// Loads the saved settings, or the default settings if none were saved yet.
+    pub fn load() -> Result<Self, SettingsError> {
+        let content = match fs::read_to_string(Self::file_path()?) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err.into()),
+        };
+        toml::from_str(&content).map_err(SettingsError::Read)
+    }
+
+    pub fn save(&self) -> Result<(), SettingsError> {
+        let file_path = Self::file_path()?;
+        if let Some(directory) = file_path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let content = toml::to_string_pretty(self).map_err(SettingsError::Write)?;
+        fs::write(file_path, content)?;
+        Ok(())
+    }
+
+    pub fn engine(&self, path: &Path) -> Option<&EngineSettings> {
+        self.engines.iter().find(|engine| engine.path == *path)
+    }
+
+    pub fn engine_mut(&mut self, path: &Path) -> Option<&mut EngineSettings> {
+        self.engines.iter_mut().find(|engine| engine.path == *path)
+    }
+}
@@ -0,0 +1,70 @@
+//! Sets up a position with the edit mode, and checks whether it can be played.
+//!
+//! Run with `cargo run -p chessboard --example edit_position`.
+use chessboard::{Chessboard, ChessboardOptionsBuilder, MessageProducer};
+use iced::{
+    Length,
+    widget::{column, container, text},
+};
+
+fn main() -> iced::Result {
+    iced::run("Chessboard - edit position", App::update, App::view)
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    UpdatePosition(String),
+}
+
+struct App {
+    board_fen: String,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self {
+            board_fen: owlchess::RawBoard::empty().as_fen(),
+        }
+    }
+}
+
+impl App {
+    fn update(&mut self, message: Message) {
+        match message {
+            Message::UpdatePosition(new_position) => self.board_fen = new_position,
+        }
+    }
+
+    fn view(&self) -> iced::Element<'_, Message> {
+        // Outside of edit mode, try_new reports the positions which cannot be played.
+        let status = match Chessboard::try_new(
+            ChessboardOptionsBuilder::new()
+                .set_position(self.board_fen.clone())
+                .build(),
+            MessageProducer {
+                build_update_position: Message::UpdatePosition,
+            },
+        ) {
+            Ok(_) => String::from("This position can be played."),
+            Err(err) => format!("This position cannot be played: {err}"),
+        };
+
+        column![
+            container(Chessboard::new(
+                ChessboardOptionsBuilder::new()
+                    .set_position(self.board_fen.clone())
+                    .set_edit_mode(true)
+                    .build(),
+                MessageProducer {
+                    build_update_position: Message::UpdatePosition,
+                },
+            ))
+            .center(Length::Fill),
+            text(&self.board_fen),
+            text(status),
+        ]
+        .padding(10)
+        .spacing(10)
+        .into()
+    }
+}
@@ -0,0 +1,68 @@
+//! Both sides are played by the user, on a board with custom colors.
+//!
+//! Run with `cargo run -p chessboard --example free_play`.
+use chessboard::{Chessboard, ChessboardColorsBuilder, ChessboardOptionsBuilder, MessageProducer};
+use iced::{
+    Color, Length,
+    widget::{button, column, container, text},
+};
+
+fn main() -> iced::Result {
+    iced::run("Chessboard - free play", App::update, App::view)
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    ToggleBoardOrientation,
+    UpdatePosition(String),
+}
+
+struct App {
+    board_reversed: bool,
+    board_fen: String,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self {
+            board_reversed: false,
+            board_fen: owlchess::Board::initial().as_fen(),
+        }
+    }
+}
+
+impl App {
+    fn update(&mut self, message: Message) {
+        match message {
+            Message::ToggleBoardOrientation => self.board_reversed = !self.board_reversed,
+            Message::UpdatePosition(new_position) => self.board_fen = new_position,
+        }
+    }
+
+    fn view(&self) -> iced::Element<'_, Message> {
+        let colors = ChessboardColorsBuilder::new()
+            .set_white_cell(Color::from_rgb8(238, 238, 210))
+            .set_black_cell(Color::from_rgb8(118, 150, 86))
+            .set_background(Color::from_rgb8(49, 46, 43))
+            .build();
+
+        column![
+            button(text("Flip board")).on_press(Message::ToggleBoardOrientation),
+            container(Chessboard::new(
+                ChessboardOptionsBuilder::new()
+                    .set_colors(colors)
+                    .set_reversed(self.board_reversed)
+                    .set_position(self.board_fen.clone())
+                    .build(),
+                MessageProducer {
+                    build_update_position: Message::UpdatePosition,
+                },
+            ))
+            .center(Length::Fill),
+            text(&self.board_fen),
+        ]
+        .padding(10)
+        .spacing(10)
+        .into()
+    }
+}
@@ -10,6 +10,8 @@ pub struct ChessboardColors {
     pub coordinates: Color,
     pub white_turn: Color,
     pub black_turn: Color,
+    /// Drawn over the highlighted cells, so it should be translucent.
+    pub highlight: Color,
 }
 
 /// Defaults colors for a [`super::Chessboard`].
@@ -22,62 +24,60 @@ impl Default for ChessboardColors {
             coordinates: Color::from_rgb8(255, 215, 0),  // gold
             white_turn: Color::WHITE,
             black_turn: Color::BLACK,
+            highlight: Color::from_rgba8(50, 205, 50, 0.6), // limegreen
         }
     }
 }
 
 /// Builds a [`ChessboardColors`] and lets you override the colors you need.
+#[derive(Default)]
 pub struct ChessboardColorsBuilder {
-    #[allow(unused)]
     colors: ChessboardColors,
 }
 
 impl ChessboardColorsBuilder {
-    #[allow(unused)]
     pub fn new() -> Self {
         ChessboardColorsBuilder {
             colors: ChessboardColors::default(),
         }
     }
 
-    #[allow(unused)]
     pub fn build(&self) -> ChessboardColors {
         self.colors.clone()
     }
 
-    #[allow(unused)]
     pub fn set_background(&mut self, color: Color) -> &mut Self {
         self.colors.background = color;
         self
     }
 
-    #[allow(unused)]
     pub fn set_white_cell(&mut self, color: Color) -> &mut Self {
         self.colors.white_cell = color;
         self
     }
 
-    #[allow(unused)]
     pub fn set_black_cell(&mut self, color: Color) -> &mut Self {
         self.colors.black_cell = color;
         self
     }
 
-    #[allow(unused)]
     pub fn set_coordinates(&mut self, color: Color) -> &mut Self {
         self.colors.coordinates = color;
         self
     }
 
-    #[allow(unused)]
     pub fn set_white_turn(&mut self, color: Color) -> &mut Self {
         self.colors.white_turn = color;
         self
     }
 
-    #[allow(unused)]
     pub fn set_black_turn(&mut self, color: Color) -> &mut Self {
         self.colors.black_turn = color;
         self
     }
+
+    pub fn set_highlight(&mut self, color: Color) -> &mut Self {
+        self.colors.highlight = color;
+        self
+    }
 }
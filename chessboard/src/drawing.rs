@@ -1,20 +1,21 @@
 use iced::{
-    Border, Color, Pixels, Point, Rectangle, Shadow,
+    Border, Color, Pixels, Point, Rectangle, Shadow, Vector,
     advanced::{
         Text,
+        graphics::geometry,
         renderer::Quad,
         svg::{self, Svg},
     },
     alignment::{Horizontal, Vertical},
     border::Radius,
-    widget::text::{LineHeight, Shaping, Wrapping},
+    widget::{
+        canvas::{Frame, Path},
+        text::{LineHeight, Shaping, Wrapping},
+    },
 };
 use owlchess::{File, Rank};
 
-use crate::{
-    Chessboard,
-    gui::widgets::chessboard::{DndData, PendingPromotion},
-};
+use crate::{Chessboard, ChessboardError, DndData, PALETTE_PIECES, PendingPromotion};
 
 impl<UPM> Chessboard<UPM> {
     pub(crate) fn draw_background(
@@ -24,7 +25,7 @@ impl<UPM> Chessboard<UPM> {
     ) {
         renderer.fill_quad(
             Quad {
-                bounds: bounds,
+                bounds,
                 border: Border::default(),
                 shadow: Shadow::default(),
             },
@@ -69,13 +70,12 @@ impl<UPM> Chessboard<UPM> {
 
     pub(crate) fn draw_pieces(
         &self,
+        board_logic: &owlchess::RawBoard,
         bounds: Rectangle,
-        renderer: &mut (impl iced::advanced::Renderer + iced::advanced::svg::Renderer),
+        renderer: &mut impl iced::advanced::svg::Renderer,
     ) {
         let common_size = bounds.size().width;
         let cell_size = common_size / 9.0;
-        let board_logic = owlchess::Board::from_fen(&self.fen)
-            .expect(format!("invalid fen {}", self.fen).as_str());
 
         for row in 0..8 {
             for col in 0..8 {
@@ -85,6 +85,7 @@ impl<UPM> Chessboard<UPM> {
                     Some(DndData {
                         start_file,
                         start_rank,
+                        from_palette: false,
                         ..
                     }) => (file == start_file) && (rank == start_rank),
                     _ => false,
@@ -126,7 +127,7 @@ impl<UPM> Chessboard<UPM> {
         piece_type: owlchess::Piece,
         piece_color: owlchess::Color,
         bounds: Rectangle,
-        renderer: &mut (impl iced::advanced::Renderer + iced::advanced::svg::Renderer),
+        renderer: &mut impl iced::advanced::svg::Renderer,
     ) {
         let piece_svg = self.piece_to_svg(piece_type, piece_color);
         renderer.draw_svg(piece_svg, bounds);
@@ -168,7 +169,7 @@ impl<UPM> Chessboard<UPM> {
     pub(crate) fn draw_coordinates(
         &self,
         bounds: Rectangle,
-        renderer: &mut (impl iced::advanced::Renderer + iced::advanced::text::Renderer),
+        renderer: &mut impl iced::advanced::text::Renderer,
         viewport: &Rectangle,
     ) {
         let common_size = bounds.size().width;
@@ -181,7 +182,7 @@ impl<UPM> Chessboard<UPM> {
 
         for col in 0..8 {
             let file = if self.reversed { 7 - col } else { col };
-            let letter = (('A' as u8) + file) as char;
+            let letter = (b'A' + file) as char;
 
             let text_position_1 = Point {
                 x: bounds.x + cell_size * (0.855 + col as f32),
@@ -190,7 +191,7 @@ impl<UPM> Chessboard<UPM> {
 
             let text_position_2 = Point {
                 x: bounds.x + cell_size * (0.855 + col as f32),
-                y: bounds.y + cell_size * 8.500_000_01,
+                y: bounds.y + cell_size * 8.5,
             };
 
             renderer.fill_text(
@@ -200,7 +201,7 @@ impl<UPM> Chessboard<UPM> {
                     bounds: bounds.size(),
                     size: Pixels(width),
                     line_height: LineHeight::Absolute(Pixels(height)),
-                    font: font,
+                    font,
                     horizontal_alignment: Horizontal::Left,
                     vertical_alignment: Vertical::Top,
                     shaping: Shaping::default(),
@@ -217,7 +218,7 @@ impl<UPM> Chessboard<UPM> {
                     bounds: bounds.size(),
                     size: Pixels(width),
                     line_height: LineHeight::Absolute(Pixels(height)),
-                    font: font,
+                    font,
                     horizontal_alignment: Horizontal::Left,
                     vertical_alignment: Vertical::Top,
                     shaping: Shaping::default(),
@@ -230,7 +231,7 @@ impl<UPM> Chessboard<UPM> {
 
         for row in 0..8 {
             let rank = if self.reversed { 7 - row } else { row };
-            let digit = (('1' as u8) + rank) as char;
+            let digit = (b'1' + rank) as char;
 
             let text_position_1 = Point {
                 x: bounds.x + cell_size * 0.15,
@@ -249,7 +250,7 @@ impl<UPM> Chessboard<UPM> {
                     bounds: bounds.size(),
                     size: Pixels(width),
                     line_height: LineHeight::Absolute(Pixels(height)),
-                    font: font,
+                    font,
                     horizontal_alignment: Horizontal::Left,
                     vertical_alignment: Vertical::Top,
                     shaping: Shaping::default(),
@@ -266,7 +267,7 @@ impl<UPM> Chessboard<UPM> {
                     bounds: bounds.size(),
                     size: Pixels(width),
                     line_height: LineHeight::Absolute(Pixels(height)),
-                    font: font,
+                    font,
                     horizontal_alignment: Horizontal::Left,
                     vertical_alignment: Vertical::Top,
                     shaping: Shaping::default(),
@@ -280,14 +281,14 @@ impl<UPM> Chessboard<UPM> {
 
     pub(crate) fn draw_player_turn(
         &self,
+        board_logic: &owlchess::RawBoard,
         bounds: Rectangle,
         renderer: &mut impl iced::advanced::Renderer,
     ) {
         let common_size = bounds.size().width;
         let cell_size = common_size / 9.0;
 
-        let board_logic = owlchess::Board::from_fen(&self.fen).expect("invalid fen");
-        let is_white_turn = board_logic.side() == owlchess::Color::White;
+        let is_white_turn = board_logic.side == owlchess::Color::White;
 
         let x_factor = if self.reversed { 0.025 } else { 8.5 };
         let y_factor = if self.reversed {
@@ -330,10 +331,121 @@ impl<UPM> Chessboard<UPM> {
         );
     }
 
+    /// The center of the cell, relative to the board bounds.
+    fn cell_center(&self, coord: owlchess::Coord, cell_size: f32) -> Point {
+        let file = coord.file().index() as f32;
+        let rank = coord.rank().index() as f32;
+        let col = if self.reversed { 7.0 - file } else { file };
+        let row = if self.reversed { 7.0 - rank } else { rank };
+        Point::new(cell_size * (1.0 + col), cell_size * (1.0 + row))
+    }
+
+    pub(crate) fn draw_highlighted_cells(
+        &self,
+        bounds: Rectangle,
+        renderer: &mut impl iced::advanced::Renderer,
+    ) {
+        let cell_size = bounds.size().width / 9.0;
+        for cell in &self.highlighted_cells {
+            let center = self.cell_center(*cell, cell_size);
+            renderer.fill_quad(
+                Quad {
+                    bounds: Rectangle {
+                        x: bounds.x + center.x - cell_size / 2.0,
+                        y: bounds.y + center.y - cell_size / 2.0,
+                        width: cell_size,
+                        height: cell_size,
+                    },
+                    border: Border::default(),
+                    shadow: Shadow::default(),
+                },
+                self.colors.highlight,
+            );
+        }
+    }
+
+    pub(crate) fn draw_arrows(&self, bounds: Rectangle, renderer: &mut impl geometry::Renderer) {
+        if self.arrows.is_empty() {
+            return;
+        }
+        let cell_size = bounds.size().width / 9.0;
+        let shaft_width = cell_size * 0.18;
+        let head_width = cell_size * 0.45;
+        let head_length = cell_size * 0.4;
+
+        let mut frame = Frame::new(renderer, bounds.size());
+        for arrow in &self.arrows {
+            let start = self.cell_center(arrow.from, cell_size);
+            let end = self.cell_center(arrow.to, cell_size);
+            let length = start.distance(end);
+            if length <= head_length {
+                continue;
+            }
+            let direction = (end - start) * (1.0 / length);
+            let normal = Vector::new(-direction.y, direction.x);
+            let neck = end - direction * head_length;
+            let shaft_side = normal * (shaft_width / 2.0);
+            let head_side = normal * (head_width / 2.0);
+
+            let outline = Path::new(|path| {
+                path.move_to(start + shaft_side);
+                path.line_to(neck + shaft_side);
+                path.line_to(neck + head_side);
+                path.line_to(end);
+                path.line_to(neck - head_side);
+                path.line_to(neck - shaft_side);
+                path.line_to(start - shaft_side);
+                path.close();
+            });
+            frame.fill(&outline, arrow.color);
+        }
+        renderer.with_translation(Vector::new(bounds.x, bounds.y), |renderer| {
+            renderer.draw_geometry(frame.into_geometry());
+        });
+    }
+
+    pub(crate) fn draw_palette(
+        &self,
+        bounds: Rectangle,
+        renderer: &mut impl iced::advanced::svg::Renderer,
+    ) {
+        for color in [owlchess::Color::White, owlchess::Color::Black] {
+            for (index, piece_type) in PALETTE_PIECES.iter().enumerate() {
+                let is_dragged_piece = match self.dnd_data {
+                    Some(DndData {
+                        from_palette: true,
+                        piece_type: dragged_type,
+                        piece_color: dragged_color,
+                        ..
+                    }) => dragged_type == *piece_type && dragged_color == color,
+                    _ => false,
+                };
+                let cell_bounds = Chessboard::<UPM>::get_palette_cell_bounds(bounds, index, color);
+                renderer.fill_quad(
+                    Quad {
+                        bounds: cell_bounds,
+                        border: Border {
+                            color: self.colors.coordinates,
+                            width: 1.0,
+                            radius: Radius::default(),
+                        },
+                        shadow: Shadow::default(),
+                    },
+                    if is_dragged_piece {
+                        self.colors.black_cell
+                    } else {
+                        self.colors.white_cell
+                    },
+                );
+                self.draw_single_piece(*piece_type, color, cell_bounds, renderer);
+            }
+        }
+    }
+
     pub(crate) fn draw_dragged_piece(
         &self,
         bounds: Rectangle,
-        renderer: &mut (impl iced::advanced::Renderer + iced::advanced::svg::Renderer),
+        renderer: &mut impl iced::advanced::svg::Renderer,
     ) {
         if let Some(dnd_data) = self.dnd_data.clone() {
             let common_size = bounds.size().width;
@@ -354,7 +466,7 @@ impl<UPM> Chessboard<UPM> {
     pub(crate) fn draw_pending_promotion_piece(
         &self,
         bounds: Rectangle,
-        renderer: &mut (impl iced::advanced::Renderer + iced::advanced::svg::Renderer),
+        renderer: &mut impl iced::advanced::svg::Renderer,
     ) {
         if let Some(pending_promotion) = self.pending_promotion.clone() {
             let common_size = bounds.size().width;
@@ -374,8 +486,9 @@ impl<UPM> Chessboard<UPM> {
 
     pub(crate) fn draw_promotion_selector(
         &self,
+        board_logic: &owlchess::RawBoard,
         bounds: Rectangle,
-        renderer: &mut (impl iced::advanced::Renderer + iced::advanced::svg::Renderer),
+        renderer: &mut impl iced::advanced::svg::Renderer,
     ) {
         if let Some(pending_promotion) = self.pending_promotion.clone() {
             let common_size = bounds.size().width;
@@ -400,9 +513,7 @@ impl<UPM> Chessboard<UPM> {
                 selector_background,
             );
 
-            let board_logic = owlchess::Board::from_fen(&self.fen)
-                .expect(format!("invalid fen {}", self.fen).as_str());
-            let is_white_turn = board_logic.side() == owlchess::Color::White;
+            let is_white_turn = board_logic.side == owlchess::Color::White;
 
             let queen_svg_handle = if is_white_turn {
                 self.images.white_queen_handle.clone()
@@ -449,4 +560,46 @@ impl<UPM> Chessboard<UPM> {
             );
         }
     }
+
+    pub(crate) fn draw_error_placeholder(
+        &self,
+        error: &ChessboardError,
+        bounds: Rectangle,
+        renderer: &mut impl iced::advanced::text::Renderer,
+        viewport: &Rectangle,
+    ) {
+        let cell_size = bounds.size().width / 9.0;
+        let font = renderer.default_font();
+        let text_size = cell_size * 0.3;
+
+        let text_bounds = Rectangle {
+            x: bounds.x + cell_size * 0.5,
+            y: bounds.y + cell_size * 0.5,
+            width: cell_size * 8.0,
+            height: cell_size * 8.0,
+        };
+        renderer.fill_quad(
+            Quad {
+                bounds: text_bounds,
+                ..Default::default()
+            },
+            self.colors.black_cell,
+        );
+        renderer.fill_text(
+            Text {
+                wrapping: Wrapping::Word,
+                content: format!("Cannot show the position\n{error}"),
+                bounds: text_bounds.size(),
+                size: Pixels(text_size),
+                line_height: LineHeight::default(),
+                font,
+                horizontal_alignment: Horizontal::Center,
+                vertical_alignment: Vertical::Center,
+                shaping: Shaping::default(),
+            },
+            text_bounds.center(),
+            self.colors.coordinates,
+            *viewport,
+        );
+    }
 }
@@ -0,0 +1,39 @@
+//! Errors raised when building a [`super::Chessboard`].
+use std::fmt;
+
+use owlchess::board::{FenParseError, RawFenParseError, ValidateError};
+
+/// The position given to a [`super::Chessboard`] cannot be shown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChessboardError {
+    /// The FEN could not be parsed.
+    InvalidFen(RawFenParseError),
+    /// The FEN was parsed, but the position is not legal (play mode only).
+    IllegalPosition(ValidateError),
+}
+
+impl fmt::Display for ChessboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChessboardError::InvalidFen(err) => write!(f, "invalid fen: {err}"),
+            ChessboardError::IllegalPosition(err) => write!(f, "illegal position: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ChessboardError {}
+
+impl From<RawFenParseError> for ChessboardError {
+    fn from(err: RawFenParseError) -> Self {
+        ChessboardError::InvalidFen(err)
+    }
+}
+
+impl From<FenParseError> for ChessboardError {
+    fn from(err: FenParseError) -> Self {
+        match err {
+            FenParseError::Fen(err) => ChessboardError::InvalidFen(err),
+            FenParseError::Valid(err) => ChessboardError::IllegalPosition(err),
+        }
+    }
+}
@@ -0,0 +1,423 @@
+use iced::{
+    Rectangle,
+    advanced::{Clipboard, Layout, clipboard, graphics::core::event, mouse},
+    keyboard::{Key, Modifiers},
+};
+
+use crate::{Chessboard, DndData, PendingPromotion, Position, PromotionPiece, get_coord};
+
+/// The position after dropping the dragged piece in edit mode, on the target cell or off the
+/// board (which removes it). None when a piece of the palette is dropped off the board.
+fn drop_setup_piece(
+    raw_board: &owlchess::RawBoard,
+    dnd_data: &DndData,
+    target_cell: Option<(i8, i8)>,
+) -> Option<owlchess::RawBoard> {
+    if dnd_data.from_palette && target_cell.is_none() {
+        return None;
+    }
+    let mut board_logic = *raw_board;
+    if !dnd_data.from_palette {
+        board_logic.put(
+            get_coord(dnd_data.start_file, dnd_data.start_rank),
+            owlchess::Cell::EMPTY,
+        );
+    }
+    if let Some((file, rank)) = target_cell {
+        board_logic.put(
+            get_coord(file as u8, rank as u8),
+            owlchess::Cell::from_parts(dnd_data.piece_color, dnd_data.piece_type),
+        );
+    }
+    Some(board_logic)
+}
+
+impl<UPM> Chessboard<UPM> {
+    pub(crate) fn handle_button_pressed(
+        &mut self,
+        event: iced::Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        shell: &mut iced::advanced::Shell<'_, UPM>,
+    ) {
+        if let iced::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) = event {
+            // Position relative to the component
+            let overlapping_board_position = cursor.position_in(layout.bounds());
+            if let Some(position) = overlapping_board_position {
+                if let Some(pending_promotion) = self.pending_promotion.clone() {
+                    let overlapping_queen_button_position =
+                        cursor.position_in(pending_promotion.queen_button_bounds);
+                    let overlapping_rook_button_position =
+                        cursor.position_in(pending_promotion.rook_button_bounds);
+                    let overlapping_bishop_button_position =
+                        cursor.position_in(pending_promotion.bishop_button_bounds);
+                    let overlapping_knight_button_position =
+                        cursor.position_in(pending_promotion.knight_button_bounds);
+
+                    if overlapping_queen_button_position.is_some() {
+                        self.commit_promotion(PromotionPiece::Queen, shell);
+                        self.pending_promotion = None;
+                    } else if overlapping_rook_button_position.is_some() {
+                        self.commit_promotion(PromotionPiece::Rook, shell);
+                        self.pending_promotion = None;
+                    } else if overlapping_bishop_button_position.is_some() {
+                        self.commit_promotion(PromotionPiece::Bishop, shell);
+                        self.pending_promotion = None;
+                    } else if overlapping_knight_button_position.is_some() {
+                        self.commit_promotion(PromotionPiece::Knight, shell);
+                        self.pending_promotion = None;
+                    }
+                } else {
+                    let (file, rank) = self.get_file_and_rank(position, layout.bounds());
+                    if Chessboard::<UPM>::in_cell_bounds(file, rank)
+                        && let Ok(Position::Game {
+                            board: board_logic, ..
+                        }) = &self.position
+                    {
+                        let file = file as u8;
+                        let rank = rank as u8;
+                        let matching_cell = board_logic.get(get_coord(file, rank));
+                        let piece_color = matching_cell.color();
+                        let piece_type = matching_cell.piece();
+                        let dnd_position = cursor.position_over(layout.bounds());
+                        let is_white_turn = board_logic.side() == owlchess::Color::White;
+                        if let Some(piece_color) = piece_color
+                        && let Some(piece_type) = piece_type
+                        && let Some(dnd_position) = dnd_position
+                        // is it our piece ?
+                        && is_white_turn == (piece_color == owlchess::Color::White)
+                        {
+                            self.dnd_data = Some(DndData {
+                                start_file: file,
+                                start_rank: rank,
+                                from_palette: false,
+                                location: dnd_position,
+                                piece_color,
+                                piece_type,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn handle_button_released(
+        &mut self,
+        event: iced::Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        shell: &mut iced::advanced::Shell<'_, UPM>,
+    ) {
+        let is_pending_promotion = self.pending_promotion.is_some();
+        if is_pending_promotion {
+            return;
+        }
+        if let iced::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) = event {
+            // Position relative to the component
+            let overlapping_board_position = cursor.position_in(layout.bounds());
+            if let Some(position) = overlapping_board_position {
+                if self.dnd_data.is_some() {
+                    let (file, rank) = self.get_file_and_rank(position, layout.bounds());
+                    if Chessboard::<UPM>::in_cell_bounds(file, rank)
+                        && let Ok(position) = &self.position
+                    {
+                        let dnd_data_clone = self.dnd_data.clone().unwrap();
+                        let start_file = dnd_data_clone.start_file;
+                        let start_rank = dnd_data_clone.start_rank;
+                        let end_file = file as u8;
+                        let end_rank = rank as u8;
+
+                        let is_promotion_move = position
+                            .find_legal_move(
+                                start_file,
+                                start_rank,
+                                end_file,
+                                end_rank,
+                                Some(PromotionPiece::Queen),
+                            )
+                            .is_some();
+
+                        if is_promotion_move {
+                            let is_white_turn = position.raw().side == owlchess::Color::White;
+                            let piece_color = if is_white_turn {
+                                owlchess::Color::White
+                            } else {
+                                owlchess::Color::Black
+                            };
+                            let dnd_data_clone = self.dnd_data.clone().unwrap();
+                            let location = dnd_data_clone.location;
+                            let start_file = dnd_data_clone.start_file;
+                            let start_rank = dnd_data_clone.start_rank;
+
+                            self.dnd_data = None;
+                            let bounds = layout.bounds();
+                            let cell_size = bounds.size().width / 9.0;
+                            let queen_button_bounds = Rectangle {
+                                x: bounds.x + cell_size * 1.0,
+                                y: bounds.y + cell_size * 4.0,
+                                width: cell_size,
+                                height: cell_size,
+                            };
+                            let rook_button_bounds = Rectangle {
+                                x: bounds.x + cell_size * 3.0,
+                                y: bounds.y + cell_size * 4.0,
+                                width: cell_size,
+                                height: cell_size,
+                            };
+                            let bishop_button_bounds = Rectangle {
+                                x: bounds.x + cell_size * 5.0,
+                                y: bounds.y + cell_size * 4.0,
+                                width: cell_size,
+                                height: cell_size,
+                            };
+                            let knight_button_bounds = Rectangle {
+                                x: bounds.x + cell_size * 7.0,
+                                y: bounds.y + cell_size * 4.0,
+                                width: cell_size,
+                                height: cell_size,
+                            };
+                            self.pending_promotion = Some(PendingPromotion {
+                                piece_color,
+                                location,
+                                start_file,
+                                start_rank,
+                                end_file,
+                                end_rank,
+                                queen_button_bounds,
+                                rook_button_bounds,
+                                bishop_button_bounds,
+                                knight_button_bounds,
+                            });
+                        } else {
+                            let matching_legal_move = position
+                                .find_legal_move(start_file, start_rank, end_file, end_rank, None);
+                            if let Some(matching_legal_move) = matching_legal_move
+                                && let Position::Game { board, .. } = position
+                            {
+                                let resulting_board_logic = board.make_move(matching_legal_move);
+                                if let Ok(resulting_board_logic) = resulting_board_logic {
+                                    let new_fen = resulting_board_logic.as_fen();
+                                    let update_message =
+                                        (self.messages_producer.build_update_position)(new_fen);
+                                    self.dnd_data = None;
+                                    shell.publish(update_message);
+                                }
+                            } else {
+                                if let Some(build_castling) = self.build_castling
+                                    && let Some((king, rook)) = position
+                                        .find_castling(start_file, start_rank, end_file, end_rank)
+                                {
+                                    shell.publish(build_castling(king, rook));
+                                }
+                                self.dnd_data = None;
+                            }
+                        }
+                    } else {
+                        self.dnd_data = None;
+                    }
+                }
+            } else {
+                self.dnd_data = None;
+            }
+        }
+    }
+
+    pub(crate) fn handle_mouse_moved(
+        &mut self,
+        event: iced::Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+    ) {
+        let is_pending_promotion = self.pending_promotion.is_some();
+        if is_pending_promotion {
+            return;
+        }
+        if let iced::Event::Mouse(mouse::Event::CursorMoved { position: _ }) = event {
+            // Position relative to the component
+            let position = cursor.position_in(layout.bounds());
+            if let Some(position) = position {
+                let (file, rank) = self.get_file_and_rank(position, layout.bounds());
+                let dnd_position = cursor.position_over(layout.bounds());
+                if Chessboard::<UPM>::in_cell_bounds(file, rank)
+                    && let Some(dnd_position) = dnd_position
+                    && let Some(dnd_data) = self.dnd_data.as_mut()
+                {
+                    dnd_data.location = dnd_position;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn handle_editor_button_pressed(
+        &mut self,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+    ) {
+        let dnd_position = cursor.position_over(layout.bounds());
+        if let Some(dnd_position) = dnd_position {
+            if let Some((piece_type, piece_color)) =
+                Chessboard::<UPM>::get_palette_piece(cursor, layout.bounds())
+            {
+                self.dnd_data = Some(DndData {
+                    start_file: 0,
+                    start_rank: 0,
+                    from_palette: true,
+                    location: dnd_position,
+                    piece_type,
+                    piece_color,
+                });
+                return;
+            }
+
+            let position = cursor.position_in(layout.bounds());
+            if let Some(position) = position {
+                let (file, rank) = self.get_file_and_rank(position, layout.bounds());
+                if Chessboard::<UPM>::in_cell_bounds(file, rank)
+                    && let Ok(position) = &self.position
+                {
+                    let file = file as u8;
+                    let rank = rank as u8;
+                    let board_logic = position.raw();
+                    let matching_cell = board_logic.get(get_coord(file, rank));
+                    if let Some(piece_color) = matching_cell.color()
+                        && let Some(piece_type) = matching_cell.piece()
+                    {
+                        self.dnd_data = Some(DndData {
+                            start_file: file,
+                            start_rank: rank,
+                            from_palette: false,
+                            location: dnd_position,
+                            piece_type,
+                            piece_color,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn handle_editor_button_released(
+        &mut self,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        shell: &mut iced::advanced::Shell<'_, UPM>,
+    ) {
+        if let Some(dnd_data) = self.dnd_data.take()
+            && let Ok(position) = &self.position
+        {
+            // Dropping a piece outside of the cells removes it from the board.
+            let target_cell = cursor
+                .position_in(layout.bounds())
+                .map(|position| self.get_file_and_rank(position, layout.bounds()))
+                .filter(|(file, rank)| Chessboard::<UPM>::in_cell_bounds(*file, *rank));
+
+            if let Some(board_logic) = drop_setup_piece(position.raw(), &dnd_data, target_cell) {
+                let new_fen = board_logic.as_fen();
+                let update_message = (self.messages_producer.build_update_position)(new_fen);
+                shell.publish(update_message);
+            }
+        }
+    }
+
+    pub(crate) fn handle_editor_mouse_moved(&mut self, layout: Layout<'_>, cursor: mouse::Cursor) {
+        let dnd_position = cursor.position_over(layout.bounds());
+        if let Some(dnd_position) = dnd_position
+            && let Some(dnd_data) = self.dnd_data.as_mut()
+        {
+            dnd_data.location = dnd_position;
+        }
+    }
+
+    pub(crate) fn handle_key_pressed(
+        &mut self,
+        key: Key,
+        modifiers: Modifiers,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut iced::advanced::Shell<'_, UPM>,
+    ) -> event::Status {
+        // Shortcuts only apply to the hovered board, so that they don't collide with text inputs.
+        if !modifiers.command() || !cursor.is_over(layout.bounds()) {
+            return event::Status::Ignored;
+        }
+        match key.as_ref() {
+            Key::Character("c") => {
+                if let Ok(position) = &self.position {
+                    clipboard.write(clipboard::Kind::Standard, position.raw().as_fen());
+                }
+                event::Status::Captured
+            }
+            Key::Character("v") => {
+                if let Some(text) = clipboard.read(clipboard::Kind::Standard) {
+                    match self.build_paste {
+                        Some(build_paste) => shell.publish(build_paste(text)),
+                        None => {
+                            if let Ok(position) =
+                                Chessboard::<UPM>::parse_position(text.trim(), self.edit_mode)
+                            {
+                                let new_fen = position.raw().as_fen();
+                                let update_message =
+                                    (self.messages_producer.build_update_position)(new_fen);
+                                shell.publish(update_message);
+                            }
+                        }
+                    }
+                }
+                event::Status::Captured
+            }
+            _ => event::Status::Ignored,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iced::Point;
+
+    use super::*;
+
+    fn dragged(from_palette: bool, file: u8, rank: u8, piece: owlchess::Piece) -> DndData {
+        DndData {
+            start_file: file,
+            start_rank: rank,
+            from_palette,
+            location: Point::ORIGIN,
+            piece_type: piece,
+            piece_color: owlchess::Color::White,
+        }
+    }
+
+    #[test]
+    fn dropping_a_piece_off_the_board_removes_it() {
+        let initial = *owlchess::Board::initial().raw();
+        // The queen on d1 is dragged out of the cells.
+        let dnd_data = dragged(false, 3, 0, owlchess::Piece::Queen);
+        let board = drop_setup_piece(&initial, &dnd_data, None).unwrap();
+        assert_eq!(
+            board.as_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1"
+        );
+    }
+
+    #[test]
+    fn dropping_a_piece_on_a_cell_moves_it() {
+        let initial = *owlchess::Board::initial().raw();
+        let dnd_data = dragged(false, 3, 0, owlchess::Piece::Queen);
+        let board = drop_setup_piece(&initial, &dnd_data, Some((3, 4))).unwrap();
+        assert_eq!(
+            board.as_fen(),
+            "rnbqkbnr/pppppppp/8/3Q4/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1"
+        );
+    }
+
+    #[test]
+    fn palette_pieces_are_added_only_on_the_board() {
+        let empty = owlchess::RawBoard::empty();
+        let dnd_data = dragged(true, 0, 0, owlchess::Piece::Knight);
+        assert!(drop_setup_piece(&empty, &dnd_data, None).is_none());
+        let board = drop_setup_piece(&empty, &dnd_data, Some((6, 0))).unwrap();
+        assert_eq!(board.as_fen(), "8/8/8/8/8/8/8/6N1 w - - 0 1");
+    }
+}
@@ -0,0 +1,662 @@
+//! A chessboard component for [iced](https://iced.rs).
+//!
+//! The component shows a position given as a FEN string, and lets the user play
+//! legal moves by drag and drop (with a promotion piece selector).
+//! Each time the position changes, a message built by the [`MessageProducer`] is
+//! published with the new FEN : the application is expected to store it and
+//! give it back through the [`ChessboardOptions`] on the next view.
+//!
+//! While the cursor is over the component, Ctrl+C copies the position as FEN, and
+//! Ctrl+V pastes a position (see [`Chessboard::on_paste`]).
+//!
+//! Dropping the king onto a rook of its side can be reported as castling (see
+//! [`Chessboard::on_castling`]), which is how Chess960 castling is played.
+//!
+//! Arrows can be drawn over the pieces (see [`ChessboardOptionsBuilder::set_arrows`]) and cells
+//! can be highlighted (see [`ChessboardOptionsBuilder::set_highlighted_cells`]), for instance in
+//! order to show the moves suggested by an engine.
+//!
+//! In edit mode, pieces can be moved freely, added from a palette or removed by
+//! dragging them off the board, so that any position can be set up.
+//!
+//! ```no_run
+//! use chessboard::{Chessboard, ChessboardOptionsBuilder, MessageProducer};
+//!
+//! #[derive(Debug, Clone)]
+//! enum Message {
+//!     UpdatePosition(String),
+//! }
+//!
+//! fn view(fen: &str) -> iced::Element<'_, Message> {
+//!     Chessboard::new(
+//!         ChessboardOptionsBuilder::new()
+//!             .set_position(fen.to_string())
+//!             .build(),
+//!         MessageProducer {
+//!             build_update_position: Message::UpdatePosition,
+//!         },
+//!     )
+//!     .into()
+//! }
+//! ```
+mod colors;
+mod drawing;
+mod error;
+mod event_handling;
+mod options;
+mod pieces_images;
+
+pub use colors::{ChessboardColors, ChessboardColorsBuilder};
+pub use error::ChessboardError;
+pub use options::{Arrow, ChessboardOptions, ChessboardOptionsBuilder};
+
+use iced::{
+    Element, Length, Point, Rectangle, Size, Theme,
+    advanced::{
+        Layout, Widget,
+        graphics::core::event,
+        layout, mouse,
+        renderer::{self},
+        widget::Tree,
+    },
+    keyboard,
+};
+
+use crate::pieces_images::{PIECES_IMAGES, PiecesImages};
+
+/// Pieces offered by the palette in edit mode, one row per color.
+const PALETTE_PIECES: [owlchess::Piece; 6] = [
+    owlchess::Piece::King,
+    owlchess::Piece::Queen,
+    owlchess::Piece::Rook,
+    owlchess::Piece::Bishop,
+    owlchess::Piece::Knight,
+    owlchess::Piece::Pawn,
+];
+
+/// Height of the component, in cells, when the palette is shown below the board.
+const EDIT_MODE_HEIGHT_IN_CELLS: f32 = 11.0;
+
+/// The position shown by the component, parsed once when it is built.
+#[derive(Debug, Clone)]
+enum Position {
+    /// A legal position, on which moves can be played.
+    Game {
+        board: owlchess::Board,
+        /// Computed once, so that hit-testing during drags stays cheap.
+        legal_moves: Vec<owlchess::Move>,
+    },
+    /// A position being edited, which may be illegal.
+    Setup(owlchess::RawBoard),
+}
+
+impl Position {
+    fn raw(&self) -> &owlchess::RawBoard {
+        match self {
+            Position::Game { board, .. } => board.raw(),
+            Position::Setup(raw_board) => raw_board,
+        }
+    }
+
+    /// The legal move between both cells, if any.
+    /// A promotion piece is needed in order to match a promotion move.
+    fn find_legal_move(
+        &self,
+        start_file: u8,
+        start_rank: u8,
+        end_file: u8,
+        end_rank: u8,
+        promotion_piece: Option<PromotionPiece>,
+    ) -> Option<owlchess::Move> {
+        let Position::Game { legal_moves, .. } = self else {
+            return None;
+        };
+        let start = get_coord(start_file, start_rank);
+        let end = get_coord(end_file, end_rank);
+        let promotion_piece = promotion_piece.map(|piece| piece.to_piece());
+        legal_moves
+            .iter()
+            .find(|legal_move| {
+                legal_move.src() == start
+                    && legal_move.dst() == end
+                    && legal_move.kind().promote() == promotion_piece
+            })
+            .copied()
+    }
+
+    /// The king and the rook, when the king is dropped onto a rook of its side.
+    fn find_castling(
+        &self,
+        start_file: u8,
+        start_rank: u8,
+        end_file: u8,
+        end_rank: u8,
+    ) -> Option<(owlchess::Coord, owlchess::Coord)> {
+        let Position::Game { board, .. } = self else {
+            return None;
+        };
+        let king = get_coord(start_file, start_rank);
+        let rook = get_coord(end_file, end_rank);
+        let side = board.side();
+        (board.get(king) == owlchess::Cell::from_parts(side, owlchess::Piece::King)
+            && board.get(rook) == owlchess::Cell::from_parts(side, owlchess::Piece::Rook))
+        .then_some((king, rook))
+    }
+}
+
+/// Converts cell indices (rank 0 being the first rank) into a board coordinate.
+fn get_coord(file: u8, rank: u8) -> owlchess::Coord {
+    owlchess::Coord::from_parts(
+        owlchess::File::from_index(file as usize),
+        owlchess::Rank::from_index(7 - rank as usize),
+    )
+}
+
+#[derive(Debug, Clone)]
+struct DndData {
+    start_file: u8,
+    start_rank: u8,
+    /// The piece comes from the palette (edit mode) : start_file and start_rank are meaningless.
+    from_palette: bool,
+    location: Point,
+    piece_type: owlchess::Piece,
+    piece_color: owlchess::Color,
+}
+
+#[derive(Debug, Clone)]
+struct PendingPromotion {
+    start_file: u8,
+    start_rank: u8,
+    end_file: u8,
+    end_rank: u8,
+    location: Point,
+    piece_color: owlchess::Color,
+    queen_button_bounds: Rectangle,
+    rook_button_bounds: Rectangle,
+    bishop_button_bounds: Rectangle,
+    knight_button_bounds: Rectangle,
+}
+
+#[derive(Debug, Clone)]
+#[allow(unused)]
+enum PromotionPiece {
+    Queen,
+    Rook,
+    Bishop,
+    Knight,
+}
+
+impl PromotionPiece {
+    fn to_piece(&self) -> owlchess::Piece {
+        match self {
+            PromotionPiece::Queen => owlchess::Piece::Queen,
+            PromotionPiece::Rook => owlchess::Piece::Rook,
+            PromotionPiece::Bishop => owlchess::Piece::Bishop,
+            PromotionPiece::Knight => owlchess::Piece::Knight,
+        }
+    }
+}
+
+/// The builders for the messages the chessboard
+/// component will produce.
+/// UPM generic stands for UpdatePositionMessage
+#[derive(Debug, Clone)]
+pub struct MessageProducer<UPM> {
+    pub build_update_position: fn(String) -> UPM,
+}
+
+/// A chessboard component
+/// UPM generic stands for UpdatePositionMessage
+pub struct Chessboard<UPM> {
+    colors: ChessboardColors,
+    position: Result<Position, ChessboardError>,
+    reversed: bool,
+    edit_mode: bool,
+    read_only: bool,
+    arrows: Vec<Arrow>,
+    highlighted_cells: Vec<owlchess::Coord>,
+    images: &'static PiecesImages,
+    dnd_data: Option<DndData>,
+    pending_promotion: Option<PendingPromotion>,
+    messages_producer: MessageProducer<UPM>,
+    /// Builds the message for the text pasted with Ctrl+V.
+    build_paste: Option<fn(String) -> UPM>,
+    /// Builds the message for the king dropped onto a rook of its side.
+    build_castling: Option<fn(owlchess::Coord, owlchess::Coord) -> UPM>,
+}
+
+impl<UPM> Chessboard<UPM> {
+    /// Builds the component.
+    ///
+    /// If the position in the options cannot be parsed (or is illegal outside of edit mode),
+    /// an error placeholder is shown instead of the board.
+    /// Use [`Chessboard::try_new`] in order to handle the error yourself.
+    pub fn new(options: ChessboardOptions, messages_producer: MessageProducer<UPM>) -> Self {
+        let position = Chessboard::<UPM>::parse_position(&options.fen, options.edit_mode);
+        Chessboard::with_position(options, position, messages_producer)
+    }
+
+    /// Builds the component, failing if the position in the options cannot be parsed
+    /// (or is illegal outside of edit mode).
+    pub fn try_new(
+        options: ChessboardOptions,
+        messages_producer: MessageProducer<UPM>,
+    ) -> Result<Self, ChessboardError> {
+        let position = Chessboard::<UPM>::parse_position(&options.fen, options.edit_mode)?;
+        Ok(Chessboard::with_position(
+            options,
+            Ok(position),
+            messages_producer,
+        ))
+    }
+
+    fn parse_position(fen: &str, edit_mode: bool) -> Result<Position, ChessboardError> {
+        if edit_mode {
+            Ok(Position::Setup(owlchess::RawBoard::from_fen(fen)?))
+        } else {
+            let raw_board = owlchess::RawBoard::from_fen(fen)?;
+            // owlchess panics on a missing black king instead of reporting it.
+            for color in [owlchess::Color::White, owlchess::Color::Black] {
+                let king = owlchess::Cell::from_parts(color, owlchess::Piece::King);
+                if !raw_board.cells.contains(&king) {
+                    return Err(ChessboardError::IllegalPosition(
+                        owlchess::board::ValidateError::NoKing(color),
+                    ));
+                }
+            }
+            let board =
+                owlchess::Board::try_from(&raw_board).map_err(ChessboardError::IllegalPosition)?;
+            let legal_moves = owlchess::movegen::legal::gen_all(&board).to_vec();
+            Ok(Position::Game { board, legal_moves })
+        }
+    }
+
+    fn with_position(
+        options: ChessboardOptions,
+        position: Result<Position, ChessboardError>,
+        messages_producer: MessageProducer<UPM>,
+    ) -> Self {
+        Chessboard {
+            colors: options.colors,
+            position,
+            reversed: options.reversed,
+            edit_mode: options.edit_mode,
+            read_only: options.read_only,
+            arrows: options.arrows,
+            highlighted_cells: options.highlighted_cells,
+            images: &PIECES_IMAGES,
+            dnd_data: None,
+            pending_promotion: None,
+            messages_producer,
+            build_paste: None,
+            build_castling: None,
+        }
+    }
+
+    /// Builds the message for the text pasted with Ctrl+V, which may be anything (FEN, PGN, ...).
+    /// Without it, the text is loaded as a FEN when it is valid, and ignored otherwise.
+    pub fn on_paste(mut self, build_paste: fn(String) -> UPM) -> Self {
+        self.build_paste = Some(build_paste);
+        self
+    }
+
+    /// Builds the message for the king dropped onto a rook of its side, which is castling in
+    /// Chess960, from the squares of the king and the rook.
+    /// Without it, such a drop is ignored as any other illegal move.
+    pub fn on_castling(
+        mut self,
+        build_castling: fn(owlchess::Coord, owlchess::Coord) -> UPM,
+    ) -> Self {
+        self.build_castling = Some(build_castling);
+        self
+    }
+
+    fn get_file_and_rank(&self, position: Point, bounds: Rectangle) -> (i8, i8) {
+        let common_size = bounds.size().width;
+        let cell_size = common_size / 9.0;
+        let half_cell_size = cell_size / 2.0;
+
+        let col = ((position.x - half_cell_size) / cell_size).floor() as i8;
+        let row = ((position.y - half_cell_size) / cell_size).floor() as i8;
+
+        let file = if self.reversed { 7 - col } else { col };
+        let rank = if self.reversed { row } else { 7 - row };
+
+        (file, rank)
+    }
+
+    fn in_cell_bounds(file: i8, rank: i8) -> bool {
+        (0..8).contains(&file) && (0..8).contains(&rank)
+    }
+
+    fn get_palette_cell_bounds(
+        bounds: Rectangle,
+        index: usize,
+        color: owlchess::Color,
+    ) -> Rectangle {
+        let cell_size = bounds.size().width / 9.0;
+        let row = match color {
+            owlchess::Color::White => 9.0,
+            owlchess::Color::Black => 10.0,
+        };
+        Rectangle {
+            x: bounds.x + cell_size * (1.5 + index as f32),
+            y: bounds.y + cell_size * row,
+            width: cell_size,
+            height: cell_size,
+        }
+    }
+
+    fn get_palette_piece(
+        cursor: mouse::Cursor,
+        bounds: Rectangle,
+    ) -> Option<(owlchess::Piece, owlchess::Color)> {
+        [owlchess::Color::White, owlchess::Color::Black]
+            .into_iter()
+            .flat_map(|color| {
+                PALETTE_PIECES
+                    .iter()
+                    .enumerate()
+                    .map(move |(index, piece)| (index, *piece, color))
+            })
+            .find(|(index, _, color)| {
+                cursor.is_over(Chessboard::<UPM>::get_palette_cell_bounds(
+                    bounds, *index, *color,
+                ))
+            })
+            .map(|(_, piece, color)| (piece, color))
+    }
+
+    fn commit_promotion(
+        &mut self,
+        piece: PromotionPiece,
+        shell: &mut iced::advanced::Shell<'_, UPM>,
+    ) {
+        if let Some(pending_promotion) = self.pending_promotion.clone()
+            && let Ok(position) = &self.position
+            && let Position::Game { board, .. } = position
+        {
+            let matching_legal_move = position.find_legal_move(
+                pending_promotion.start_file,
+                pending_promotion.start_rank,
+                pending_promotion.end_file,
+                pending_promotion.end_rank,
+                Some(piece),
+            );
+            if let Some(matching_legal_move) = matching_legal_move {
+                let resulting_board_logic = board.make_move(matching_legal_move);
+                if let Ok(resulting_board_logic) = resulting_board_logic {
+                    let new_fen = resulting_board_logic.as_fen();
+                    let update_message = (self.messages_producer.build_update_position)(new_fen);
+                    self.dnd_data = None;
+                    shell.publish(update_message);
+                }
+            }
+        }
+    }
+}
+
+impl<Message, Renderer> Widget<Message, Theme, Renderer> for Chessboard<Message>
+where
+    Renderer: iced::advanced::svg::Renderer
+        + iced::advanced::text::Renderer
+        + iced::advanced::graphics::geometry::Renderer,
+{
+    fn size(&self) -> Size<Length> {
+        Size {
+            width: Length::Shrink,
+            height: Length::Shrink,
+        }
+    }
+
+    fn layout(
+        &self,
+        _tree: &mut Tree,
+        _renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        let max_size = limits.max();
+        let max_width = max_size.width;
+        let max_height = max_size.height;
+
+        if self.edit_mode {
+            let common_size = max_width.min(max_height * 9.0 / EDIT_MODE_HEIGHT_IN_CELLS);
+            let height = common_size * EDIT_MODE_HEIGHT_IN_CELLS / 9.0;
+            layout::Node::new([common_size, height].into())
+        } else {
+            let common_size = max_width.min(max_height);
+            layout::Node::new([common_size, common_size].into())
+        }
+    }
+
+    fn draw(
+        &self,
+        _state: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        let allocated_bounds = layout.bounds();
+        let allocated_size = allocated_bounds.size();
+        let allocated_width = allocated_size.width;
+        let bounds = Rectangle {
+            width: allocated_width,
+            height: allocated_width,
+            x: allocated_bounds.x,
+            y: allocated_bounds.y,
+        };
+
+        self.draw_background(allocated_bounds, renderer);
+        let board_logic = match &self.position {
+            Ok(position) => position.raw(),
+            Err(err) => {
+                self.draw_error_placeholder(err, bounds, renderer, viewport);
+                return;
+            }
+        };
+        self.draw_cells(bounds, renderer);
+        self.draw_highlighted_cells(bounds, renderer);
+        self.draw_pieces(board_logic, bounds, renderer);
+        self.draw_coordinates(bounds, renderer, viewport);
+        self.draw_player_turn(board_logic, bounds, renderer);
+        self.draw_arrows(bounds, renderer);
+        if self.edit_mode {
+            self.draw_palette(bounds, renderer);
+        }
+        self.draw_dragged_piece(bounds, renderer);
+        self.draw_pending_promotion_piece(bounds, renderer);
+        self.draw_promotion_selector(board_logic, bounds, renderer);
+    }
+
+    fn on_event(
+        &mut self,
+        _state: &mut Tree,
+        event: iced::Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _renderer: &Renderer,
+        clipboard: &mut dyn iced::advanced::Clipboard,
+        shell: &mut iced::advanced::Shell<'_, Message>,
+        _viewport: &Rectangle,
+    ) -> iced::advanced::graphics::core::event::Status {
+        if self.read_only {
+            return event::Status::Ignored;
+        }
+        if let iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) = event {
+            return self.handle_key_pressed(key, modifiers, layout, cursor, clipboard, shell);
+        }
+        if self.position.is_err() {
+            return event::Status::Ignored;
+        }
+        if self.edit_mode {
+            return match event {
+                iced::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                    self.handle_editor_button_pressed(layout, cursor);
+                    event::Status::Captured
+                }
+                iced::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                    self.handle_editor_button_released(layout, cursor, shell);
+                    event::Status::Captured
+                }
+                iced::Event::Mouse(mouse::Event::CursorMoved { position: _ }) => {
+                    self.handle_editor_mouse_moved(layout, cursor);
+                    event::Status::Captured
+                }
+                _ => event::Status::Ignored,
+            };
+        }
+        match event {
+            iced::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                self.handle_button_pressed(event, layout, cursor, shell);
+                event::Status::Captured
+            }
+            iced::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                self.handle_button_released(event, layout, cursor, shell);
+                event::Status::Captured
+            }
+            iced::Event::Mouse(mouse::Event::CursorMoved { position: _ }) => {
+                self.handle_mouse_moved(event, layout, cursor);
+                event::Status::Captured
+            }
+            _ => event::Status::Ignored,
+        }
+    }
+}
+
+impl<'a, Message: 'a, Renderer> From<Chessboard<Message>> for Element<'a, Message, Theme, Renderer>
+where
+    Renderer: iced::advanced::svg::Renderer
+        + iced::advanced::text::Renderer
+        + iced::advanced::graphics::geometry::Renderer,
+{
+    fn from(widget: Chessboard<Message>) -> Self {
+        Self::new(widget)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn producer() -> MessageProducer<String> {
+        MessageProducer {
+            build_update_position: |fen| fen,
+        }
+    }
+
+    fn options(fen: &str, edit_mode: bool) -> ChessboardOptions {
+        ChessboardOptionsBuilder::new()
+            .set_position(fen.to_string())
+            .set_edit_mode(edit_mode)
+            .build()
+    }
+
+    /// A position without black king.
+    const ILLEGAL_FEN: &str = "8/8/8/8/8/8/8/K6R w - - 0 1";
+
+    #[test]
+    fn try_new_reports_the_positions_which_cannot_be_shown() {
+        assert!(matches!(
+            Chessboard::try_new(options("not a fen", false), producer()),
+            Err(ChessboardError::InvalidFen(_))
+        ));
+        assert!(matches!(
+            Chessboard::try_new(options(ILLEGAL_FEN, false), producer()),
+            Err(ChessboardError::IllegalPosition(_))
+        ));
+        // Any position can be set up in edit mode.
+        assert!(Chessboard::try_new(options(ILLEGAL_FEN, true), producer()).is_ok());
+        assert!(Chessboard::try_new(options("not a fen", true), producer()).is_err());
+    }
+
+    #[test]
+    fn new_keeps_the_error_for_the_placeholder() {
+        let chessboard = Chessboard::new(options(ILLEGAL_FEN, false), producer());
+        assert!(matches!(
+            chessboard.position,
+            Err(ChessboardError::IllegalPosition(_))
+        ));
+        let chessboard = Chessboard::new(
+            options(&owlchess::Board::initial().as_fen(), false),
+            producer(),
+        );
+        assert!(matches!(chessboard.position, Ok(Position::Game { .. })));
+    }
+
+    #[test]
+    fn legal_moves_are_computed_once_and_reused() {
+        let chessboard = Chessboard::new(
+            options(&owlchess::Board::initial().as_fen(), false),
+            producer(),
+        );
+        let Ok(position) = &chessboard.position else {
+            panic!("the initial position is legal");
+        };
+        let Position::Game { board, legal_moves } = position else {
+            panic!("the position is played");
+        };
+        assert_eq!(legal_moves.len(), 20);
+        // e2e4, from file 4 and rank 1 to rank 3.
+        let e2e4 = position.find_legal_move(4, 1, 4, 3, None).unwrap();
+        assert_eq!(e2e4.to_string(), "e2e4");
+        assert!(position.find_legal_move(4, 1, 4, 4, None).is_none());
+
+        // The moves are looked up in the cache, not generated again.
+        let without_moves = Position::Game {
+            board: board.clone(),
+            legal_moves: Vec::new(),
+        };
+        assert!(without_moves.find_legal_move(4, 1, 4, 3, None).is_none());
+    }
+
+    #[test]
+    fn promotions_need_a_piece() {
+        let fen = "8/4P3/8/8/8/8/k7/4K3 w - - 0 1";
+        let position = Chessboard::new(options(fen, false), producer())
+            .position
+            .unwrap();
+        assert!(position.find_legal_move(4, 6, 4, 7, None).is_none());
+        let promotion = position
+            .find_legal_move(4, 6, 4, 7, Some(PromotionPiece::Knight))
+            .unwrap();
+        assert_eq!(promotion.to_string(), "e7e8n");
+    }
+
+    #[test]
+    fn piece_images_are_shared() {
+        let first = Chessboard::new(ChessboardOptions::default(), producer());
+        let second = Chessboard::new(ChessboardOptions::default(), producer());
+        assert!(std::ptr::eq(first.images, second.images));
+    }
+
+    #[test]
+    fn pasted_text_is_loaded_as_fen_unless_a_message_is_given() {
+        let chessboard = Chessboard::new(ChessboardOptions::default(), producer());
+        assert!(chessboard.build_paste.is_none());
+        let chessboard = chessboard.on_paste(|text| format!("pasted {text}"));
+        let build_paste = chessboard.build_paste.unwrap();
+        assert_eq!(build_paste(String::from("1. e4")), "pasted 1. e4");
+    }
+
+    #[test]
+    fn the_king_dropped_onto_its_rook_is_castling() {
+        let fen = "1r4kr/8/8/8/8/8/8/1R4KR w - - 0 1";
+        let chessboard = Chessboard::new(options(fen, false), producer())
+            .on_castling(|king, rook| format!("{king}{rook}"));
+        let position = chessboard.position.as_ref().unwrap();
+        // From g1 onto b1 and h1, then onto the black rook of b8.
+        let (king, rook) = position.find_castling(6, 0, 1, 0).unwrap();
+        assert_eq!((chessboard.build_castling.unwrap())(king, rook), "g1b1");
+        assert_eq!(
+            position
+                .find_castling(6, 0, 7, 0)
+                .map(|(_, rook)| rook.to_string()),
+            Some(String::from("h1"))
+        );
+        assert!(position.find_castling(6, 0, 1, 7).is_none());
+        assert!(position.find_castling(1, 0, 6, 0).is_none());
+    }
+}
@@ -0,0 +1,96 @@
+//! Options for building a [`super::Chessboard`].
+use iced::Color;
+
+use crate::ChessboardColors;
+
+/// An arrow drawn between two cells, such as a move suggested by an engine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arrow {
+    pub from: owlchess::Coord,
+    pub to: owlchess::Coord,
+    pub color: Color,
+}
+
+/// Options for building a [`super::Chessboard`].
+#[derive(Debug, Clone)]
+pub struct ChessboardOptions {
+    pub colors: ChessboardColors,
+    pub fen: String,
+    pub reversed: bool,
+    pub edit_mode: bool,
+    pub read_only: bool,
+    pub arrows: Vec<Arrow>,
+    pub highlighted_cells: Vec<owlchess::Coord>,
+}
+
+impl Default for ChessboardOptions {
+    fn default() -> Self {
+        Self {
+            colors: ChessboardColors::default(),
+            fen: owlchess::Board::initial().as_fen(),
+            reversed: false,
+            edit_mode: false,
+            read_only: false,
+            arrows: Vec::new(),
+            highlighted_cells: Vec::new(),
+        }
+    }
+}
+
+/// Builds a [`ChessboardOptions`] and lets you override the options you need.
+#[derive(Default)]
+pub struct ChessboardOptionsBuilder {
+    options: ChessboardOptions,
+}
+
+impl ChessboardOptionsBuilder {
+    pub fn new() -> Self {
+        Self {
+            options: ChessboardOptions::default(),
+        }
+    }
+
+    pub fn set_colors(&mut self, colors: ChessboardColors) -> &mut Self {
+        self.options.colors = colors;
+        self
+    }
+
+    pub fn set_position(&mut self, fen: String) -> &mut Self {
+        self.options.fen = fen;
+        self
+    }
+
+    pub fn set_reversed(&mut self, reversed: bool) -> &mut Self {
+        self.options.reversed = reversed;
+        self
+    }
+
+    /// In edit mode, pieces can be moved freely, dropped from the palette
+    /// or dragged off the board, and the position does not need to be legal.
+    pub fn set_edit_mode(&mut self, edit_mode: bool) -> &mut Self {
+        self.options.edit_mode = edit_mode;
+        self
+    }
+
+    /// In read only mode, the board ignores the mouse and the keyboard, as when watching a game.
+    pub fn set_read_only(&mut self, read_only: bool) -> &mut Self {
+        self.options.read_only = read_only;
+        self
+    }
+
+    /// Arrows drawn over the pieces, in order.
+    pub fn set_arrows(&mut self, arrows: Vec<Arrow>) -> &mut Self {
+        self.options.arrows = arrows;
+        self
+    }
+
+    /// Cells drawn with the highlight color, under the pieces.
+    pub fn set_highlighted_cells(&mut self, cells: Vec<owlchess::Coord>) -> &mut Self {
+        self.options.highlighted_cells = cells;
+        self
+    }
+
+    pub fn build(&self) -> ChessboardOptions {
+        self.options.clone()
+    }
+}
@@ -1,5 +1,11 @@
+use std::sync::LazyLock;
+
 use iced::widget::svg::Handle;
 
+/// Shared by all the chessboards, so that the svg data is not hashed again
+/// each time a component is built.
+pub static PIECES_IMAGES: LazyLock<PiecesImages> = LazyLock::new(PiecesImages::new);
+
 pub struct PiecesImages {
     pub white_pawn_handle: Handle,
     pub white_knight_handle: Handle,